@@ -0,0 +1,107 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Authorizer Codec
+//
+
+use std::collections::HashMap;
+
+use crate::lambda::Context;
+
+pub const OP_HANDLE_AUTHORIZER_REQUEST: &str = "HandleAuthorizerRequest";
+
+/// Describes an API Gateway Lambda authorizer request received from AWS Lambda.
+/// `TOKEN` and `REQUEST` authorizers for both REST APIs and HTTP APIs are supported.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct AuthorizerRequest {
+    /// The authorizer type, `TOKEN` or `REQUEST`.
+    #[serde(default)]
+    pub authorizer_type: String,
+    /// The payload format version, `1.0` for REST APIs and HTTP API 1.0 payloads or `2.0` for HTTP API 2.0 payloads.
+    #[serde(default)]
+    pub version: String,
+    /// The ARN of the method (REST API) or route (HTTP API) being authorized.
+    #[serde(default)]
+    pub resource_arn: String,
+    /// The bearer token of a `TOKEN` authorizer request.
+    #[serde(default)]
+    pub authorization_token: String,
+    /// The identity source values of a `REQUEST` authorizer request.
+    #[serde(default)]
+    pub identity_source: Vec<String>,
+    /// The HTTP method of the request being authorized.
+    #[serde(default)]
+    pub method: String,
+    /// The path of the request being authorized.
+    #[serde(default)]
+    pub path: String,
+    /// The HTTP API route key, e.g. `GET /pets/{id}`, or the REST API resource, e.g. `/pets/{id}`.
+    #[serde(default)]
+    pub route_key: String,
+    /// The headers of a `REQUEST` authorizer request.
+    #[serde(default)]
+    pub header: HashMap<String, String>,
+    /// The query string of a `REQUEST` authorizer request.
+    #[serde(default)]
+    pub query_string: String,
+    /// The path parameters of a `REQUEST` authorizer request.
+    #[serde(default)]
+    pub path_parameters: HashMap<String, String>,
+    /// The stage variables of a `REQUEST` authorizer request.
+    #[serde(default)]
+    pub stage_variables: HashMap<String, String>,
+
+    /// The invocation context.
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// Describes an actor's response to an API Gateway Lambda authorizer request.
+/// The provider returns it to API Gateway as an IAM policy or, if configured for HTTP API 2.0 payloads, a simple response.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct AuthorizerResponse {
+    /// Whether or not the request is allowed.
+    pub allow: bool,
+    /// The principal identifier, e.g. the user ID. Not used in simple responses.
+    #[serde(default)]
+    pub principal_id: String,
+    /// The resource ARNs to which an IAM policy applies. If empty, the policy applies to the request's resource ARN.
+    /// Not used in simple responses.
+    #[serde(default)]
+    pub resources: Vec<String>,
+    /// Values passed to the integration in the request context's authorizer properties.
+    #[serde(default)]
+    pub context: HashMap<String, String>,
+}
+
+impl AuthorizerResponse {
+    /// Returns a response allowing the request for the specified principal.
+    pub fn allow(principal_id: &str) -> AuthorizerResponse {
+        AuthorizerResponse {
+            allow: true,
+            principal_id: principal_id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a response denying the request for the specified principal.
+    pub fn deny(principal_id: &str) -> AuthorizerResponse {
+        AuthorizerResponse {
+            allow: false,
+            principal_id: principal_id.into(),
+            ..Default::default()
+        }
+    }
+}
@@ -16,7 +16,11 @@
 // waSCC AWS Lambda Event Codec
 //
 
+use std::collections::HashMap;
+
 pub const OP_HANDLE_EVENT: &str = "HandleEvent";
+pub const OP_HANDLE_SHUTDOWN: &str = "HandleShutdown";
+pub const OP_STREAM_RESPONSE_CHUNK: &str = "StreamResponseChunk";
 
 /// Describes an event received from AWS Lambda.
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -25,6 +29,91 @@ pub struct Event {
     #[serde(with = "serde_bytes")]
     #[serde(default)]
     pub body: Vec<u8>,
+
+    /// The invocation context.
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// Describes the context of an AWS Lambda invocation.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Context {
+    /// The AWS request ID of the invocation.
+    #[serde(default)]
+    pub request_id: String,
+    /// The invocation deadline in milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub deadline_ms: u64,
+    /// The ARN of the function, version or alias that was invoked.
+    #[serde(default)]
+    pub invoked_function_arn: String,
+    /// The AWS X-Ray trace ID of the invocation.
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// The client context sent by the AWS Mobile SDK.
+    #[serde(default)]
+    pub client_context: Option<ClientContext>,
+    /// The Amazon Cognito identity that authorized the invocation.
+    #[serde(default)]
+    pub identity: Option<CognitoIdentity>,
+
+    /// The name of the function.
+    #[serde(default)]
+    pub function_name: String,
+    /// The version of the function.
+    #[serde(default)]
+    pub function_version: String,
+    /// The amount of memory available to the function in MB.
+    #[serde(default)]
+    pub memory_limit_in_mb: u32,
+    /// The name of the function's CloudWatch Logs group.
+    #[serde(default)]
+    pub log_group_name: String,
+    /// The name of the function's CloudWatch Logs stream.
+    #[serde(default)]
+    pub log_stream_name: String,
+
+    /// The source of the invocation event, e.g. `aws:sqs`. Empty if not known.
+    #[serde(default)]
+    pub event_source: String,
+}
+
+/// Describes the client context sent by the AWS Mobile SDK.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ClientContext {
+    /// Information about the client application.
+    #[serde(default)]
+    pub client: ClientApplication,
+    /// Custom values set by the client application.
+    #[serde(default)]
+    pub custom: HashMap<String, String>,
+    /// Environment information provided by the AWS Mobile SDK.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Describes the client application that invoked the function.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ClientApplication {
+    #[serde(default)]
+    pub installation_id: String,
+    #[serde(default)]
+    pub app_title: String,
+    #[serde(default)]
+    pub app_version_name: String,
+    #[serde(default)]
+    pub app_version_code: String,
+    #[serde(default)]
+    pub app_package_name: String,
+}
+
+/// Describes the Amazon Cognito identity that authorized the invocation.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct CognitoIdentity {
+    #[serde(default, alias = "cognitoIdentityId")]
+    pub cognito_identity_id: String,
+    #[serde(default, alias = "cognitoIdentityPoolId")]
+    pub cognito_identity_pool_id: String,
 }
 
 /// Describes a response to AWS Lambda.
@@ -34,6 +123,10 @@ pub struct Response {
     #[serde(with = "serde_bytes")]
     #[serde(default)]
     pub body: Vec<u8>,
+
+    /// Any error to report to AWS Lambda instead of the response body.
+    #[serde(default)]
+    pub error: Option<FunctionError>,
 }
 
 impl Response {
@@ -49,6 +142,39 @@ impl Response {
     {
         Ok(Response {
             body: serde_json::to_vec(t)?,
+            error: None,
+        })
+    }
+
+    /// Returns a response that reports an error of the specified type.
+    pub fn error(error_type: &str, error_message: &str) -> Response {
+        Response {
+            body: vec![],
+            error: Some(FunctionError {
+                error_type: error_type.into(),
+                error_message: error_message.into(),
+                data: vec![],
+            }),
+        }
+    }
+
+    /// Returns a response that reports an error of the specified type
+    /// with the JSON serialization of an object as additional data.
+    pub fn error_with_data<T>(
+        error_type: &str,
+        error_message: &str,
+        data: &T,
+    ) -> Result<Response, Box<dyn std::error::Error>>
+    where
+        T: serde::ser::Serialize + ?Sized,
+    {
+        Ok(Response {
+            body: vec![],
+            error: Some(FunctionError {
+                error_type: error_type.into(),
+                error_message: error_message.into(),
+                data: serde_json::to_vec(data)?,
+            }),
         })
     }
 }
@@ -57,10 +183,69 @@ impl Default for Response {
     /// Returns the default value for `Response`.
     /// The default Response is empty.
     fn default() -> Self {
-        Response { body: vec![] }
+        Response {
+            body: vec![],
+            error: None,
+        }
     }
 }
 
+/// Describes a chunk of a streamed response to AWS Lambda.
+/// An actor sends chunks to the provider while handling an event.
+/// The body of the `Response` the actor returns is sent as the last chunk.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct ResponseChunk {
+    /// The AWS request ID of the invocation being responded to.
+    pub request_id: String,
+
+    /// The raw bytes of the chunk.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub body: Vec<u8>,
+
+    /// The content type of the response.
+    /// Only the first chunk's content type is used.
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+impl ResponseChunk {
+    /// Returns a chunk of the response to the specified invocation.
+    pub fn new(request_id: &str, body: Vec<u8>) -> ResponseChunk {
+        ResponseChunk {
+            request_id: request_id.into(),
+            body,
+            content_type: None,
+        }
+    }
+}
+
+/// Describes the shutdown of the AWS Lambda execution environment.
+/// Actors receive this so that they can flush any buffered data.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Shutdown {
+    /// The reason for the shutdown, `sigterm` when the AWS Lambda machinery signals the runtime.
+    #[serde(default)]
+    pub reason: String,
+    /// The shutdown deadline in milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub deadline_ms: u64,
+}
+
+/// Describes an error returned by an actor to AWS Lambda.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct FunctionError {
+    /// The error type, for example `OrderNotFound`.
+    pub error_type: String,
+    /// The error message.
+    #[serde(default)]
+    pub error_message: String,
+    /// The raw JSON bytes of any additional error data.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub data: Vec<u8>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,4 +256,28 @@ mod tests {
         let result = Response::json(&i);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn error_with_data_response() {
+        let result = Response::error_with_data("OrderNotFound", "Order not found", &42);
+        assert!(result.is_ok());
+
+        let response = result.unwrap();
+        assert!(response.body.is_empty());
+        assert!(response.error.is_some());
+        let error = response.error.unwrap();
+        assert_eq!("OrderNotFound", error.error_type);
+        assert_eq!("Order not found", error.error_message);
+        assert_eq!(b"42".to_vec(), error.data);
+    }
+
+    #[test]
+    fn event_without_context() {
+        let result: Result<Event, _> = serde_json::from_str(r#"{"body":[123,125]}"#);
+        assert!(result.is_ok());
+
+        let event = result.unwrap();
+        assert_eq!(b"{}".to_vec(), event.body);
+        assert_eq!(Context::default(), event.context);
+    }
 }
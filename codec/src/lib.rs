@@ -19,5 +19,24 @@
 #[macro_use]
 extern crate serde_derive;
 
+mod authorizer;
 mod lambda;
-pub use lambda::{Event, Response, OP_HANDLE_EVENT};
+mod sqs;
+mod stream;
+mod telemetry;
+mod websocket;
+pub use authorizer::{AuthorizerRequest, AuthorizerResponse, OP_HANDLE_AUTHORIZER_REQUEST};
+pub use lambda::{
+    ClientApplication, ClientContext, CognitoIdentity, Context, Event, FunctionError, Response,
+    ResponseChunk, Shutdown, OP_HANDLE_EVENT, OP_HANDLE_SHUTDOWN, OP_STREAM_RESPONSE_CHUNK,
+};
+pub use sqs::{SqsMessage, SqsMessageAttribute, SqsMessageEvent, OP_HANDLE_SQS_MESSAGE};
+pub use stream::{
+    AttributeValue, DynamoDbRecord, DynamoDbRecordEvent, KinesisRecord, KinesisRecordEvent,
+    OP_HANDLE_DYNAMODB_RECORD, OP_HANDLE_KINESIS_RECORD,
+};
+pub use telemetry::{TelemetryBatch, TelemetryEvent, OP_HANDLE_TELEMETRY};
+pub use websocket::{
+    PostToConnectionRequest, WebSocketEvent, WebSocketResponse, OP_HANDLE_WEBSOCKET_EVENT,
+    OP_POST_TO_CONNECTION,
+};
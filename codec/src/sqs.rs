@@ -0,0 +1,81 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda SQS Codec
+//
+
+use std::collections::HashMap;
+
+use crate::lambda::Context;
+
+pub const OP_HANDLE_SQS_MESSAGE: &str = "HandleSqsMessage";
+
+/// Describes an Amazon SQS message received from AWS Lambda.
+/// The actor's `Response` reports whether or not the message was processed;
+/// a response with an error reports the message as a batch item failure.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SqsMessageEvent {
+    /// The message.
+    #[serde(default)]
+    pub message: SqsMessage,
+
+    /// The invocation context.
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// Describes an Amazon SQS message.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SqsMessage {
+    /// The message ID.
+    #[serde(default)]
+    pub message_id: String,
+    /// The receipt handle used to delete the message.
+    #[serde(default)]
+    pub receipt_handle: String,
+    /// The message body.
+    #[serde(default)]
+    pub body: String,
+    /// The MD5 digest of the message body.
+    #[serde(default)]
+    pub md5_of_body: String,
+    /// The system attributes, for example `ApproximateReceiveCount`.
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    /// The message attributes set by the sender.
+    #[serde(default)]
+    pub message_attributes: HashMap<String, SqsMessageAttribute>,
+    /// The ARN of the queue.
+    #[serde(default)]
+    pub event_source_arn: String,
+    /// The AWS Region of the queue.
+    #[serde(default)]
+    pub aws_region: String,
+}
+
+/// Describes an Amazon SQS message attribute.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct SqsMessageAttribute {
+    /// The attribute's data type, for example `String`, `Number` or `Binary`.
+    #[serde(default)]
+    pub data_type: String,
+    /// The value of a `String` or `Number` attribute.
+    #[serde(default)]
+    pub string_value: Option<String>,
+    /// The value of a `Binary` attribute.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub binary_value: Vec<u8>,
+}
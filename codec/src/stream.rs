@@ -0,0 +1,143 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Stream Codec
+//
+
+use std::collections::HashMap;
+
+use crate::lambda::Context;
+
+pub const OP_HANDLE_DYNAMODB_RECORD: &str = "HandleDynamoDbRecord";
+pub const OP_HANDLE_KINESIS_RECORD: &str = "HandleKinesisRecord";
+
+/// Describes an Amazon Kinesis Data Streams record received from AWS Lambda.
+/// The actor's `Response` reports whether or not the record was processed;
+/// records are dispatched in order and dispatch stops at the first error.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct KinesisRecordEvent {
+    /// The record.
+    #[serde(default)]
+    pub record: KinesisRecord,
+
+    /// The invocation context.
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// Describes an Amazon Kinesis Data Streams record.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct KinesisRecord {
+    /// The event ID, `shardId:sequenceNumber`.
+    #[serde(default)]
+    pub event_id: String,
+    /// The ARN of the stream.
+    #[serde(default)]
+    pub event_source_arn: String,
+    /// The AWS Region of the stream.
+    #[serde(default)]
+    pub aws_region: String,
+    /// The partition key.
+    #[serde(default)]
+    pub partition_key: String,
+    /// The sequence number of the record within its shard.
+    #[serde(default)]
+    pub sequence_number: String,
+    /// The approximate time the record was added to the stream, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub approximate_arrival_timestamp: f64,
+    /// The decoded record data.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub data: Vec<u8>,
+}
+
+/// Describes an Amazon DynamoDB Streams record received from AWS Lambda.
+/// The actor's `Response` reports whether or not the record was processed;
+/// records are dispatched in order and dispatch stops at the first error.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DynamoDbRecordEvent {
+    /// The record.
+    #[serde(default)]
+    pub record: DynamoDbRecord,
+
+    /// The invocation context.
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// Describes an Amazon DynamoDB Streams record.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct DynamoDbRecord {
+    /// The event ID.
+    #[serde(default)]
+    pub event_id: String,
+    /// The type of change, `INSERT`, `MODIFY` or `REMOVE`.
+    #[serde(default)]
+    pub event_name: String,
+    /// The ARN of the stream.
+    #[serde(default)]
+    pub event_source_arn: String,
+    /// The AWS Region of the table.
+    #[serde(default)]
+    pub aws_region: String,
+    /// The sequence number of the record within its shard.
+    #[serde(default)]
+    pub sequence_number: String,
+    /// The approximate time the change was made, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub approximate_creation_date_time: f64,
+    /// The size of the record in bytes.
+    #[serde(default)]
+    pub size_bytes: i64,
+    /// The information written to the stream, for example `NEW_AND_OLD_IMAGES`.
+    #[serde(default)]
+    pub stream_view_type: String,
+    /// The primary key attributes of the item.
+    #[serde(default)]
+    pub keys: HashMap<String, AttributeValue>,
+    /// The item after it was changed, if in the stream.
+    #[serde(default)]
+    pub new_image: HashMap<String, AttributeValue>,
+    /// The item before it was changed, if in the stream.
+    #[serde(default)]
+    pub old_image: HashMap<String, AttributeValue>,
+}
+
+/// Describes an Amazon DynamoDB attribute value.
+/// Binary values are decoded.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum AttributeValue {
+    /// A string.
+    S(String),
+    /// A number, as a string.
+    N(String),
+    /// A binary value.
+    B(#[serde(with = "serde_bytes")] Vec<u8>),
+    /// A string set.
+    SS(Vec<String>),
+    /// A number set.
+    NS(Vec<String>),
+    /// A binary set.
+    BS(Vec<serde_bytes::ByteBuf>),
+    /// A map of attributes.
+    M(HashMap<String, AttributeValue>),
+    /// A list of attributes.
+    L(Vec<AttributeValue>),
+    /// A null value.
+    NULL(bool),
+    /// A Boolean value.
+    BOOL(bool),
+}
@@ -0,0 +1,44 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Telemetry Codec
+//
+
+pub const OP_HANDLE_TELEMETRY: &str = "HandleTelemetry";
+
+/// Describes a batch of events received from the AWS Lambda Telemetry API.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TelemetryBatch {
+    /// The telemetry events, in the order they were sent.
+    #[serde(default)]
+    pub events: Vec<TelemetryEvent>,
+}
+
+/// Describes a single telemetry event.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct TelemetryEvent {
+    /// The time the event was generated, in ISO 8601 format.
+    #[serde(default)]
+    pub time: String,
+
+    /// The event type, for example `platform.start` or `function`.
+    #[serde(default)]
+    pub event_type: String,
+
+    /// The raw JSON bytes of the event record.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub record: Vec<u8>,
+}
@@ -0,0 +1,99 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda WebSocket Codec
+//
+
+use std::collections::HashMap;
+
+use crate::lambda::Context;
+
+pub const OP_HANDLE_WEBSOCKET_EVENT: &str = "HandleWebSocketEvent";
+pub const OP_POST_TO_CONNECTION: &str = "PostToConnection";
+
+/// Describes an API Gateway WebSocket API event received from AWS Lambda.
+/// For the `$connect` route, the status code of the actor's `WebSocketResponse`
+/// determines whether or not the connection is accepted.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct WebSocketEvent {
+    /// The route key, e.g. `$connect`, `$disconnect`, `$default` or a custom route's key.
+    #[serde(default)]
+    pub route_key: String,
+    /// The event type, `CONNECT`, `DISCONNECT` or `MESSAGE`.
+    #[serde(default)]
+    pub event_type: String,
+    /// The ID of the client connection.
+    #[serde(default)]
+    pub connection_id: String,
+    /// The domain name of the WebSocket API.
+    #[serde(default)]
+    pub domain_name: String,
+    /// The stage of the WebSocket API.
+    #[serde(default)]
+    pub stage: String,
+    /// The headers of the `$connect` handshake request.
+    #[serde(default)]
+    pub header: HashMap<String, String>,
+    /// The query string of the `$connect` handshake request.
+    #[serde(default)]
+    pub query_string: String,
+    /// The message received from the client.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub body: Vec<u8>,
+
+    /// The invocation context.
+    #[serde(default)]
+    pub context: Context,
+}
+
+/// Describes an actor's response to an API Gateway WebSocket API event.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct WebSocketResponse {
+    /// The status code. A `$connect` route response with a status code other than 2xx rejects the connection.
+    pub status_code: u32,
+    /// Any message to return to the client if the route has a route response.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub body: Vec<u8>,
+}
+
+impl WebSocketResponse {
+    /// Returns a successful, empty response.
+    pub fn ok() -> WebSocketResponse {
+        WebSocketResponse {
+            status_code: 200,
+            body: vec![],
+        }
+    }
+}
+
+/// Describes a message an actor posts to a WebSocket API client connection
+/// via the API Gateway management API.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct PostToConnectionRequest {
+    /// The domain name of the WebSocket API, as received in the `WebSocketEvent`.
+    #[serde(default)]
+    pub domain_name: String,
+    /// The stage of the WebSocket API, as received in the `WebSocketEvent`.
+    #[serde(default)]
+    pub stage: String,
+    /// The ID of the client connection.
+    pub connection_id: String,
+    /// The message to send.
+    #[serde(with = "serde_bytes")]
+    #[serde(default)]
+    pub data: Vec<u8>,
+}
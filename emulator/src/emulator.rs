@@ -0,0 +1,590 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// AWS Lambda Runtime API Emulator
+//
+
+use http1::{Reply, Request};
+
+use std::collections::VecDeque;
+use std::net::TcpListener;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const API_VERSION: &str = "/2018-06-01/runtime";
+const ERROR_TYPE_HEADER_NAME: &str = "Lambda-Runtime-Function-Error-Type";
+const ERROR_BODY_HEADER_NAME: &str = "Lambda-Runtime-Function-Error-Body";
+
+/// Represents the emulated function's settings.
+#[derive(Clone, Debug)]
+pub struct EmulatorSettings {
+    /// The function name.
+    pub function_name: String,
+    /// The invocation timeout.
+    pub timeout: Duration,
+}
+
+impl EmulatorSettings {
+    /// Returns the ARN of the emulated function.
+    pub fn function_arn(&self) -> String {
+        format!(
+            "arn:aws:lambda:us-east-1:000000000000:function:{}",
+            self.function_name
+        )
+    }
+}
+
+impl Default for EmulatorSettings {
+    /// Returns the default value for `EmulatorSettings`.
+    fn default() -> Self {
+        Self {
+            function_name: "emulator".into(),
+            timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+/// Describes why the emulator completed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Completion {
+    /// All events were handled.
+    Drained,
+    /// The runtime reported an initialization error.
+    InitializationError,
+    /// An invocation timed out.
+    Timeout,
+}
+
+/// Describes the outcome of an invocation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The runtime sent a response.
+    Response(Vec<u8>),
+    /// The runtime sent an error.
+    Error { error_type: String, body: Vec<u8> },
+    /// The runtime didn't respond before the deadline.
+    Timeout,
+}
+
+/// Records the result of an invocation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvocationResult {
+    pub request_id: String,
+    pub outcome: Outcome,
+}
+
+impl InvocationResult {
+    /// Returns whether or not the invocation failed.
+    pub fn is_error(&self) -> bool {
+        !matches!(self.outcome, Outcome::Response(_))
+    }
+
+    /// Returns the result as a JSON value suitable for reporting.
+    pub fn to_json(&self, timeout: Duration) -> serde_json::Value {
+        match &self.outcome {
+            Outcome::Response(body) => serde_json::json!({
+                "requestId": self.request_id,
+                "response": json_or_string(body),
+            }),
+            Outcome::Error { error_type, body } => serde_json::json!({
+                "requestId": self.request_id,
+                "errorType": error_type,
+                "error": json_or_string(body),
+            }),
+            Outcome::Timeout => serde_json::json!({
+                "requestId": self.request_id,
+                "errorType": "Sandbox.Timedout",
+                "error": {
+                    "errorMessage": format!("Task timed out after {:.2} seconds", timeout.as_secs_f64()),
+                },
+            }),
+        }
+    }
+}
+
+/// Returns the specified bytes as JSON, or as a string if they aren't valid JSON.
+fn json_or_string(body: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(body).into()))
+}
+
+/// Represents an invocation that is awaiting its response.
+struct InFlight {
+    request_id: String,
+}
+
+/// Represents the emulator's mutable state.
+struct State {
+    completion: Option<Completion>,
+    events: VecDeque<serde_json::Value>,
+    in_flight: Option<InFlight>,
+    next_request: u64,
+    results: Vec<InvocationResult>,
+}
+
+/// Emulates the AWS Lambda runtime API for a queue of events.
+#[derive(Clone)]
+pub struct Emulator {
+    settings: EmulatorSettings,
+    state: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl Emulator {
+    /// Creates a new `Emulator` that invokes the runtime with the specified events in order.
+    pub fn new(events: Vec<serde_json::Value>, settings: EmulatorSettings) -> Self {
+        Self {
+            settings,
+            state: Arc::new((
+                Mutex::new(State {
+                    completion: None,
+                    events: events.into(),
+                    in_flight: None,
+                    next_request: 1,
+                    results: vec![],
+                }),
+                Condvar::new(),
+            )),
+        }
+    }
+
+    /// Serves the runtime API on the specified listener.
+    /// Each connection is served on its own thread.
+    pub fn start(&self, listener: TcpListener) {
+        let emulator = self.clone();
+        thread::spawn(move || {
+            let result = http1::serve(listener, move |request| {
+                info!("{} {}", request.method, request.path);
+                emulator.handle(request)
+            });
+            if let Err(e) = result {
+                error!("{}", e);
+            }
+        });
+    }
+
+    /// Waits up to the specified time for the emulator to complete.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Completion> {
+        let (lock, cvar) = &*self.state;
+        let state = lock.lock().unwrap();
+        let (state, _) = cvar
+            .wait_timeout_while(state, timeout, |state| state.completion.is_none())
+            .unwrap();
+
+        state.completion
+    }
+
+    /// Returns the results of the completed invocations, in order.
+    pub fn results(&self) -> Vec<InvocationResult> {
+        self.state.0.lock().unwrap().results.clone()
+    }
+
+    /// Handles a runtime API request, returning the response status, headers and body.
+    /// A request for the next event when there are none completes the emulator and fails with a container error.
+    fn handle(&self, request: &Request) -> Reply {
+        let path = if request.path.starts_with(API_VERSION) {
+            &request.path[API_VERSION.len()..]
+        } else {
+            return not_found();
+        };
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["invocation", "next"]) => self.next_invocation(),
+            ("POST", ["invocation", request_id, "response"]) => {
+                match request.header(ERROR_TYPE_HEADER_NAME) {
+                    // A streamed response that failed reports its error in the trailers.
+                    Some(error_type) => {
+                        let body = request
+                            .header(ERROR_BODY_HEADER_NAME)
+                            .map(|body| base64::decode(body).unwrap_or_else(|_| body.into()))
+                            .unwrap_or_default();
+                        self.complete_invocation(
+                            request_id,
+                            Outcome::Error {
+                                error_type: error_type.into(),
+                                body,
+                            },
+                        )
+                    }
+                    None => self
+                        .complete_invocation(request_id, Outcome::Response(request.body.clone())),
+                }
+            }
+            ("POST", ["invocation", request_id, "error"]) => self.complete_invocation(
+                request_id,
+                Outcome::Error {
+                    error_type: request
+                        .header(ERROR_TYPE_HEADER_NAME)
+                        .unwrap_or("Unhandled")
+                        .into(),
+                    body: request.body.clone(),
+                },
+            ),
+            ("POST", ["init", "error"]) => {
+                error!(
+                    "Initialization error: {}",
+                    String::from_utf8_lossy(&request.body)
+                );
+                self.complete(Completion::InitializationError);
+                accepted()
+            }
+            _ => not_found(),
+        }
+    }
+
+    /// Returns the next event.
+    /// If there are none the emulator completes and the runtime is told to exit.
+    fn next_invocation(&self) -> Reply {
+        let (lock, cvar) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        let event = match state.events.pop_front() {
+            Some(event) => event,
+            None => {
+                // Tell the runtime to exit, as the AWS Lambda service does when the container is in an unrecoverable state.
+                state.completion.get_or_insert(Completion::Drained);
+                cvar.notify_all();
+                return (
+                    500,
+                    vec![],
+                    serde_json::json!({
+                        "errorMessage": "No more events",
+                        "errorType": "Runtime.NoMoreEvents",
+                    })
+                    .to_string()
+                    .into_bytes(),
+                );
+            }
+        };
+
+        let n = state.next_request;
+        state.next_request += 1;
+        let request_id = format!("00000000-0000-0000-0000-{:012x}", n);
+        state.in_flight = Some(InFlight {
+            request_id: request_id.clone(),
+        });
+        drop(state);
+
+        // Enforce the timeout.
+        let emulator = self.clone();
+        let timeout = self.settings.timeout;
+        let timed_out = request_id.clone();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            emulator.time_out(&timed_out);
+        });
+
+        let deadline_ms = SystemTime::now()
+            .checked_add(self.settings.timeout)
+            .and_then(|deadline| deadline.duration_since(UNIX_EPOCH).ok())
+            .map(|deadline| deadline.as_millis())
+            .unwrap_or_default();
+        let headers = vec![
+            ("Lambda-Runtime-Aws-Request-Id", request_id.clone()),
+            ("Lambda-Runtime-Deadline-Ms", deadline_ms.to_string()),
+            (
+                "Lambda-Runtime-Invoked-Function-Arn",
+                self.settings.function_arn(),
+            ),
+            (
+                "Lambda-Runtime-Trace-Id",
+                format!("Root=1-00000000-{:024x};Sampled=0", n),
+            ),
+            ("Content-Type", "application/json".into()),
+        ];
+
+        (200, headers, event.to_string().into_bytes())
+    }
+
+    /// Records the outcome of the in-flight invocation.
+    fn complete_invocation(&self, request_id: &str, outcome: Outcome) -> Reply {
+        let mut state = self.state.0.lock().unwrap();
+        match &state.in_flight {
+            Some(in_flight) if in_flight.request_id == request_id => {}
+            _ => {
+                return (
+                    400,
+                    vec![],
+                    serde_json::json!({
+                        "errorMessage": format!("Invalid request ID: {}", request_id),
+                        "errorType": "InvalidRequestID",
+                    })
+                    .to_string()
+                    .into_bytes(),
+                )
+            }
+        }
+
+        state.in_flight = None;
+        state.results.push(InvocationResult {
+            request_id: request_id.into(),
+            outcome,
+        });
+
+        accepted()
+    }
+
+    /// Records a timeout if the specified invocation is still in flight.
+    /// The AWS Lambda service would restart the runtime, so the emulator completes.
+    fn time_out(&self, request_id: &str) {
+        let mut state = self.state.0.lock().unwrap();
+        match &state.in_flight {
+            Some(in_flight) if in_flight.request_id == request_id => {}
+            _ => return,
+        }
+
+        warn!("Invocation {} timed out", request_id);
+        state.in_flight = None;
+        state.results.push(InvocationResult {
+            request_id: request_id.into(),
+            outcome: Outcome::Timeout,
+        });
+        drop(state);
+
+        self.complete(Completion::Timeout);
+    }
+
+    /// Completes the emulator.
+    fn complete(&self, completion: Completion) {
+        let (lock, cvar) = &*self.state;
+        lock.lock().unwrap().completion.get_or_insert(completion);
+        cvar.notify_all();
+    }
+}
+
+/// Returns an "Accepted" response.
+fn accepted() -> Reply {
+    (202, vec![], br#"{"status":"OK"}"#.to_vec())
+}
+
+/// Returns a "Not Found" response.
+fn not_found() -> Reply {
+    (404, vec![], vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    const EVENT: &str = r#"{"input":"Hello world"}"#;
+    const RESPONSE: &str = r#"{"output":"HELLO WORLD"}"#;
+
+    /// Starts an emulator with a single event and the specified timeout.
+    /// Returns the emulator and its endpoint.
+    fn start_emulator(timeout: Duration) -> (Emulator, String) {
+        let settings = EmulatorSettings {
+            timeout,
+            ..Default::default()
+        };
+        let emulator = Emulator::new(vec![serde_json::from_str(EVENT).unwrap()], settings);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}{}", listener.local_addr().unwrap(), API_VERSION);
+        emulator.start(listener);
+
+        (emulator, endpoint)
+    }
+
+    /// Gets the next invocation event, returning its request ID.
+    fn next_invocation(endpoint: &str) -> String {
+        let resp = reqwest::blocking::get(&format!("{}/invocation/next", endpoint)).unwrap();
+        assert_eq!(200, resp.status().as_u16());
+        let request_id = resp
+            .headers()
+            .get("Lambda-Runtime-Aws-Request-Id")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(resp.headers().contains_key("Lambda-Runtime-Deadline-Ms"));
+        assert_eq!(
+            "arn:aws:lambda:us-east-1:000000000000:function:emulator",
+            resp.headers()
+                .get("Lambda-Runtime-Invoked-Function-Arn")
+                .unwrap()
+        );
+        assert_eq!(EVENT, resp.text().unwrap());
+
+        request_id
+    }
+
+    /// Asks for the next invocation event, which drains the emulator.
+    fn drain(endpoint: &str) {
+        let resp = reqwest::blocking::get(&format!("{}/invocation/next", endpoint)).unwrap();
+        assert_eq!(500, resp.status().as_u16());
+    }
+
+    #[test]
+    fn emulator_response() {
+        let (emulator, endpoint) = start_emulator(Duration::from_secs(5));
+        let request_id = next_invocation(&endpoint);
+
+        let resp = reqwest::blocking::Client::new()
+            .post(&format!("{}/invocation/{}/response", endpoint, request_id))
+            .body(RESPONSE)
+            .send()
+            .unwrap();
+        assert_eq!(202, resp.status().as_u16());
+
+        drain(&endpoint);
+        assert_eq!(
+            Some(Completion::Drained),
+            emulator.wait_timeout(Duration::from_secs(5))
+        );
+        let results = emulator.results();
+        assert_eq!(
+            vec![InvocationResult {
+                request_id,
+                outcome: Outcome::Response(RESPONSE.into()),
+            }],
+            results
+        );
+        assert!(!results[0].is_error());
+        assert_eq!(
+            serde_json::json!({"output": "HELLO WORLD"}),
+            results[0].to_json(Duration::from_secs(5))["response"]
+        );
+    }
+
+    #[test]
+    fn emulator_error() {
+        let (emulator, endpoint) = start_emulator(Duration::from_secs(5));
+        let request_id = next_invocation(&endpoint);
+
+        let resp = reqwest::blocking::Client::new()
+            .post(&format!("{}/invocation/{}/error", endpoint, request_id))
+            .header(ERROR_TYPE_HEADER_NAME, "OrderNotFound")
+            .body(r#"{"errorMessage":"Order not found"}"#)
+            .send()
+            .unwrap();
+        assert_eq!(202, resp.status().as_u16());
+
+        drain(&endpoint);
+        assert_eq!(
+            Some(Completion::Drained),
+            emulator.wait_timeout(Duration::from_secs(5))
+        );
+        let results = emulator.results();
+        assert_eq!(1, results.len());
+        assert!(results[0].is_error());
+        match &results[0].outcome {
+            Outcome::Error { error_type, .. } => assert_eq!("OrderNotFound", error_type),
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn emulator_streamed_response_error() {
+        let (emulator, endpoint) = start_emulator(Duration::from_secs(5));
+        let request_id = next_invocation(&endpoint);
+
+        let addr = endpoint
+            .trim_start_matches("http://")
+            .trim_end_matches(API_VERSION);
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST {}/invocation/{}/response HTTP/1.1\r\n\
+             Transfer-Encoding: chunked\r\n\r\n\
+             5\r\nHello\r\n0\r\n\
+             {}: Oops\r\n{}: {}\r\n\r\n",
+            API_VERSION,
+            request_id,
+            ERROR_TYPE_HEADER_NAME,
+            ERROR_BODY_HEADER_NAME,
+            base64::encode(r#"{"errorMessage":"Oops"}"#)
+        )
+        .unwrap();
+        let mut buf = [0; 12];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(b"HTTP/1.1 202", &buf);
+
+        let results = emulator.results();
+        assert_eq!(
+            vec![InvocationResult {
+                request_id,
+                outcome: Outcome::Error {
+                    error_type: "Oops".into(),
+                    body: br#"{"errorMessage":"Oops"}"#.to_vec(),
+                },
+            }],
+            results
+        );
+    }
+
+    #[test]
+    fn emulator_invalid_request_id() {
+        let (emulator, endpoint) = start_emulator(Duration::from_secs(5));
+        next_invocation(&endpoint);
+
+        let resp = reqwest::blocking::Client::new()
+            .post(&format!("{}/invocation/{}/response", endpoint, "INVALID"))
+            .body(RESPONSE)
+            .send()
+            .unwrap();
+        assert_eq!(400, resp.status().as_u16());
+        assert!(emulator.results().is_empty());
+    }
+
+    #[test]
+    fn emulator_timeout() {
+        let (emulator, endpoint) = start_emulator(Duration::from_millis(100));
+        let request_id = next_invocation(&endpoint);
+
+        assert_eq!(
+            Some(Completion::Timeout),
+            emulator.wait_timeout(Duration::from_secs(5))
+        );
+
+        // Late responses are rejected.
+        let resp = reqwest::blocking::Client::new()
+            .post(&format!("{}/invocation/{}/response", endpoint, request_id))
+            .body(RESPONSE)
+            .send()
+            .unwrap();
+        assert_eq!(400, resp.status().as_u16());
+
+        let results = emulator.results();
+        assert_eq!(
+            vec![InvocationResult {
+                request_id,
+                outcome: Outcome::Timeout,
+            }],
+            results
+        );
+        assert_eq!(
+            "Sandbox.Timedout",
+            results[0].to_json(Duration::from_millis(100))["errorType"]
+        );
+    }
+
+    #[test]
+    fn emulator_initialization_error() {
+        let (emulator, endpoint) = start_emulator(Duration::from_secs(5));
+
+        let resp = reqwest::blocking::Client::new()
+            .post(&format!("{}/init/error", endpoint))
+            .body(r#"{"errorMessage":"Failed"}"#)
+            .send()
+            .unwrap();
+        assert_eq!(202, resp.status().as_u16());
+
+        assert_eq!(
+            Some(Completion::InitializationError),
+            emulator.wait_timeout(Duration::from_secs(5))
+        );
+    }
+}
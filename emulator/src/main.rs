@@ -0,0 +1,173 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// AWS Lambda Runtime API Emulator
+//
+
+#[macro_use]
+extern crate anyhow;
+
+use emulator::{Completion, Emulator, EmulatorSettings};
+use log::{debug, info};
+
+use std::io::Read;
+use std::net::TcpListener;
+use std::process::{Child, Command};
+use std::time::Duration;
+
+const USAGE: &str = "Usage: emulator [--port PORT] [--timeout SECONDS] [--function-name NAME] \
+                     [EVENT_FILE...] [-- COMMAND [ARG...]]";
+
+/// Represents the command line arguments.
+struct Args {
+    port: u16,
+    settings: EmulatorSettings,
+    event_files: Vec<String>,
+    command: Vec<String>,
+}
+
+/// Entry point.
+/// Invocation results are written to stdout as JSON lines.
+/// The process exits with a non-zero status if any invocation failed.
+fn main() -> anyhow::Result<()> {
+    if env_logger::builder().try_init().is_err() {
+        debug!("Logger already intialized");
+    }
+
+    let args = parse_args(std::env::args().skip(1))?;
+    let events = load_events(&args.event_files)?;
+    info!("Queued {} events", events.len());
+
+    let listener = TcpListener::bind(("127.0.0.1", args.port))?;
+    let runtime_api = listener.local_addr()?.to_string();
+    info!("Runtime API listening on {}", runtime_api);
+
+    let emulator = Emulator::new(events, args.settings.clone());
+    emulator.start(listener);
+
+    let mut child = match args.command.split_first() {
+        Some((program, program_args)) => Some(spawn_runtime(
+            program,
+            program_args,
+            &runtime_api,
+            &args.settings,
+        )?),
+        None => None,
+    };
+
+    let completion = loop {
+        if let Some(completion) = emulator.wait_timeout(Duration::from_millis(100)) {
+            break Some(completion);
+        }
+        if let Some(child) = child.as_mut() {
+            if let Some(status) = child.try_wait()? {
+                eprintln!("Runtime exited: {}", status);
+                // The runtime exits once the emulator is drained.
+                break emulator.wait_timeout(Duration::default());
+            }
+        }
+    };
+
+    if let Some(child) = child.as_mut() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    let results = emulator.results();
+    for result in &results {
+        println!("{}", result.to_json(args.settings.timeout));
+    }
+
+    let ok = completion == Some(Completion::Drained) && !results.iter().any(|r| r.is_error());
+    std::process::exit(if ok { 0 } else { 1 });
+}
+
+/// Parses the command line arguments.
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+    let mut parsed = Args {
+        port: 9001,
+        settings: EmulatorSettings::default(),
+        event_files: vec![],
+        command: vec![],
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => parsed.port = value(&mut args, &arg)?.parse()?,
+            "--timeout" => {
+                parsed.settings.timeout = Duration::from_secs(value(&mut args, &arg)?.parse()?)
+            }
+            "--function-name" => parsed.settings.function_name = value(&mut args, &arg)?,
+            "--" => {
+                parsed.command = args.collect();
+                break;
+            }
+            "-h" | "--help" => return Err(anyhow!("{}", USAGE)),
+            _ if arg.starts_with("--") => return Err(anyhow!("Unknown option {}\n{}", arg, USAGE)),
+            _ => parsed.event_files.push(arg),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Returns the value of the specified option.
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> anyhow::Result<String> {
+    args.next()
+        .ok_or_else(|| anyhow!("Missing value for {}\n{}", option, USAGE))
+}
+
+/// Loads events from the specified JSON files, one event per file.
+/// If there are no files, a sequence of JSON events is read from stdin.
+fn load_events(files: &[String]) -> anyhow::Result<Vec<serde_json::Value>> {
+    if files.is_empty() {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        return Ok(serde_json::Deserializer::from_str(&input)
+            .into_iter()
+            .collect::<Result<_, _>>()?);
+    }
+
+    files
+        .iter()
+        .map(|file| {
+            let contents = std::fs::read(file)?;
+            serde_json::from_slice(&contents).map_err(|e| anyhow!("{}: {}", file, e))
+        })
+        .collect()
+}
+
+/// Spawns the runtime with the environment AWS Lambda would provide.
+fn spawn_runtime(
+    program: &str,
+    args: &[String],
+    runtime_api: &str,
+    settings: &EmulatorSettings,
+) -> anyhow::Result<Child> {
+    info!("Starting runtime {}", program);
+
+    Ok(Command::new(program)
+        .args(args)
+        .env("AWS_LAMBDA_RUNTIME_API", runtime_api)
+        .env("AWS_LAMBDA_FUNCTION_NAME", &settings.function_name)
+        .env("AWS_LAMBDA_FUNCTION_VERSION", "$LATEST")
+        .env("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "128")
+        .env(
+            "AWS_LAMBDA_LOG_GROUP_NAME",
+            format!("/aws/lambda/{}", settings.function_name),
+        )
+        .env("AWS_LAMBDA_LOG_STREAM_NAME", "emulator")
+        .spawn()?)
+}
@@ -0,0 +1,346 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// Minimal HTTP/1.1
+//
+
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate log;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+/// Represents a response's status, headers and body.
+pub type Reply = (u16, Vec<(&'static str, String)>, Vec<u8>);
+
+/// Represents an HTTP request.
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns the value of the specified header or trailer.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+}
+
+/// Represents an HTTP response.
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Returns the value of the specified header or trailer.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+}
+
+/// Serves HTTP/1.1 on the specified listener until it fails.
+/// Each connection is served on its own thread and each request on it is answered by the specified handler.
+pub fn serve<H>(listener: TcpListener, handler: H) -> anyhow::Result<()>
+where
+    H: Fn(&Request) -> Reply + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let handler = Arc::clone(&handler);
+        thread::spawn(move || {
+            if let Err(e) = serve_connection(stream, &*handler) {
+                debug!("Connection closed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Serves the requests on a connection until it is closed.
+fn serve_connection<H>(stream: TcpStream, handler: &H) -> anyhow::Result<()>
+where
+    H: Fn(&Request) -> Reply,
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    while let Some(request) = read_request(&mut reader)? {
+        debug!("{} {}", request.method, request.path);
+        let (status, headers, body) = handler(&request);
+        write_response(&mut writer, status, &headers, &body)?;
+    }
+
+    Ok(())
+}
+
+/// Reads an HTTP/1.1 request.
+/// Chunked request bodies are decoded and any trailers are added to the headers.
+/// Returns `None` if the connection was closed.
+pub fn read_request<R: BufRead>(reader: &mut R) -> anyhow::Result<Option<Request>> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = read_headers(reader)?;
+    let body = read_body(reader, &mut headers, false)?;
+
+    Ok(Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    }))
+}
+
+/// Reads an HTTP/1.1 response.
+/// Chunked response bodies are decoded and any trailers are added to the headers.
+/// A response body with neither a length nor chunked encoding is read until the connection is closed.
+pub fn read_response<R: BufRead>(reader: &mut R) -> anyhow::Result<Response> {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow!("Invalid response status: {}", status_line.trim_end()))?;
+
+    let mut headers = read_headers(reader)?;
+    let body = read_body(reader, &mut headers, true)?;
+
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Reads a message body.
+/// Any trailers are added to the headers.
+fn read_body<R: BufRead>(
+    reader: &mut R,
+    headers: &mut Vec<(String, String)>,
+    until_closed: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let chunked = headers
+        .iter()
+        .any(|(k, v)| k.eq_ignore_ascii_case("transfer-encoding") && v.contains("chunked"));
+
+    let mut body = vec![];
+    if chunked {
+        loop {
+            let mut size_line = String::new();
+            reader.read_line(&mut size_line)?;
+            // Ignore any chunk extensions.
+            let size = size_line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| anyhow!("Invalid chunk size: {}", size_line.trim_end()))?;
+            if size == 0 {
+                break;
+            }
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk)?;
+            chunk.truncate(size);
+            body.append(&mut chunk);
+        }
+        headers.append(&mut read_headers(reader)?);
+    } else {
+        match header(headers, "content-length") {
+            Some(content_length) => {
+                body.resize(content_length.parse()?, 0);
+                reader.read_exact(&mut body)?;
+            }
+            None if until_closed => {
+                reader.read_to_end(&mut body)?;
+            }
+            None => {}
+        }
+    }
+
+    Ok(body)
+}
+
+/// Returns the value of the specified header.
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Reads HTTP headers up to the blank line that ends them.
+fn read_headers<R: BufRead>(reader: &mut R) -> anyhow::Result<Vec<(String, String)>> {
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(pos) = line.find(':') {
+            headers.push((line[..pos].trim().into(), line[pos + 1..].trim().into()));
+        }
+    }
+
+    Ok(headers)
+}
+
+/// Writes an HTTP/1.1 response.
+pub fn write_response<W: Write>(
+    writer: &mut W,
+    status: u16,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> anyhow::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason(status));
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(body)?;
+
+    Ok(())
+}
+
+/// Returns the reason phrase for the status codes used in this workspace.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn read_request_content_length() {
+        let mut input: &[u8] = b"POST /path HTTP/1.1\r\nContent-Length: 5\r\n\r\nHello";
+        let request = read_request(&mut input).unwrap().unwrap();
+        assert_eq!("POST", request.method);
+        assert_eq!("/path", request.path);
+        assert_eq!(Some("5"), request.header("content-length"));
+        assert_eq!(b"Hello".to_vec(), request.body);
+    }
+
+    #[test]
+    fn read_request_chunked_with_trailers() {
+        let mut input: &[u8] = b"POST /path HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nHello\r\n6;ext=1\r\n world\r\n0\r\nError-Type: Oops\r\n\r\n";
+        let request = read_request(&mut input).unwrap().unwrap();
+        assert_eq!(b"Hello world".to_vec(), request.body);
+        assert_eq!(Some("Oops"), request.header("error-type"));
+    }
+
+    #[test]
+    fn read_request_closed() {
+        let mut input: &[u8] = b"";
+        assert!(read_request(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_response_content_length() {
+        let mut input: &[u8] = b"HTTP/1.1 413 Payload Too Large\r\nContent-Length: 5\r\n\r\nHello";
+        let response = read_response(&mut input).unwrap();
+        assert_eq!(413, response.status);
+        assert_eq!(b"Hello".to_vec(), response.body);
+    }
+
+    #[test]
+    fn read_response_chunked() {
+        let mut input: &[u8] = b"HTTP/1.1 202 Accepted\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nHello\r\n0\r\n\r\n";
+        let response = read_response(&mut input).unwrap();
+        assert_eq!(202, response.status);
+        assert_eq!(b"Hello".to_vec(), response.body);
+    }
+
+    #[test]
+    fn read_response_until_closed() {
+        let mut input: &[u8] = b"HTTP/1.1 200 OK\r\n\r\nHello";
+        let response = read_response(&mut input).unwrap();
+        assert_eq!(200, response.status);
+        assert_eq!(b"Hello".to_vec(), response.body);
+    }
+
+    #[test]
+    fn read_response_invalid_status() {
+        let mut input: &[u8] = b"Hello\r\n\r\n";
+        assert!(read_response(&mut input).is_err());
+    }
+
+    #[test]
+    fn write_response_ok() {
+        let mut output = vec![];
+        write_response(&mut output, 202, &[("X-Test", "1".into())], b"{}").unwrap();
+        assert_eq!(
+            b"HTTP/1.1 202 Accepted\r\nX-Test: 1\r\nContent-Length: 2\r\n\r\n{}".to_vec(),
+            output
+        );
+    }
+
+    #[test]
+    fn serve_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            serve(listener, |request| {
+                (
+                    200,
+                    vec![("X-Path", request.path.clone())],
+                    request.body.clone(),
+                )
+            })
+        });
+
+        // Both requests are served on the same connection.
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"POST /one HTTP/1.1\r\nContent-Length: 3\r\n\r\nOne")
+            .unwrap();
+        stream
+            .write_all(b"POST /two HTTP/1.1\r\nContent-Length: 3\r\n\r\nTwo")
+            .unwrap();
+        let expected = b"HTTP/1.1 200 OK\r\nX-Path: /one\r\nContent-Length: 3\r\n\r\nOne\
+            HTTP/1.1 200 OK\r\nX-Path: /two\r\nContent-Length: 3\r\n\r\nTwo";
+        let mut responses = vec![0; expected.len()];
+        stream.read_exact(&mut responses).unwrap();
+        assert_eq!(expected.to_vec(), responses);
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::http::query_string;
+
+/// The payload format version of HTTP API authorizer requests that can use simple responses.
+const SIMPLE_RESPONSE_VERSION: &str = "2.0";
+
+/// The version of the IAM policy language.
+const POLICY_VERSION: &str = "2012-10-17";
+
+/// The IAM action allowing invocation of an API.
+const INVOKE_ACTION: &str = "execute-api:Invoke";
+
+/// Represents an API Gateway Lambda authorizer request in any of the
+/// REST API `TOKEN` and `REQUEST` or HTTP API 1.0 and 2.0 payload formats.
+/// https://docs.aws.amazon.com/apigateway/latest/developerguide/api-gateway-lambda-authorizer-input.html
+/// https://docs.aws.amazon.com/apigateway/latest/developerguide/http-api-lambda-authorizer.html
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuthorizerEvent {
+    #[serde(rename = "type")]
+    authorizer_type: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    method_arn: Option<String>,
+    #[serde(default)]
+    route_arn: Option<String>,
+    #[serde(default)]
+    authorization_token: Option<String>,
+    /// A comma-separated string in the HTTP API 1.0 payload format and an array in the 2.0 format.
+    #[serde(default)]
+    identity_source: Option<serde_json::Value>,
+    #[serde(default)]
+    http_method: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    raw_path: Option<String>,
+    #[serde(default)]
+    resource: Option<String>,
+    #[serde(default)]
+    route_key: Option<String>,
+    #[serde(default)]
+    headers: Option<HashMap<String, String>>,
+    #[serde(default)]
+    query_string_parameters: Option<HashMap<String, String>>,
+    #[serde(default)]
+    multi_value_query_string_parameters: Option<HashMap<String, Vec<String>>>,
+    #[serde(default)]
+    raw_query_string: Option<String>,
+    #[serde(default)]
+    path_parameters: Option<HashMap<String, String>>,
+    #[serde(default)]
+    stage_variables: Option<HashMap<String, String>>,
+    #[serde(default)]
+    request_context: Option<serde_json::Value>,
+}
+
+impl AuthorizerEvent {
+    /// Returns the ARN of the method or route being authorized.
+    pub fn resource_arn(&self) -> String {
+        self.method_arn
+            .as_ref()
+            .or(self.route_arn.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns whether or not the request uses the HTTP API 2.0 payload format, which allows simple responses.
+    pub fn is_simple_response_format(&self) -> bool {
+        self.version.as_deref() == Some(SIMPLE_RESPONSE_VERSION)
+    }
+
+    /// Converts the event to an actor's authorizer request with the specified invocation context.
+    pub fn into_request(self, context: codec::Context) -> codec::AuthorizerRequest {
+        let resource_arn = self.resource_arn();
+        let identity_source = match self.identity_source {
+            Some(serde_json::Value::String(s)) => s
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            Some(serde_json::Value::Array(values)) => values
+                .into_iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => vec![],
+        };
+        let request_context = self.request_context;
+        let method = self.http_method.or_else(|| {
+            request_context
+                .as_ref()
+                .and_then(|c| c.pointer("/http/method"))
+                .and_then(|m| m.as_str())
+                .map(String::from)
+        });
+        let query_string = match self.raw_query_string {
+            Some(query_string) => query_string,
+            None => query_string(
+                self.query_string_parameters.unwrap_or_default(),
+                self.multi_value_query_string_parameters.unwrap_or_default(),
+            ),
+        };
+
+        codec::AuthorizerRequest {
+            authorizer_type: self.authorizer_type,
+            version: self.version.unwrap_or_else(|| "1.0".into()),
+            resource_arn,
+            authorization_token: self.authorization_token.unwrap_or_default(),
+            identity_source,
+            method: method.unwrap_or_default(),
+            path: self.raw_path.or(self.path).unwrap_or_default(),
+            route_key: self.route_key.or(self.resource).unwrap_or_default(),
+            header: self.headers.unwrap_or_default(),
+            query_string,
+            path_parameters: self.path_parameters.unwrap_or_default(),
+            stage_variables: self.stage_variables.unwrap_or_default(),
+            context,
+        }
+    }
+}
+
+/// Represents a Lambda authorizer's response to API Gateway.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(crate) enum AuthorizerResult {
+    Policy(PolicyResponse),
+    Simple(SimpleResponse),
+}
+
+impl AuthorizerResult {
+    /// Returns the response to API Gateway for an actor's authorizer response.
+    /// A simple response is returned only if enabled and the request uses the HTTP API 2.0 payload format.
+    pub fn new(response: codec::AuthorizerResponse, resource_arn: String, simple: bool) -> Self {
+        if simple {
+            return AuthorizerResult::Simple(SimpleResponse {
+                is_authorized: response.allow,
+                context: response.context,
+            });
+        }
+
+        let resources = if response.resources.is_empty() {
+            vec![resource_arn]
+        } else {
+            response.resources
+        };
+        AuthorizerResult::Policy(PolicyResponse {
+            principal_id: response.principal_id,
+            policy_document: PolicyDocument {
+                version: POLICY_VERSION,
+                statement: vec![PolicyStatement {
+                    action: INVOKE_ACTION,
+                    effect: if response.allow { "Allow" } else { "Deny" },
+                    resource: resources,
+                }],
+            },
+            context: response.context,
+        })
+    }
+}
+
+/// Represents an IAM policy authorizer response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PolicyResponse {
+    principal_id: String,
+    policy_document: PolicyDocument,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    context: HashMap<String, String>,
+}
+
+/// Represents an IAM policy document.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PolicyDocument {
+    version: &'static str,
+    statement: Vec<PolicyStatement>,
+}
+
+/// Represents an IAM policy statement.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PolicyStatement {
+    action: &'static str,
+    effect: &'static str,
+    resource: Vec<String>,
+}
+
+/// Represents an HTTP API simple authorizer response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SimpleResponse {
+    is_authorized: bool,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    context: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_common::*;
+
+    #[test]
+    fn authorizer_event_token() {
+        let event: AuthorizerEvent =
+            serde_json::from_value(valid_token_authorizer_request()).unwrap();
+        assert!(!event.is_simple_response_format());
+
+        let request = event.into_request(codec::Context::default());
+        assert_eq!("TOKEN", request.authorizer_type);
+        assert_eq!("1.0", request.version);
+        assert_eq!("Bearer abc", request.authorization_token);
+        assert_eq!(
+            "arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/pets",
+            request.resource_arn
+        );
+        assert!(request.identity_source.is_empty());
+    }
+
+    #[test]
+    fn authorizer_event_request_v1() {
+        let event: AuthorizerEvent = serde_json::from_value(serde_json::json!({
+            "type": "REQUEST",
+            "methodArn": "arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/pets/42",
+            "resource": "/pets/{id}",
+            "path": "/pets/42",
+            "httpMethod": "GET",
+            "headers": {"authorization": "Bearer abc"},
+            "queryStringParameters": null,
+            "multiValueQueryStringParameters": {"tag": ["a", "b"]},
+            "pathParameters": {"id": "42"},
+            "stageVariables": null,
+            "requestContext": {"stage": "prod"},
+        }))
+        .unwrap();
+
+        let request = event.into_request(codec::Context::default());
+        assert_eq!("REQUEST", request.authorizer_type);
+        assert_eq!("GET", request.method);
+        assert_eq!("/pets/42", request.path);
+        assert_eq!("/pets/{id}", request.route_key);
+        assert_eq!("tag=a&tag=b", request.query_string);
+        assert_eq!("42", request.path_parameters.get("id").unwrap());
+        assert_eq!("Bearer abc", request.header.get("authorization").unwrap());
+    }
+
+    #[test]
+    fn authorizer_event_request_v2() {
+        let event: AuthorizerEvent =
+            serde_json::from_value(valid_http_api_authorizer_request()).unwrap();
+        assert!(event.is_simple_response_format());
+
+        let request = event.into_request(codec::Context::default());
+        assert_eq!("2.0", request.version);
+        assert_eq!("POST", request.method);
+        assert_eq!("/pets", request.path);
+        assert_eq!("POST /pets", request.route_key);
+        assert_eq!("a=1", request.query_string);
+        assert_eq!(vec!["Bearer abc".to_string()], request.identity_source);
+        assert_eq!(
+            "arn:aws:execute-api:us-east-1:123456789012:abcdef123/$default/POST/pets",
+            request.resource_arn
+        );
+    }
+
+    #[test]
+    fn authorizer_result_policy() {
+        let mut response = codec::AuthorizerResponse::allow("user");
+        response.context.insert("tenant".into(), "acme".into());
+
+        let result = AuthorizerResult::new(response, "arn:method".into(), false);
+        assert_eq!(
+            serde_json::json!({
+                "principalId": "user",
+                "policyDocument": {
+                    "Version": "2012-10-17",
+                    "Statement": [{
+                        "Action": "execute-api:Invoke",
+                        "Effect": "Allow",
+                        "Resource": ["arn:method"],
+                    }],
+                },
+                "context": {"tenant": "acme"},
+            }),
+            serde_json::to_value(result).unwrap()
+        );
+
+        let mut response = codec::AuthorizerResponse::deny("user");
+        response.resources = vec!["arn:a".into(), "arn:b".into()];
+        let result =
+            serde_json::to_value(AuthorizerResult::new(response, "arn:method".into(), false))
+                .unwrap();
+        assert_eq!("Deny", result["policyDocument"]["Statement"][0]["Effect"]);
+        assert_eq!(
+            serde_json::json!(["arn:a", "arn:b"]),
+            result["policyDocument"]["Statement"][0]["Resource"]
+        );
+        assert!(result.get("context").is_none());
+    }
+
+    #[test]
+    fn authorizer_result_simple() {
+        let response = codec::AuthorizerResponse::deny("user");
+
+        let result = AuthorizerResult::new(response, "arn:route".into(), true);
+        assert_eq!(
+            serde_json::json!({"isAuthorized": false}),
+            serde_json::to_value(result).unwrap()
+        );
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use serde::Serialize;
+
+/// Represents a partial batch response.
+/// Only the failed items of a batch are retried.
+/// https://docs.aws.amazon.com/lambda/latest/dg/with-sqs.html#services-sqs-batchfailurereporting
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BatchResponse {
+    batch_item_failures: Vec<BatchItemFailure>,
+}
+
+impl BatchResponse {
+    /// Records the failure of the item with the specified identifier.
+    pub fn add_failure(&mut self, item_identifier: &str) {
+        self.batch_item_failures.push(BatchItemFailure {
+            item_identifier: item_identifier.into(),
+        });
+    }
+
+    /// Returns the identifiers of the failed items.
+    pub fn failures(&self) -> Vec<&str> {
+        self.batch_item_failures
+            .iter()
+            .map(|f| f.item_identifier.as_str())
+            .collect()
+    }
+}
+
+/// Identifies a failed item of a batch.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchItemFailure {
+    item_identifier: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_response_json() {
+        let mut response = BatchResponse::default();
+        assert_eq!(
+            serde_json::json!({"batchItemFailures": []}),
+            serde_json::to_value(&response).unwrap()
+        );
+
+        response.add_failure("ID");
+        assert_eq!(vec!["ID"], response.failures());
+        assert_eq!(
+            serde_json::json!({"batchItemFailures": [{"itemIdentifier": "ID"}]}),
+            serde_json::to_value(&response).unwrap()
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use std::fmt;
+
+/// The source of a raw Lambda invocation event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum EventSource {
+    /// Amazon SQS messages.
+    Sqs,
+    /// Amazon SNS notifications.
+    Sns,
+    /// Amazon S3 event notifications.
+    S3,
+    /// Amazon DynamoDB Streams records.
+    DynamoDb,
+    /// Amazon Kinesis Data Streams records.
+    Kinesis,
+    /// Amazon EventBridge events.
+    EventBridge,
+    /// Amazon EventBridge scheduled events.
+    Scheduled,
+    /// Application Load Balancer requests.
+    Alb,
+    /// API Gateway REST API (v1 payload format) requests.
+    ApiGatewayV1,
+    /// API Gateway HTTP API (v2 payload format) requests.
+    ApiGatewayV2,
+    /// API Gateway WebSocket API events.
+    WebSocket,
+    /// Lambda function URL requests.
+    FunctionUrl,
+    /// API Gateway Lambda authorizer requests.
+    Authorizer,
+    /// Any other event.
+    Unknown,
+}
+
+impl EventSource {
+    /// Classifies a parsed Lambda invocation event by its discriminating keys.
+    pub fn classify_value(event: &serde_json::Value) -> Self {
+        // Record-based events.
+        if let Some(record) = event.get("Records").and_then(|r| r.get(0)) {
+            let source = record
+                .get("eventSource")
+                .or_else(|| record.get("EventSource"))
+                .and_then(|s| s.as_str());
+            return match source {
+                Some("aws:sqs") => EventSource::Sqs,
+                Some("aws:sns") => EventSource::Sns,
+                Some("aws:s3") => EventSource::S3,
+                Some("aws:dynamodb") => EventSource::DynamoDb,
+                Some("aws:kinesis") => EventSource::Kinesis,
+                _ => EventSource::Unknown,
+            };
+        }
+
+        if let Some(detail_type) = event.get("detail-type").and_then(|d| d.as_str()) {
+            if detail_type == "Scheduled Event"
+                && event.get("source").and_then(|s| s.as_str()) == Some("aws.events")
+            {
+                return EventSource::Scheduled;
+            }
+            return EventSource::EventBridge;
+        }
+
+        // Authorizer requests can otherwise look like proxy requests.
+        if let Some(authorizer_type) = event.get("type").and_then(|t| t.as_str()) {
+            if (authorizer_type == "TOKEN" || authorizer_type == "REQUEST")
+                && (event.get("methodArn").is_some() || event.get("routeArn").is_some())
+            {
+                return EventSource::Authorizer;
+            }
+        }
+
+        if let Some(request_context) = event.get("requestContext") {
+            if request_context.get("elb").is_some() {
+                return EventSource::Alb;
+            }
+            if request_context.get("connectionId").is_some() {
+                return EventSource::WebSocket;
+            }
+            if event.get("version").and_then(|v| v.as_str()) == Some("2.0")
+                || request_context.get("http").is_some()
+            {
+                let domain_name = request_context
+                    .get("domainName")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or_default();
+                if domain_name.contains(".lambda-url.") {
+                    return EventSource::FunctionUrl;
+                }
+                return EventSource::ApiGatewayV2;
+            }
+            if event.get("httpMethod").is_some() {
+                return EventSource::ApiGatewayV1;
+            }
+        }
+
+        EventSource::Unknown
+    }
+
+    /// Returns the name of the event source, e.g. `aws:sqs`.
+    pub fn name(self) -> &'static str {
+        match self {
+            EventSource::Sqs => "aws:sqs",
+            EventSource::Sns => "aws:sns",
+            EventSource::S3 => "aws:s3",
+            EventSource::DynamoDb => "aws:dynamodb",
+            EventSource::Kinesis => "aws:kinesis",
+            EventSource::EventBridge => "aws:events",
+            EventSource::Scheduled => "aws:scheduled",
+            EventSource::Alb => "aws:elb",
+            EventSource::ApiGatewayV1 => "aws:apigateway:v1",
+            EventSource::ApiGatewayV2 => "aws:apigateway:v2",
+            EventSource::WebSocket => "aws:apigateway:websocket",
+            EventSource::FunctionUrl => "aws:lambda-url",
+            EventSource::Authorizer => "aws:apigateway:authorizer",
+            EventSource::Unknown => "",
+        }
+    }
+
+    /// Returns the name of the more general event source this one belongs to, if any.
+    pub fn family(self) -> Option<&'static str> {
+        match self {
+            EventSource::Scheduled => Some(EventSource::EventBridge.name()),
+            EventSource::ApiGatewayV1
+            | EventSource::ApiGatewayV2
+            | EventSource::WebSocket
+            | EventSource::Authorizer => Some("aws:apigateway"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EventSource {
+    /// Formats the event source's name.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventSource::Unknown => write!(f, "unknown"),
+            source => write!(f, "{}", source.name()),
+        }
+    }
+}
+
+/// Represents a raw Lambda invocation event that has been parsed once and classified.
+#[derive(Debug)]
+pub(crate) struct ClassifiedEvent {
+    body: Vec<u8>,
+    source: EventSource,
+    value: Option<serde_json::Value>,
+}
+
+impl ClassifiedEvent {
+    /// Parses and classifies a raw Lambda invocation event.
+    /// An event that isn't JSON is classified as unknown.
+    pub fn new(body: Vec<u8>) -> Self {
+        let value: Option<serde_json::Value> = serde_json::from_slice(&body).ok();
+        let source = value
+            .as_ref()
+            .map(EventSource::classify_value)
+            .unwrap_or(EventSource::Unknown);
+
+        Self {
+            body,
+            source,
+            value,
+        }
+    }
+
+    /// Returns the raw event.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the event's source.
+    pub fn source(&self) -> EventSource {
+        self.source
+    }
+
+    /// Returns the event's source and the parsed event, or `None` if the event isn't JSON.
+    pub fn into_parts(self) -> (EventSource, Option<serde_json::Value>) {
+        (self.source, self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests_common::*;
+
+    /// Returns the classification of the specified JSON event.
+    fn classify(event: serde_json::Value) -> EventSource {
+        ClassifiedEvent::new(event.to_string().into_bytes()).source()
+    }
+
+    #[test]
+    fn classify_records() {
+        let records = |key: &str, source: &str| {
+            let mut record = serde_json::Map::new();
+            record.insert(key.into(), source.into());
+            serde_json::json!({ "Records": [record] })
+        };
+
+        assert_eq!(
+            EventSource::Sqs,
+            classify(records("eventSource", "aws:sqs"))
+        );
+        assert_eq!(
+            EventSource::Sns,
+            classify(records("EventSource", "aws:sns"))
+        );
+        assert_eq!(EventSource::S3, classify(records("eventSource", "aws:s3")));
+        assert_eq!(
+            EventSource::DynamoDb,
+            classify(records("eventSource", "aws:dynamodb"))
+        );
+        assert_eq!(
+            EventSource::Kinesis,
+            classify(records("eventSource", "aws:kinesis"))
+        );
+        assert_eq!(
+            EventSource::Unknown,
+            classify(records("eventSource", "aws:unknown"))
+        );
+    }
+
+    #[test]
+    fn classify_eventbridge() {
+        assert_eq!(
+            EventSource::Scheduled,
+            classify(serde_json::json!({
+                "source": "aws.events",
+                "detail-type": "Scheduled Event",
+                "detail": {},
+            }))
+        );
+        assert_eq!(
+            EventSource::EventBridge,
+            classify(serde_json::json!({
+                "source": "com.example.orders",
+                "detail-type": "Order Placed",
+                "detail": {},
+            }))
+        );
+    }
+
+    #[test]
+    fn classify_http() {
+        let alb = serde_json::to_value(valid_alb_target_group_request()).unwrap();
+        assert_eq!(EventSource::Alb, classify(alb));
+        let v1 = serde_json::to_value(valid_api_gateway_proxy_request()).unwrap();
+        assert_eq!(EventSource::ApiGatewayV1, classify(v1));
+        let mut v2 = serde_json::to_value(valid_api_gatewayv2_proxy_request()).unwrap();
+        assert_eq!(EventSource::ApiGatewayV2, classify(v2.clone()));
+        v2["requestContext"]["domainName"] = "abcdefg.lambda-url.us-east-1.on.aws".into();
+        assert_eq!(EventSource::FunctionUrl, classify(v2));
+        let websocket = valid_websocket_request("$connect", "CONNECT");
+        assert_eq!(EventSource::WebSocket, classify(websocket));
+    }
+
+    #[test]
+    fn classify_authorizer() {
+        assert_eq!(
+            EventSource::Authorizer,
+            classify(valid_token_authorizer_request())
+        );
+        assert_eq!(
+            EventSource::Authorizer,
+            classify(valid_http_api_authorizer_request())
+        );
+        let mut v1 = serde_json::to_value(valid_api_gateway_proxy_request()).unwrap();
+        v1["type"] = "REQUEST".into();
+        assert_eq!(EventSource::ApiGatewayV1, classify(v1.clone()));
+        v1["methodArn"] = "arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/".into();
+        assert_eq!(EventSource::Authorizer, classify(v1));
+    }
+
+    #[test]
+    fn classify_unknown() {
+        assert_eq!(
+            EventSource::Unknown,
+            ClassifiedEvent::new(EVENT_BODY.to_vec()).source()
+        );
+        assert_eq!(
+            EventSource::Unknown,
+            classify(serde_json::json!({"input": "Hello world"}))
+        );
+    }
+
+    #[test]
+    fn event_source_family() {
+        assert_eq!(Some("aws:events"), EventSource::Scheduled.family());
+        assert_eq!(Some("aws:apigateway"), EventSource::ApiGatewayV2.family());
+        assert_eq!(None, EventSource::Sqs.family());
+        assert_eq!("unknown", EventSource::Unknown.to_string());
+    }
+}
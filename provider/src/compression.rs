@@ -0,0 +1,167 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use std::io::Write;
+
+/// The buffer size used when compressing with brotli.
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// The brotli quality, from 0 to 11. Lower qualities are much faster for on-the-fly compression.
+const BROTLI_QUALITY: u32 = 5;
+
+/// The base 2 logarithm of the brotli window size.
+const BROTLI_LG_WINDOW_SIZE: u32 = 22;
+
+/// Represents a content encoding used to compress an HTTP response body.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ContentEncoding {
+    Brotli,
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// Returns the content encoding preferred by a client, given its `Accept-Encoding` request header.
+    /// Brotli is chosen over gzip when both are equally acceptable.
+    pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let mut brotli = None;
+        let mut gzip = None;
+        let mut any = None;
+        // The values of repeated headers are newline-separated.
+        for coding in accept_encoding.split(&[',', '\n'][..]) {
+            let mut parts = coding.split(';');
+            let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .map(str::trim)
+                .filter(|p| p.starts_with("q="))
+                .filter_map(|p| p[2..].trim().parse::<f32>().ok())
+                .next()
+                .unwrap_or(1.0);
+            match name.as_str() {
+                "br" => brotli = Some(quality),
+                "gzip" | "x-gzip" => gzip = Some(quality),
+                "*" => any = Some(quality),
+                _ => {}
+            }
+        }
+
+        let brotli = brotli.or(any).unwrap_or(0.0);
+        let gzip = gzip.or(any).unwrap_or(0.0);
+        if brotli > 0.0 && brotli >= gzip {
+            Some(ContentEncoding::Brotli)
+        } else if gzip > 0.0 {
+            Some(ContentEncoding::Gzip)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the encoding's `Content-Encoding` response header value.
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Gzip => "gzip",
+        }
+    }
+
+    /// Compresses a body using the encoding.
+    pub fn compress(self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentEncoding::Brotli => {
+                let mut writer = brotli::CompressorWriter::new(
+                    Vec::new(),
+                    BROTLI_BUFFER_SIZE,
+                    BROTLI_QUALITY,
+                    BROTLI_LG_WINDOW_SIZE,
+                );
+                writer.write_all(body)?;
+                Ok(writer.into_inner())
+            }
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn negotiate_none() {
+        assert_eq!(None, ContentEncoding::negotiate(""));
+        assert_eq!(None, ContentEncoding::negotiate("identity"));
+        assert_eq!(None, ContentEncoding::negotiate("deflate, gzip;q=0"));
+        assert_eq!(None, ContentEncoding::negotiate("*;q=0"));
+    }
+
+    #[test]
+    fn negotiate_preference() {
+        assert_eq!(
+            Some(ContentEncoding::Brotli),
+            ContentEncoding::negotiate("gzip, deflate, br")
+        );
+        assert_eq!(
+            Some(ContentEncoding::Gzip),
+            ContentEncoding::negotiate("GZIP, br;q=0.5")
+        );
+        assert_eq!(
+            Some(ContentEncoding::Gzip),
+            ContentEncoding::negotiate("br;q=0, *")
+        );
+        assert_eq!(
+            Some(ContentEncoding::Brotli),
+            ContentEncoding::negotiate("*")
+        );
+    }
+
+    #[test]
+    fn negotiate_repeated_header() {
+        assert_eq!(
+            Some(ContentEncoding::Gzip),
+            ContentEncoding::negotiate("br;q=0.5\ngzip")
+        );
+    }
+
+    #[test]
+    fn compress_round_trip() {
+        let body = "Hello world ".repeat(100).into_bytes();
+
+        let compressed = ContentEncoding::Gzip.compress(&body).unwrap();
+        assert!(compressed.len() < body.len());
+        let mut decompressed = vec![];
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(body, decompressed);
+
+        let compressed = ContentEncoding::Brotli.compress(&body).unwrap();
+        assert!(compressed.len() < body.len());
+        let mut decompressed = vec![];
+        brotli::Decompressor::new(compressed.as_slice(), BROTLI_BUFFER_SIZE)
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(body, decompressed);
+    }
+}
@@ -0,0 +1,421 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::http::{add_vary_header, header_value};
+
+/// The configuration value listing the origins allowed to make cross-origin requests, or `*` for any.
+const ALLOW_ORIGINS_KEY: &str = "WASCC_CORS_ALLOW_ORIGINS";
+
+/// The configuration value listing the methods allowed in cross-origin requests.
+const ALLOW_METHODS_KEY: &str = "WASCC_CORS_ALLOW_METHODS";
+
+/// The configuration value listing the request headers allowed in cross-origin requests.
+const ALLOW_HEADERS_KEY: &str = "WASCC_CORS_ALLOW_HEADERS";
+
+/// The configuration value listing the response headers exposed to cross-origin requests.
+const EXPOSE_HEADERS_KEY: &str = "WASCC_CORS_EXPOSE_HEADERS";
+
+/// The configuration value holding how long, in seconds, a preflight response can be cached.
+const MAX_AGE_KEY: &str = "WASCC_CORS_MAX_AGE";
+
+/// The configuration value enabling credentialed cross-origin requests.
+const ALLOW_CREDENTIALS_KEY: &str = "WASCC_CORS_ALLOW_CREDENTIALS";
+
+/// The methods allowed in cross-origin requests if none are configured.
+const DEFAULT_ALLOW_METHODS: &[&str] = &["GET", "HEAD", "PUT", "PATCH", "POST", "DELETE"];
+
+/// The value that matches any origin, method or header.
+const WILDCARD: &str = "*";
+
+/// The request header that carries the origin of a cross-origin request.
+const ORIGIN_HEADER: &str = "origin";
+
+/// The preflight request header that carries the method of the actual request.
+const REQUEST_METHOD_HEADER: &str = "access-control-request-method";
+
+/// The preflight request header that lists the headers of the actual request.
+const REQUEST_HEADERS_HEADER: &str = "access-control-request-headers";
+
+/// The response header that carries the allowed origin.
+const ALLOW_ORIGIN_HEADER: &str = "access-control-allow-origin";
+
+/// The preflight response header that lists the allowed methods.
+const ALLOW_METHODS_HEADER: &str = "access-control-allow-methods";
+
+/// The preflight response header that lists the allowed request headers.
+const ALLOW_HEADERS_HEADER: &str = "access-control-allow-headers";
+
+/// The response header that lists the response headers exposed to the client.
+const EXPOSE_HEADERS_HEADER: &str = "access-control-expose-headers";
+
+/// The preflight response header that carries how long, in seconds, the response can be cached.
+const MAX_AGE_HEADER: &str = "access-control-max-age";
+
+/// The response header that allows credentialed requests.
+const ALLOW_CREDENTIALS_HEADER: &str = "access-control-allow-credentials";
+
+/// Represents the CORS settings of each actor bound to the HTTP request provider.
+pub(crate) type ActorCorsSettings = Arc<RwLock<HashMap<String, CorsSettings>>>;
+
+/// Represents an actor's CORS settings.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CorsSettings {
+    allow_origins: Vec<String>,
+    allow_methods: Vec<String>,
+    allow_headers: Vec<String>,
+    expose_headers: Vec<String>,
+    max_age: Option<u64>,
+    allow_credentials: bool,
+}
+
+impl CorsSettings {
+    /// Creates a new `CorsSettings` from an actor's capability configuration values.
+    /// Returns `None` if no allowed origins are configured.
+    /// Returns an error if credentialed requests are allowed from any origin,
+    /// which would let any website make credentialed requests.
+    pub fn from_config(values: &HashMap<String, String>) -> anyhow::Result<Option<Self>> {
+        let list = |key: &str| -> Vec<String> {
+            values
+                .get(key)
+                .map(|s| {
+                    s.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let allow_origins = list(ALLOW_ORIGINS_KEY);
+        if allow_origins.is_empty() {
+            return Ok(None);
+        }
+
+        let allow_credentials = values
+            .get(ALLOW_CREDENTIALS_KEY)
+            .map(|v| v.trim().eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if allow_credentials && allow_origins.iter().any(|o| o == WILDCARD) {
+            return Err(anyhow!(
+                "{} must list the allowed origins when {} is true",
+                ALLOW_ORIGINS_KEY,
+                ALLOW_CREDENTIALS_KEY
+            ));
+        }
+
+        let mut allow_methods = list(ALLOW_METHODS_KEY);
+        if allow_methods.is_empty() {
+            allow_methods = DEFAULT_ALLOW_METHODS
+                .iter()
+                .map(|m| m.to_string())
+                .collect();
+        }
+
+        Ok(Some(Self {
+            allow_origins,
+            allow_methods,
+            allow_headers: list(ALLOW_HEADERS_KEY),
+            expose_headers: list(EXPOSE_HEADERS_KEY),
+            max_age: values.get(MAX_AGE_KEY).and_then(|v| v.trim().parse().ok()),
+            allow_credentials,
+        }))
+    }
+
+    /// Returns the response to a CORS preflight request, or `None` if the request is not a preflight request.
+    /// A preflight request from a disallowed origin or for a disallowed method gets a response with no CORS headers.
+    pub fn preflight_response(
+        &self,
+        request: &wascc_codec::http::Request,
+    ) -> Option<wascc_codec::http::Response> {
+        if !request.method.eq_ignore_ascii_case("OPTIONS") {
+            return None;
+        }
+        let origin = header_value(&request.header, ORIGIN_HEADER)?;
+        let method = header_value(&request.header, REQUEST_METHOD_HEADER)?;
+
+        let mut header = HashMap::new();
+        add_vary_header(&mut header, "Origin");
+        if let Some(allow_origin) = self.allow_origin(origin) {
+            if self.allows(&self.allow_methods, method) {
+                header.insert(ALLOW_ORIGIN_HEADER.into(), allow_origin);
+                header.insert(ALLOW_METHODS_HEADER.into(), self.allow_methods.join(", "));
+                let allow_headers = if self.allow_headers.is_empty() {
+                    // Allow whatever headers the client asks for.
+                    add_vary_header(&mut header, "Access-Control-Request-Headers");
+                    header_value(&request.header, REQUEST_HEADERS_HEADER)
+                        .unwrap_or_default()
+                        .to_string()
+                } else {
+                    self.allow_headers.join(", ")
+                };
+                if !allow_headers.is_empty() {
+                    header.insert(ALLOW_HEADERS_HEADER.into(), allow_headers);
+                }
+                if let Some(max_age) = self.max_age {
+                    header.insert(MAX_AGE_HEADER.into(), max_age.to_string());
+                }
+                if self.allow_credentials {
+                    header.insert(ALLOW_CREDENTIALS_HEADER.into(), "true".into());
+                }
+            } else {
+                debug!("CORS preflight request for disallowed method {}", method);
+            }
+        } else {
+            debug!("CORS preflight request from disallowed origin {}", origin);
+        }
+
+        Some(wascc_codec::http::Response {
+            status_code: 204,
+            status: "No Content".into(),
+            header,
+            body: vec![],
+        })
+    }
+
+    /// Adds CORS headers to an actor's response to a request from the specified origin, if it is allowed.
+    /// Any CORS headers set by the actor are replaced.
+    pub fn decorate(&self, origin: Option<&str>, response: &mut wascc_codec::http::Response) {
+        add_vary_header(&mut response.header, "Origin");
+        let allow_origin = match origin.and_then(|o| self.allow_origin(o)) {
+            Some(allow_origin) => allow_origin,
+            None => return,
+        };
+
+        let header = &mut response.header;
+        set_header(header, ALLOW_ORIGIN_HEADER, allow_origin);
+        if !self.expose_headers.is_empty() {
+            set_header(
+                header,
+                EXPOSE_HEADERS_HEADER,
+                self.expose_headers.join(", "),
+            );
+        }
+        if self.allow_credentials {
+            set_header(header, ALLOW_CREDENTIALS_HEADER, "true".into());
+        }
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` value for an origin, or `None` if the origin is not allowed.
+    fn allow_origin(&self, origin: &str) -> Option<String> {
+        if self.allow_origins.iter().any(|o| o == WILDCARD) {
+            Some(WILDCARD.into())
+        } else if self.allows(&self.allow_origins, origin) {
+            Some(origin.into())
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether or not a value is in an allowed list, which may contain the wildcard.
+    fn allows(&self, allowed: &[String], value: &str) -> bool {
+        allowed
+            .iter()
+            .any(|a| a == WILDCARD || a.eq_ignore_ascii_case(value))
+    }
+}
+
+/// Sets an HTTP response header, removing any header with the same name in a different case.
+fn set_header(header: &mut HashMap<String, String>, name: &str, value: String) {
+    header.retain(|n, _| !n.eq_ignore_ascii_case(name));
+    header.insert(name.into(), value);
+}
+
+/// Returns the value of an actor's HTTP request's `Origin` header, if present.
+pub(crate) fn origin(request: &wascc_codec::http::Request) -> Option<String> {
+    header_value(&request.header, ORIGIN_HEADER).map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_common::*;
+
+    /// Returns CORS settings for the specified configuration values.
+    fn cors_settings(pairs: &[(&str, &str)]) -> CorsSettings {
+        let values = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        CorsSettings::from_config(&values).unwrap().unwrap()
+    }
+
+    /// Returns a CORS preflight request.
+    fn preflight_request(origin: &str, method: &str) -> wascc_codec::http::Request {
+        let mut header = HashMap::new();
+        header.insert("Origin".into(), origin.into());
+        header.insert("Access-Control-Request-Method".into(), method.into());
+        header.insert(
+            "Access-Control-Request-Headers".into(),
+            "content-type".into(),
+        );
+        wascc_codec::http::Request {
+            method: "OPTIONS".into(),
+            path: "/".into(),
+            query_string: "".into(),
+            header,
+            body: vec![],
+        }
+    }
+
+    #[test]
+    fn cors_settings_not_configured() {
+        assert!(CorsSettings::from_config(&HashMap::new())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn cors_settings_wildcard_with_credentials() {
+        let mut values = HashMap::new();
+        values.insert(ALLOW_ORIGINS_KEY.into(), "https://example.com, *".into());
+        values.insert(ALLOW_CREDENTIALS_KEY.into(), "true".into());
+        assert!(CorsSettings::from_config(&values).is_err());
+
+        values.insert(ALLOW_CREDENTIALS_KEY.into(), "false".into());
+        assert!(CorsSettings::from_config(&values).unwrap().is_some());
+    }
+
+    #[test]
+    fn preflight_response_allowed() {
+        let settings = cors_settings(&[
+            (
+                ALLOW_ORIGINS_KEY,
+                "https://example.com, https://example.org",
+            ),
+            (ALLOW_METHODS_KEY, "GET,POST"),
+            (ALLOW_HEADERS_KEY, "content-type,authorization"),
+            (MAX_AGE_KEY, "600"),
+            (ALLOW_CREDENTIALS_KEY, "true"),
+        ]);
+
+        let response = settings
+            .preflight_response(&preflight_request("https://example.org", "POST"))
+            .unwrap();
+        assert_eq!(204, response.status_code);
+        let header = &response.header;
+        assert_eq!(
+            "https://example.org",
+            header.get(ALLOW_ORIGIN_HEADER).unwrap()
+        );
+        assert_eq!("GET, POST", header.get(ALLOW_METHODS_HEADER).unwrap());
+        assert_eq!(
+            "content-type, authorization",
+            header.get(ALLOW_HEADERS_HEADER).unwrap()
+        );
+        assert_eq!("600", header.get(MAX_AGE_HEADER).unwrap());
+        assert_eq!("true", header.get(ALLOW_CREDENTIALS_HEADER).unwrap());
+        assert_eq!("Origin", header.get("vary").unwrap());
+    }
+
+    #[test]
+    fn preflight_response_wildcard() {
+        let settings = cors_settings(&[(ALLOW_ORIGINS_KEY, "*")]);
+
+        let response = settings
+            .preflight_response(&preflight_request("https://example.com", "DELETE"))
+            .unwrap();
+        let header = &response.header;
+        assert_eq!("*", header.get(ALLOW_ORIGIN_HEADER).unwrap());
+        assert_eq!(
+            "GET, HEAD, PUT, PATCH, POST, DELETE",
+            header.get(ALLOW_METHODS_HEADER).unwrap()
+        );
+        assert_eq!("content-type", header.get(ALLOW_HEADERS_HEADER).unwrap());
+        assert!(!header.contains_key(MAX_AGE_HEADER));
+        assert!(!header.contains_key(ALLOW_CREDENTIALS_HEADER));
+    }
+
+    #[test]
+    fn preflight_response_disallowed() {
+        let settings = cors_settings(&[
+            (ALLOW_ORIGINS_KEY, "https://example.com"),
+            (ALLOW_METHODS_KEY, "GET"),
+        ]);
+
+        let response = settings
+            .preflight_response(&preflight_request("https://example.org", "GET"))
+            .unwrap();
+        assert!(!response.header.contains_key(ALLOW_ORIGIN_HEADER));
+
+        let response = settings
+            .preflight_response(&preflight_request("https://example.com", "PUT"))
+            .unwrap();
+        assert!(!response.header.contains_key(ALLOW_ORIGIN_HEADER));
+    }
+
+    #[test]
+    fn preflight_response_not_preflight() {
+        let settings = cors_settings(&[(ALLOW_ORIGINS_KEY, "*")]);
+
+        let mut request = preflight_request("https://example.com", "GET");
+        request.method = "GET".into();
+        assert!(settings.preflight_response(&request).is_none());
+
+        let mut request = preflight_request("https://example.com", "GET");
+        request.header.remove("Access-Control-Request-Method");
+        assert!(settings.preflight_response(&request).is_none());
+    }
+
+    #[test]
+    fn decorate() {
+        let settings = cors_settings(&[
+            (ALLOW_ORIGINS_KEY, "https://example.com"),
+            (EXPOSE_HEADERS_KEY, "x-request-id"),
+        ]);
+
+        let mut response = valid_http_response();
+        settings.decorate(Some("https://example.com"), &mut response);
+        let header = &response.header;
+        assert_eq!(
+            "https://example.com",
+            header.get(ALLOW_ORIGIN_HEADER).unwrap()
+        );
+        assert_eq!("x-request-id", header.get(EXPOSE_HEADERS_HEADER).unwrap());
+        assert_eq!("Origin", header.get("vary").unwrap());
+
+        let mut response = valid_http_response();
+        settings.decorate(Some("https://example.org"), &mut response);
+        assert!(!response.header.contains_key(ALLOW_ORIGIN_HEADER));
+
+        let mut response = valid_http_response();
+        settings.decorate(None, &mut response);
+        assert!(!response.header.contains_key(ALLOW_ORIGIN_HEADER));
+    }
+
+    #[test]
+    fn decorate_replaces_actor_header() {
+        let settings = cors_settings(&[(ALLOW_ORIGINS_KEY, "https://example.com")]);
+
+        let mut response = valid_http_response();
+        response
+            .header
+            .insert("Access-Control-Allow-Origin".into(), "*".into());
+        settings.decorate(Some("https://example.com"), &mut response);
+        let allow_origins: Vec<&String> = response
+            .header
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(ALLOW_ORIGIN_HEADER))
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(vec!["https://example.com"], allow_origins);
+    }
+}
@@ -20,14 +20,26 @@ use aws_lambda_events::event::{alb, apigw};
 use serde::{Deserialize, Serialize};
 use wascc_codec::{deserialize, serialize};
 
+use std::collections::HashSet;
 use std::convert::TryInto;
-use std::sync::Arc;
-
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::authorizer::{AuthorizerEvent, AuthorizerResult};
+use crate::batch::BatchResponse;
+use crate::classify::{ClassifiedEvent, EventSource};
+use crate::cors::{self, ActorCorsSettings};
+use crate::function_url::{FunctionUrlRequest, FunctionUrlResponse};
 use crate::http::{
-    AlbTargetGroupRequestWrapper, AlbTargetGroupResponseWrapper, ApiGatewayProxyRequestWrapper,
-    ApiGatewayProxyResponseWrapper, ApiGatewayV2ProxyRequestWrapper,
-    ApiGatewayV2ProxyResponseWrapper,
+    accept_encoding, AlbTargetGroupRequestWrapper, AlbTargetGroupResponseWrapper,
+    ApiGatewayProxyRequestWrapper, ApiGatewayProxyResponseWrapper, ApiGatewayV2ProxyRequestWrapper,
+    ApiGatewayV2ProxyResponseWrapper, HttpResponse, HttpSettings,
 };
+use crate::sqs::{SqsEvent, SqsRecord};
+use crate::stream::{DynamoDbEvent, KinesisEvent};
+use crate::websocket::{WebSocketApiResponse, WebSocketRequest};
 use crate::HostDispatcher;
 
 /// A dispatcher error.
@@ -57,11 +69,103 @@ pub(crate) enum DispatcherError {
     },
 }
 
+/// The actor handled the request and returned an error.
+#[derive(thiserror::Error, Debug)]
+#[error("{}", error_message)]
+pub(crate) struct ActorError {
+    error_type: String,
+    error_message: String,
+    data: Option<serde_json::Value>,
+}
+
+impl ActorError {
+    /// Returns the error type chosen by the actor.
+    pub fn error_type(&self) -> &str {
+        self.error_type.as_str()
+    }
+
+    /// Returns any additional error data.
+    pub fn data(&self) -> Option<&serde_json::Value> {
+        self.data.as_ref()
+    }
+}
+
+impl From<codec::FunctionError> for ActorError {
+    /// Converts an actor's function error to an `ActorError`.
+    fn from(error: codec::FunctionError) -> Self {
+        let data = if error.data.is_empty() {
+            None
+        } else {
+            // Pass through any error data that isn't valid JSON as a string.
+            Some(serde_json::from_slice(&error.data).unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&error.data).into())
+            }))
+        };
+
+        Self {
+            error_type: error.error_type,
+            error_message: error.error_message,
+            data,
+        }
+    }
+}
+
+/// The invocation deadline was reached before the actor responded.
+#[derive(thiserror::Error, Debug)]
+#[error("Guest {} failed to respond within {} ms", actor, timeout_ms)]
+pub(crate) struct DeadlineExceededError {
+    actor: String,
+    timeout_ms: u128,
+}
+
+impl DeadlineExceededError {
+    /// Returns a new `DeadlineExceededError` for the specified actor and dispatch timeout.
+    pub fn new(actor: &str, timeout: Duration) -> Self {
+        Self {
+            actor: actor.into(),
+            timeout_ms: timeout.as_millis(),
+        }
+    }
+}
+
+/// The actor is still handling a dispatch that was abandoned at an earlier invocation deadline.
+#[derive(thiserror::Error, Debug)]
+#[error("Guest {} is still handling an abandoned invocation", actor)]
+pub(crate) struct ActorBusyError {
+    actor: String,
+}
+
+impl ActorBusyError {
+    /// Returns a new `ActorBusyError` for the specified actor.
+    pub fn new(actor: &str) -> Self {
+        Self {
+            actor: actor.into(),
+        }
+    }
+}
+
 /// Represents dispatching an invocation request to an actor and returning its response.
 pub(crate) trait InvocationEventDispatcher {
     /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
-    /// The bodies of the invocation event and response are passed and returned.
-    fn dispatch_invocation_event(&self, actor: &str, event: &[u8]) -> anyhow::Result<Vec<u8>>;
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: &[u8],
+    ) -> anyhow::Result<Vec<u8>>;
+
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The invocation context and event are passed and the body of the invocation response is returned.
+    /// By default the raw event is dispatched.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.dispatch_invocation_event(actor, context, event.body())
+    }
 }
 
 /// Represents dispatching a request to an actor and returning its response.
@@ -113,12 +217,45 @@ pub(crate) struct NotHttpRequestError;
 /// Dispatches HTTP requests.
 pub(crate) struct HttpRequestDispatcher {
     host_dispatcher: HostDispatcher,
+    settings: HttpSettings,
+    cors: ActorCorsSettings,
 }
 
 impl HttpRequestDispatcher {
     /// Returns a new `HttpRequestDispatcher`.
-    pub fn new(host_dispatcher: HostDispatcher) -> Self {
-        Self { host_dispatcher }
+    /// The CORS settings of each actor are shared with the provider, which adds them as actors are bound.
+    pub fn new(
+        host_dispatcher: HostDispatcher,
+        settings: HttpSettings,
+        cors: ActorCorsSettings,
+    ) -> Self {
+        Self {
+            host_dispatcher,
+            settings,
+            cors,
+        }
+    }
+
+    /// Dispatches an actor's HTTP request, returning its response.
+    /// If the actor has CORS settings, preflight requests are answered without invoking the actor.
+    fn dispatch_http_request(
+        &self,
+        actor: &str,
+        request: wascc_codec::http::Request,
+    ) -> anyhow::Result<HttpResponse> {
+        let cors = self.cors.read().unwrap().get(actor).cloned();
+        if let Some(response) = cors.as_ref().and_then(|c| c.preflight_response(&request)) {
+            info!("HttpRequestDispatcher answered CORS preflight request");
+            return Ok(self.settings.response(response, None));
+        }
+
+        let accept_encoding = accept_encoding(&request);
+        let origin = cors::origin(&request);
+        let mut response = self.dispatch_request(actor, request)?;
+        if let Some(cors) = cors {
+            cors.decorate(origin.as_deref(), &mut response);
+        }
+        Ok(self.settings.response(response, accept_encoding.as_deref()))
     }
 
     /// Dispatches an ALB target group request.
@@ -128,9 +265,14 @@ impl HttpRequestDispatcher {
         request: AlbTargetGroupRequestWrapper,
     ) -> anyhow::Result<AlbTargetGroupResponseWrapper> {
         info!("HttpRequestDispatcher dispatch ALB target group request");
-        Ok(self
-            .dispatch_request(actor, request.try_into()?)?
-            .try_into()?)
+        // The response must use multi-value headers if the request did.
+        let multi_value = request.is_multi_value();
+        let response = self.dispatch_http_request(actor, request.try_into()?)?;
+        if multi_value {
+            AlbTargetGroupResponseWrapper::try_from_multi_value(response)
+        } else {
+            Ok(response.try_into()?)
+        }
     }
 
     /// Dispatches an API Gateway proxy request.
@@ -140,11 +282,45 @@ impl HttpRequestDispatcher {
         request: ApiGatewayProxyRequestWrapper,
     ) -> anyhow::Result<ApiGatewayProxyResponseWrapper> {
         info!("HttpRequestDispatcher dispatch API Gateway proxy request");
+        let request = request.rewrite_path(&self.settings);
         Ok(self
-            .dispatch_request(actor, request.try_into()?)?
+            .dispatch_http_request(actor, request.try_into()?)?
             .try_into()?)
     }
 
+    /// Dispatches a Lambda function URL request.
+    fn dispatch_function_url_request(
+        &self,
+        actor: &str,
+        request: FunctionUrlRequest,
+    ) -> anyhow::Result<FunctionUrlResponse> {
+        info!(
+            "HttpRequestDispatcher dispatch function URL request (auth type {})",
+            request.auth_type()
+        );
+        Ok(self
+            .dispatch_http_request(actor, request.try_into()?)?
+            .try_into()?)
+    }
+
+    /// Dispatches an API Gateway Lambda authorizer request.
+    fn dispatch_authorizer_request(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: AuthorizerEvent,
+    ) -> anyhow::Result<AuthorizerResult> {
+        info!("HttpRequestDispatcher dispatch API Gateway authorizer request");
+        let simple =
+            self.settings.authorizer_simple_responses() && event.is_simple_response_format();
+        let resource_arn = event.resource_arn();
+        let dispatcher = AuthorizerRequestDispatcher {
+            host_dispatcher: Arc::clone(&self.host_dispatcher),
+        };
+        let response = dispatcher.dispatch_request(actor, event.into_request(context.clone()))?;
+        Ok(AuthorizerResult::new(response, resource_arn, simple))
+    }
+
     /// Dispatches an API Gateway v2 proxy request.
     fn dispatch_apigwv2_request(
         &self,
@@ -152,8 +328,9 @@ impl HttpRequestDispatcher {
         request: ApiGatewayV2ProxyRequestWrapper,
     ) -> anyhow::Result<ApiGatewayV2ProxyResponseWrapper> {
         info!("HttpRequestDispatcher dispatch API Gateway v2 proxy request");
+        let request = request.rewrite_path(&self.settings);
         Ok(self
-            .dispatch_request(actor, request.try_into()?)?
+            .dispatch_http_request(actor, request.try_into()?)?
             .try_into()?)
     }
 }
@@ -163,47 +340,73 @@ impl Clone for HttpRequestDispatcher {
     fn clone(&self) -> Self {
         Self {
             host_dispatcher: Arc::clone(&self.host_dispatcher),
+            settings: self.settings.clone(),
+            cors: Arc::clone(&self.cors),
         }
     }
 }
 
 impl InvocationEventDispatcher for HttpRequestDispatcher {
     /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
-    /// The bodies of the invocation event and response are passed and returned.
-    fn dispatch_invocation_event(&self, actor: &str, body: &[u8]) -> anyhow::Result<Vec<u8>> {
-        let body = std::str::from_utf8(body).map_err(|e| {
-            debug!("{}", e);
-            NotHttpRequestError {}
-        })?;
-
-        debug!("Lambda invocation event body:\n{}", body);
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.dispatch_classified_event(actor, context, ClassifiedEvent::new(body.to_vec()))
+    }
 
-        match serde_json::from_str(body) {
-            Ok(request @ alb::AlbTargetGroupRequest { .. }) => {
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The invocation context and event are passed and the body of the invocation response is returned.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        debug!(
+            "Lambda invocation event body:\n{}",
+            String::from_utf8_lossy(event.body())
+        );
+
+        let (source, event) = event.into_parts();
+        let event = event.ok_or(NotHttpRequestError {})?;
+        match source {
+            EventSource::Alb => {
+                let request: alb::AlbTargetGroupRequest = serde_json::from_value(event)?;
                 let response: alb::AlbTargetGroupResponse =
                     self.dispatch_alb_request(actor, request.into())?.into();
-                return serde_json::to_vec(&response).map_err(|e| e.into());
+                serde_json::to_vec(&response).map_err(|e| e.into())
             }
-            _ => debug!("Not an ALB request"),
-        };
-        match serde_json::from_str(body) {
-            Ok(request @ apigw::ApiGatewayProxyRequest { .. }) => {
+            EventSource::ApiGatewayV1 => {
+                let request: apigw::ApiGatewayProxyRequest = serde_json::from_value(event)?;
                 let response: apigw::ApiGatewayProxyResponse =
                     self.dispatch_apigw_request(actor, request.into())?.into();
-                return serde_json::to_vec(&response).map_err(|e| e.into());
+                serde_json::to_vec(&response).map_err(|e| e.into())
             }
-            _ => debug!("Not an API Gateway proxy request"),
-        };
-        match serde_json::from_str(body) {
-            Ok(request @ apigw::ApiGatewayV2httpRequest { .. }) => {
+            EventSource::FunctionUrl => {
+                let request: FunctionUrlRequest = serde_json::from_value(event)?;
+                let response = self.dispatch_function_url_request(actor, request)?;
+                serde_json::to_vec(&response).map_err(|e| e.into())
+            }
+            EventSource::ApiGatewayV2 => {
+                let request: apigw::ApiGatewayV2httpRequest = serde_json::from_value(event)?;
                 let response: apigw::ApiGatewayV2httpResponse =
                     self.dispatch_apigwv2_request(actor, request.into())?.into();
-                return serde_json::to_vec(&response).map_err(|e| e.into());
+                serde_json::to_vec(&response).map_err(|e| e.into())
             }
-            _ => debug!("Not an API Gateway v2 proxy request"),
-        };
-
-        Err(NotHttpRequestError {}.into())
+            EventSource::Authorizer => {
+                let event: AuthorizerEvent = serde_json::from_value(event)?;
+                let response = self.dispatch_authorizer_request(actor, context, event)?;
+                serde_json::to_vec(&response).map_err(|e| e.into())
+            }
+            source => {
+                debug!("Not an HTTP request: {}", source);
+                Err(NotHttpRequestError {}.into())
+            }
+        }
     }
 }
 
@@ -222,6 +425,26 @@ impl Dispatcher<'_> for HttpRequestDispatcher {
     }
 }
 
+/// Dispatches API Gateway Lambda authorizer requests.
+struct AuthorizerRequestDispatcher {
+    host_dispatcher: HostDispatcher,
+}
+
+impl Dispatcher<'_> for AuthorizerRequestDispatcher {
+    /// The request type.
+    type T = codec::AuthorizerRequest;
+    /// The response type.
+    type U = codec::AuthorizerResponse;
+
+    /// The operation this dispatcher dispatches.
+    const OP: &'static str = codec::OP_HANDLE_AUTHORIZER_REQUEST;
+
+    /// Returns a shared host dispatcher.
+    fn host_dispatcher(&self) -> HostDispatcher {
+        Arc::clone(&self.host_dispatcher)
+    }
+}
+
 /// Dispatches raw Lambda events.
 pub(crate) struct RawEventDispatcher {
     host_dispatcher: HostDispatcher,
@@ -245,13 +468,23 @@ impl Clone for RawEventDispatcher {
 
 impl InvocationEventDispatcher for RawEventDispatcher {
     /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
-    /// The bodies of the invocation event and response are passed and returned.
-    fn dispatch_invocation_event(&self, actor: &str, body: &[u8]) -> anyhow::Result<Vec<u8>> {
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
         let raw_event = codec::Event {
             body: body.to_vec(),
+            context: context.clone(),
         };
 
-        Ok(self.dispatch_request(actor, raw_event)?.body)
+        let response = self.dispatch_request(actor, raw_event)?;
+        match response.error {
+            Some(error) => Err(ActorError::from(error).into()),
+            None => Ok(response.body),
+        }
     }
 }
 
@@ -270,32 +503,610 @@ impl Dispatcher<'_> for RawEventDispatcher {
     }
 }
 
+/// The invocation request is not an Amazon SQS event.
+#[derive(thiserror::Error, Debug)]
+#[error("Not an SQS event")]
+pub(crate) struct NotSqsEventError;
+
+/// Dispatches Amazon SQS messages.
+/// Each message is dispatched in turn and any failures are reported as a partial batch response.
+pub(crate) struct SqsEventDispatcher {
+    host_dispatcher: HostDispatcher,
+}
+
+impl SqsEventDispatcher {
+    /// Returns a new `SqsEventDispatcher`.
+    pub fn new(host_dispatcher: HostDispatcher) -> Self {
+        Self { host_dispatcher }
+    }
+
+    /// Dispatches an Amazon SQS message.
+    fn dispatch_message(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        record: SqsRecord,
+    ) -> anyhow::Result<()> {
+        let event = codec::SqsMessageEvent {
+            message: record.try_into()?,
+            context: context.clone(),
+        };
+
+        let response = self.dispatch_request(actor, event)?;
+        match response.error {
+            Some(error) => Err(ActorError::from(error).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Clone for SqsEventDispatcher {
+    /// Returns a copy of the value.
+    fn clone(&self) -> Self {
+        Self {
+            host_dispatcher: Arc::clone(&self.host_dispatcher),
+        }
+    }
+}
+
+impl InvocationEventDispatcher for SqsEventDispatcher {
+    /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.dispatch_classified_event(actor, context, ClassifiedEvent::new(body.to_vec()))
+    }
+
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The invocation context and event are passed and the body of the invocation response is returned.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (_, event) = event.into_parts();
+        let event = event.ok_or(NotSqsEventError {})?;
+        let event: SqsEvent = serde_json::from_value(event).map_err(|e| {
+            debug!("{}", e);
+            NotSqsEventError {}
+        })?;
+
+        info!(
+            "SqsEventDispatcher dispatch {} message(s)",
+            event.records.len()
+        );
+        let mut response = BatchResponse::default();
+        for record in event.records {
+            let message_id = record.message_id.clone();
+            // Messages in a FIFO queue must be processed in order,
+            // so all messages after a failure are also failed.
+            if record.is_fifo() && !response.failures().is_empty() {
+                response.add_failure(&message_id);
+                continue;
+            }
+            if let Err(e) = self.dispatch_message(actor, context, record) {
+                error!("Message {} failed: {}", message_id, e);
+                response.add_failure(&message_id);
+            }
+        }
+
+        serde_json::to_vec(&response).map_err(|e| e.into())
+    }
+}
+
+impl Dispatcher<'_> for SqsEventDispatcher {
+    /// The request type.
+    type T = codec::SqsMessageEvent;
+    /// The response type.
+    type U = codec::Response;
+
+    /// The operation this dispatcher dispatches.
+    const OP: &'static str = codec::OP_HANDLE_SQS_MESSAGE;
+
+    /// Returns a shared host dispatcher.
+    fn host_dispatcher(&self) -> HostDispatcher {
+        Arc::clone(&self.host_dispatcher)
+    }
+}
+
+/// The invocation request is not an API Gateway WebSocket API event.
+#[derive(thiserror::Error, Debug)]
+#[error("Not a WebSocket event")]
+pub(crate) struct NotWebSocketEventError;
+
+/// Dispatches API Gateway WebSocket API events.
+pub(crate) struct WebSocketEventDispatcher {
+    host_dispatcher: HostDispatcher,
+}
+
+impl WebSocketEventDispatcher {
+    /// Returns a new `WebSocketEventDispatcher`.
+    pub fn new(host_dispatcher: HostDispatcher) -> Self {
+        Self { host_dispatcher }
+    }
+}
+
+impl Clone for WebSocketEventDispatcher {
+    /// Returns a copy of the value.
+    fn clone(&self) -> Self {
+        Self {
+            host_dispatcher: Arc::clone(&self.host_dispatcher),
+        }
+    }
+}
+
+impl InvocationEventDispatcher for WebSocketEventDispatcher {
+    /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.dispatch_classified_event(actor, context, ClassifiedEvent::new(body.to_vec()))
+    }
+
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The invocation context and event are passed and the body of the invocation response is returned.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        let event = match event.into_parts() {
+            (EventSource::WebSocket, Some(event)) => event,
+            _ => return Err(NotWebSocketEventError {}.into()),
+        };
+
+        let request: WebSocketRequest = serde_json::from_value(event)?;
+        info!(
+            "WebSocketEventDispatcher dispatch {} route",
+            request.route_key()
+        );
+        let mut event: codec::WebSocketEvent = request.try_into()?;
+        event.context = context.clone();
+
+        let response: WebSocketApiResponse = self.dispatch_request(actor, event)?.into();
+        serde_json::to_vec(&response).map_err(|e| e.into())
+    }
+}
+
+impl Dispatcher<'_> for WebSocketEventDispatcher {
+    /// The request type.
+    type T = codec::WebSocketEvent;
+    /// The response type.
+    type U = codec::WebSocketResponse;
+
+    /// The operation this dispatcher dispatches.
+    const OP: &'static str = codec::OP_HANDLE_WEBSOCKET_EVENT;
+
+    /// Returns a shared host dispatcher.
+    fn host_dispatcher(&self) -> HostDispatcher {
+        Arc::clone(&self.host_dispatcher)
+    }
+}
+
+/// The invocation request is not an Amazon Kinesis or Amazon DynamoDB Streams event.
+#[derive(thiserror::Error, Debug)]
+#[error("Not a stream event")]
+pub(crate) struct NotStreamEventError;
+
+/// Dispatches Amazon Kinesis Data Streams and Amazon DynamoDB Streams records.
+/// Records are dispatched in order and dispatch stops at the first failure,
+/// which is reported as a partial batch response so that the stream resumes from the failed record.
+pub(crate) struct StreamEventDispatcher {
+    dynamodb: DynamoDbRecordDispatcher,
+    kinesis: KinesisRecordDispatcher,
+}
+
+impl StreamEventDispatcher {
+    /// Returns a new `StreamEventDispatcher`.
+    pub fn new(host_dispatcher: HostDispatcher) -> Self {
+        Self {
+            dynamodb: DynamoDbRecordDispatcher {
+                host_dispatcher: Arc::clone(&host_dispatcher),
+            },
+            kinesis: KinesisRecordDispatcher { host_dispatcher },
+        }
+    }
+
+    /// Dispatches records in order until the first failure.
+    /// Returns a partial batch response identifying any failed record by its sequence number.
+    fn dispatch_records<R>(
+        records: Vec<R>,
+        sequence_number: impl Fn(&R) -> String,
+        dispatch: impl Fn(R) -> anyhow::Result<codec::Response>,
+    ) -> BatchResponse {
+        let mut response = BatchResponse::default();
+        for record in records {
+            let sequence_number = sequence_number(&record);
+            let result = dispatch(record).and_then(|r| match r.error {
+                Some(error) => Err(ActorError::from(error).into()),
+                None => Ok(()),
+            });
+            if let Err(e) = result {
+                error!("Record {} failed: {}", sequence_number, e);
+                response.add_failure(&sequence_number);
+                break;
+            }
+        }
+
+        response
+    }
+}
+
+impl Clone for StreamEventDispatcher {
+    /// Returns a copy of the value.
+    fn clone(&self) -> Self {
+        Self::new(Arc::clone(&self.kinesis.host_dispatcher))
+    }
+}
+
+impl InvocationEventDispatcher for StreamEventDispatcher {
+    /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.dispatch_classified_event(actor, context, ClassifiedEvent::new(body.to_vec()))
+    }
+
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The invocation context and event are passed and the body of the invocation response is returned.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (source, event) = event.into_parts();
+        let event = event.ok_or(NotStreamEventError {})?;
+        let response = match source {
+            EventSource::Kinesis => {
+                let event: KinesisEvent = serde_json::from_value(event)?;
+                info!(
+                    "StreamEventDispatcher dispatch {} Kinesis record(s)",
+                    event.records.len()
+                );
+                Self::dispatch_records(
+                    event.records,
+                    |r| r.kinesis.sequence_number.clone(),
+                    |r| {
+                        let event = codec::KinesisRecordEvent {
+                            record: r.try_into()?,
+                            context: context.clone(),
+                        };
+                        self.kinesis.dispatch_request(actor, event)
+                    },
+                )
+            }
+            EventSource::DynamoDb => {
+                let event: DynamoDbEvent = serde_json::from_value(event)?;
+                info!(
+                    "StreamEventDispatcher dispatch {} DynamoDB record(s)",
+                    event.records.len()
+                );
+                Self::dispatch_records(
+                    event.records,
+                    |r| r.dynamodb.sequence_number.clone(),
+                    |r| {
+                        let event = codec::DynamoDbRecordEvent {
+                            record: r.try_into()?,
+                            context: context.clone(),
+                        };
+                        self.dynamodb.dispatch_request(actor, event)
+                    },
+                )
+            }
+            source => {
+                debug!("Not a stream event: {}", source);
+                return Err(NotStreamEventError {}.into());
+            }
+        };
+
+        serde_json::to_vec(&response).map_err(|e| e.into())
+    }
+}
+
+/// Dispatches Amazon Kinesis Data Streams records.
+struct KinesisRecordDispatcher {
+    host_dispatcher: HostDispatcher,
+}
+
+impl Dispatcher<'_> for KinesisRecordDispatcher {
+    /// The request type.
+    type T = codec::KinesisRecordEvent;
+    /// The response type.
+    type U = codec::Response;
+
+    /// The operation this dispatcher dispatches.
+    const OP: &'static str = codec::OP_HANDLE_KINESIS_RECORD;
+
+    /// Returns a shared host dispatcher.
+    fn host_dispatcher(&self) -> HostDispatcher {
+        Arc::clone(&self.host_dispatcher)
+    }
+}
+
+/// Dispatches Amazon DynamoDB Streams records.
+struct DynamoDbRecordDispatcher {
+    host_dispatcher: HostDispatcher,
+}
+
+impl Dispatcher<'_> for DynamoDbRecordDispatcher {
+    /// The request type.
+    type T = codec::DynamoDbRecordEvent;
+    /// The response type.
+    type U = codec::Response;
+
+    /// The operation this dispatcher dispatches.
+    const OP: &'static str = codec::OP_HANDLE_DYNAMODB_RECORD;
+
+    /// Returns a shared host dispatcher.
+    fn host_dispatcher(&self) -> HostDispatcher {
+        Arc::clone(&self.host_dispatcher)
+    }
+}
+
+/// Dispatches invocation events, abandoning any dispatch that doesn't complete before the invocation deadline.
+/// An abandoned dispatch can't be stopped and the actor handles no other message until it completes,
+/// so dispatches to the actor fail with an `ActorBusyError` until then.
+pub(crate) struct DeadlineDispatcher<D> {
+    abandoned: Arc<Mutex<HashSet<String>>>,
+    dispatcher: D,
+    margin: Duration,
+}
+
+impl<D> DeadlineDispatcher<D> {
+    /// The time before the invocation deadline at which a dispatch is abandoned.
+    /// This leaves time to report the timeout before the Lambda machinery stops the function.
+    const DEFAULT_MARGIN: Duration = Duration::from_millis(250);
+
+    /// Returns a new `DeadlineDispatcher` wrapping the specified dispatcher.
+    pub fn new(dispatcher: D) -> Self {
+        Self::with_margin(dispatcher, Self::DEFAULT_MARGIN)
+    }
+
+    /// Returns a new `DeadlineDispatcher` wrapping the specified dispatcher
+    /// that abandons dispatches at the specified time before the invocation deadline.
+    pub fn with_margin(dispatcher: D, margin: Duration) -> Self {
+        Self {
+            abandoned: Arc::new(Mutex::new(HashSet::new())),
+            dispatcher,
+            margin,
+        }
+    }
+
+    /// Returns the time remaining to dispatch before the specified deadline,
+    /// or `None` if there is no deadline.
+    fn time_remaining(&self, deadline_ms: u64) -> Option<Duration> {
+        if deadline_ms == 0 {
+            return None;
+        }
+
+        let deadline = Duration::from_millis(deadline_ms);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Some(
+            deadline
+                .checked_sub(now)
+                .and_then(|d| d.checked_sub(self.margin))
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl<D: Clone + Send + InvocationEventDispatcher + 'static> InvocationEventDispatcher
+    for DeadlineDispatcher<D>
+{
+    /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
+    /// The invocation context and the bodies of the invocation event and response are passed and returned.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.dispatch_classified_event(actor, context, ClassifiedEvent::new(body.to_vec()))
+    }
+
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The invocation context and event are passed and the body of the invocation response is returned.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        if self.abandoned.lock().unwrap().contains(actor) {
+            return Err(ActorBusyError::new(actor).into());
+        }
+
+        let timeout = match self.time_remaining(context.deadline_ms) {
+            None => {
+                return self
+                    .dispatcher
+                    .dispatch_classified_event(actor, context, event)
+            }
+            Some(timeout) => timeout,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let dispatcher = self.dispatcher.clone();
+        let actor_id = actor.to_owned();
+        let context = context.clone();
+        let abandoned = Arc::clone(&self.abandoned);
+        thread::spawn(move || {
+            let result = dispatcher.dispatch_classified_event(&actor_id, &context, event);
+            // The lock orders completion with abandonment, so the actor can't be left marked as busy.
+            let mut abandoned = abandoned.lock().unwrap();
+            if tx.send(result).is_err() {
+                info!("Abandoned dispatch to actor {} completed", actor_id);
+                abandoned.remove(&actor_id);
+            }
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                let mut abandoned = self.abandoned.lock().unwrap();
+                // The dispatch may have completed since the timeout.
+                if let Ok(result) = rx.try_recv() {
+                    return result;
+                }
+                drop(rx);
+                abandoned.insert(actor.into());
+                Err(DeadlineExceededError::new(actor, timeout).into())
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(anyhow!(
+                "Dispatch to actor {} terminated unexpectedly",
+                actor
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::tests_common::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns a test invocation context with a deadline the specified time from now.
+    fn context_with_deadline(from_now: Duration) -> codec::Context {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        codec::Context {
+            deadline_ms: (now + from_now).as_millis() as u64,
+            ..context()
+        }
+    }
+
+    /// Tests successfully dispatching a raw event before the deadline.
+    #[test]
+    fn dispatch_raw_event_deadline_ok() {
+        let response = codec::Response {
+            body: RESPONSE_BODY.to_vec(),
+            error: None,
+        };
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = DeadlineDispatcher::new(RawEventDispatcher::new(host_dispatcher));
+
+        let result = dispatcher.dispatch_invocation_event(
+            MODULE_ID,
+            &context_with_deadline(Duration::from_secs(30)),
+            EVENT_BODY,
+        );
+        assert!(result.is_ok());
+        assert_eq!(RESPONSE_BODY, result.unwrap().as_slice());
+    }
+
+    /// Tests abandoning a raw event dispatch at the deadline.
+    #[test]
+    fn dispatch_raw_event_deadline_exceeded_error() {
+        let host_dispatcher = slow_host_dispatcher(Duration::from_millis(500));
+        let dispatcher = DeadlineDispatcher::with_margin(
+            RawEventDispatcher::new(host_dispatcher),
+            Duration::from_millis(0),
+        );
+
+        let result = dispatcher.dispatch_invocation_event(
+            MODULE_ID,
+            &context_with_deadline(Duration::from_millis(50)),
+            EVENT_BODY,
+        );
+        assert!(result.is_err());
+
+        let e = result.unwrap_err();
+        assert!(e.is::<DeadlineExceededError>());
+    }
+
+    /// Tests that an actor is dispatched to again only once its abandoned dispatch completes.
+    #[test]
+    fn dispatch_raw_event_deadline_exceeded_actor_busy() {
+        let host_dispatcher = slow_host_dispatcher(Duration::from_millis(200));
+        let dispatcher = DeadlineDispatcher::with_margin(
+            RawEventDispatcher::new(host_dispatcher),
+            Duration::from_millis(0),
+        );
+
+        let result = dispatcher.dispatch_invocation_event(
+            MODULE_ID,
+            &context_with_deadline(Duration::from_millis(50)),
+            EVENT_BODY,
+        );
+        assert!(result.unwrap_err().is::<DeadlineExceededError>());
+
+        let result = dispatcher.dispatch_invocation_event(
+            MODULE_ID,
+            &context_with_deadline(Duration::from_secs(30)),
+            EVENT_BODY,
+        );
+        assert!(result.unwrap_err().is::<ActorBusyError>());
+
+        // The slow actor is dispatched to again, and fails, once the abandoned dispatch completes.
+        thread::sleep(Duration::from_millis(300));
+        let result = dispatcher.dispatch_invocation_event(
+            MODULE_ID,
+            &context_with_deadline(Duration::from_secs(30)),
+            EVENT_BODY,
+        );
+        let e = result.unwrap_err();
+        assert!(!e.is::<ActorBusyError>());
+        assert!(e.to_string().contains(ERROR_MESSAGE));
+    }
 
     /// Tests successfully dispatching a raw event.
     #[test]
     fn dispatch_raw_event_ok() {
         let response = codec::Response {
             body: RESPONSE_BODY.to_vec(),
+            error: None,
         };
         let host_dispatcher = mock_host_dispatcher(response);
         let dispatcher = RawEventDispatcher::new(host_dispatcher);
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, EVENT_BODY);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), EVENT_BODY);
         assert!(result.is_ok());
         assert_eq!(RESPONSE_BODY, result.unwrap().as_slice());
     }
 
+    /// Tests dispatching a raw event that the actor fails with a typed error.
+    #[test]
+    fn dispatch_raw_event_actor_error() {
+        let response = codec::Response::error("OrderNotFound", ERROR_MESSAGE);
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = RawEventDispatcher::new(host_dispatcher);
+
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), EVENT_BODY);
+        assert!(result.is_err());
+
+        let e = result.unwrap_err();
+        assert!(e.is::<ActorError>());
+        let e = e.downcast_ref::<ActorError>().unwrap();
+        assert_eq!("OrderNotFound", e.error_type());
+        assert_eq!(ERROR_MESSAGE, e.to_string());
+        assert!(e.data().is_none());
+    }
+
     /// Tests failing to dispatch an event.
     #[test]
     fn dispatch_raw_event_not_dispatched_error() {
         let host_dispatcher = error_host_dispatcher();
         let dispatcher = RawEventDispatcher::new(host_dispatcher);
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, EVENT_BODY);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), EVENT_BODY);
         assert!(result.is_err());
 
         let e = result.unwrap_err();
@@ -312,7 +1123,7 @@ mod tests {
         let host_dispatcher = mock_host_dispatcher(RESPONSE_BODY);
         let dispatcher = RawEventDispatcher::new(host_dispatcher);
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, EVENT_BODY);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), EVENT_BODY);
         assert!(result.is_err());
 
         let e = result.unwrap_err();
@@ -328,18 +1139,40 @@ mod tests {
     fn dispatch_alb_target_group_request_ok() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result =
             dispatcher.dispatch_alb_request(MODULE_ID, valid_alb_target_group_request().into());
         assert!(result.is_ok());
     }
 
+    /// Tests that a multi-value ALB target group request gets a multi-value response.
+    #[test]
+    fn dispatch_alb_target_group_request_multi_value_ok() {
+        let response = valid_http_response();
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = http_dispatcher(host_dispatcher);
+
+        let mut request = valid_alb_target_group_request();
+        request
+            .multi_value_headers
+            .insert("accept".into(), vec!["application/json".into()]);
+        let result = dispatcher.dispatch_alb_request(MODULE_ID, request.into());
+        assert!(result.is_ok());
+
+        let response: alb::AlbTargetGroupResponse = result.unwrap().into();
+        assert!(response.headers.is_empty());
+        assert_eq!(
+            &vec!["test".to_string()],
+            response.multi_value_headers.get("server").unwrap()
+        );
+    }
+
     /// Tests failing to dispatch an ALB target group request.
     #[test]
     fn dispatch_alb_target_group_request_not_dispatched_error() {
         let host_dispatcher = error_host_dispatcher();
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result =
             dispatcher.dispatch_alb_request(MODULE_ID, valid_alb_target_group_request().into());
@@ -357,7 +1190,7 @@ mod tests {
     #[test]
     fn dispatch_alb_target_group_deserialization_error() {
         let host_dispatcher = mock_host_dispatcher(RESPONSE_BODY);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result =
             dispatcher.dispatch_alb_request(MODULE_ID, valid_alb_target_group_request().into());
@@ -376,7 +1209,7 @@ mod tests {
     fn dispatch_api_gateway_proxy_request_ok() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result =
             dispatcher.dispatch_apigw_request(MODULE_ID, valid_api_gateway_proxy_request().into());
@@ -387,7 +1220,7 @@ mod tests {
     #[test]
     fn dispatch_api_gateway_proxy_request_not_dispatched_error() {
         let host_dispatcher = error_host_dispatcher();
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result =
             dispatcher.dispatch_apigw_request(MODULE_ID, valid_api_gateway_proxy_request().into());
@@ -405,7 +1238,7 @@ mod tests {
     #[test]
     fn dispatch_api_gateway_proxy_request_deserialization_error() {
         let host_dispatcher = mock_host_dispatcher(RESPONSE_BODY);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result =
             dispatcher.dispatch_apigw_request(MODULE_ID, valid_api_gateway_proxy_request().into());
@@ -424,18 +1257,129 @@ mod tests {
     fn dispatch_api_gatewayv2_proxy_request_ok() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = dispatcher
             .dispatch_apigwv2_request(MODULE_ID, valid_api_gatewayv2_proxy_request().into());
         assert!(result.is_ok());
     }
 
+    /// Tests successfully dispatching a REST API `TOKEN` authorizer request.
+    #[test]
+    fn dispatch_token_authorizer_request_ok() {
+        let response = codec::AuthorizerResponse::allow("user");
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = http_dispatcher(host_dispatcher);
+
+        let body = valid_token_authorizer_request().to_string();
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), body.as_bytes());
+        assert!(result.is_ok());
+
+        let response: serde_json::Value = serde_json::from_slice(&result.unwrap()).unwrap();
+        assert_eq!("user", response["principalId"]);
+        let statement = &response["policyDocument"]["Statement"][0];
+        assert_eq!("Allow", statement["Effect"]);
+        assert_eq!(
+            "arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/pets",
+            statement["Resource"][0]
+        );
+    }
+
+    /// Tests that an HTTP API 2.0 authorizer request gets a simple response when configured.
+    #[test]
+    fn dispatch_http_api_authorizer_request_simple_ok() {
+        let response = codec::AuthorizerResponse::deny("user");
+        let host_dispatcher = mock_host_dispatcher(response);
+        let mut values = std::collections::HashMap::new();
+        values.insert("WASCC_AUTHORIZER_SIMPLE_RESPONSES".into(), "true".into());
+        let dispatcher = HttpRequestDispatcher::new(
+            host_dispatcher,
+            HttpSettings::from_config(&values),
+            ActorCorsSettings::default(),
+        );
+
+        let body = valid_http_api_authorizer_request().to_string();
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), body.as_bytes());
+        assert!(result.is_ok());
+
+        let response: serde_json::Value = serde_json::from_slice(&result.unwrap()).unwrap();
+        assert_eq!(serde_json::json!({"isAuthorized": false}), response);
+    }
+
+    /// Returns the specified CORS settings for the test actor.
+    fn actor_cors_settings(pairs: &[(&str, &str)]) -> ActorCorsSettings {
+        let values = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let cors = ActorCorsSettings::default();
+        cors.write().unwrap().insert(
+            MODULE_ID.into(),
+            cors::CorsSettings::from_config(&values).unwrap().unwrap(),
+        );
+        cors
+    }
+
+    /// Tests that a CORS preflight request is answered without dispatching it to the actor.
+    #[test]
+    fn dispatch_api_gatewayv2_proxy_request_cors_preflight_ok() {
+        let host_dispatcher = error_host_dispatcher();
+        let dispatcher = HttpRequestDispatcher::new(
+            host_dispatcher,
+            HttpSettings::default(),
+            actor_cors_settings(&[("WASCC_CORS_ALLOW_ORIGINS", "https://example.com")]),
+        );
+
+        let mut request = valid_api_gatewayv2_proxy_request();
+        request.request_context.http.method = Some("OPTIONS".into());
+        request
+            .headers
+            .insert("origin".into(), "https://example.com".into());
+        request
+            .headers
+            .insert("access-control-request-method".into(), "PUT".into());
+        let result = dispatcher.dispatch_apigwv2_request(MODULE_ID, request.into());
+        assert!(result.is_ok());
+
+        let response: apigw::ApiGatewayV2httpResponse = result.unwrap().into();
+        assert_eq!(204, response.status_code);
+        assert_eq!(
+            "https://example.com",
+            response.headers.get("access-control-allow-origin").unwrap()
+        );
+    }
+
+    /// Tests that an actor's response to a cross-origin request has CORS headers added.
+    #[test]
+    fn dispatch_api_gatewayv2_proxy_request_cors_ok() {
+        let response = valid_http_response();
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = HttpRequestDispatcher::new(
+            host_dispatcher,
+            HttpSettings::default(),
+            actor_cors_settings(&[("WASCC_CORS_ALLOW_ORIGINS", "*")]),
+        );
+
+        let mut request = valid_api_gatewayv2_proxy_request();
+        request
+            .headers
+            .insert("origin".into(), "https://example.com".into());
+        let result = dispatcher.dispatch_apigwv2_request(MODULE_ID, request.into());
+        assert!(result.is_ok());
+
+        let response: apigw::ApiGatewayV2httpResponse = result.unwrap().into();
+        assert_eq!(
+            "*",
+            response.headers.get("access-control-allow-origin").unwrap()
+        );
+        assert_eq!("test", response.headers.get("server").unwrap());
+    }
+
     /// Tests failing to dispatch an API Gateway v2 proxy request.
     #[test]
     fn dispatch_api_gatewayv2_proxy_request_not_dispatched_error() {
         let host_dispatcher = error_host_dispatcher();
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = dispatcher
             .dispatch_apigwv2_request(MODULE_ID, valid_api_gatewayv2_proxy_request().into());
@@ -453,7 +1397,7 @@ mod tests {
     #[test]
     fn dispatch_api_gatewayv2_proxy_request_deserialization_error() {
         let host_dispatcher = mock_host_dispatcher(RESPONSE_BODY);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = dispatcher
             .dispatch_apigwv2_request(MODULE_ID, valid_api_gatewayv2_proxy_request().into());
@@ -467,18 +1411,35 @@ mod tests {
         }
     }
 
+    /// Tests successfully dispatching a Lambda function URL request encoded as JSON.
+    #[test]
+    fn dispatch_function_url_request_json_ok() {
+        let response = valid_http_response();
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = http_dispatcher(host_dispatcher);
+
+        let body = serde_json::to_vec(&valid_function_url_request()).unwrap();
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
+        assert!(result.is_ok());
+
+        let response: serde_json::Value = serde_json::from_slice(&result.unwrap()).unwrap();
+        assert_eq!(200, response["statusCode"]);
+        assert_eq!("test", response["headers"]["server"]);
+        assert_eq!(serde_json::json!([]), response["cookies"]);
+    }
+
     /// Tests successfully dispatching an ALB target group request encoded as JSON.
     #[test]
     fn dispatch_alb_target_group_request_json_ok() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = serde_json::to_vec(&valid_alb_target_group_request());
         assert!(result.is_ok());
         let body = result.unwrap();
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &body);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
         assert!(result.is_ok());
     }
 
@@ -487,13 +1448,13 @@ mod tests {
     fn dispatch_api_gateway_proxy_request_json_ok() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = serde_json::to_vec(&valid_api_gateway_proxy_request());
         assert!(result.is_ok());
         let body = result.unwrap();
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &body);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
         assert!(result.is_ok());
     }
 
@@ -502,31 +1463,359 @@ mod tests {
     fn dispatch_api_gatewayv2_proxy_request_json_ok() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = serde_json::to_vec(&valid_api_gatewayv2_proxy_request());
         assert!(result.is_ok());
         let body = result.unwrap();
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &body);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
+        assert!(result.is_ok());
+    }
+
+    /// Tests successfully dispatching a classified API Gateway v2 proxy request.
+    #[test]
+    fn dispatch_classified_api_gatewayv2_proxy_request_ok() {
+        let response = valid_http_response();
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = http_dispatcher(host_dispatcher);
+
+        let result = serde_json::to_vec(&valid_api_gatewayv2_proxy_request());
+        assert!(result.is_ok());
+        let event = ClassifiedEvent::new(result.unwrap());
+        assert_eq!(EventSource::ApiGatewayV2, event.source());
+
+        let result = dispatcher.dispatch_classified_event(MODULE_ID, &context(), event);
         assert!(result.is_ok());
     }
 
+    /// Tests failing to dispatch a classified event that isn't JSON as an HTTP-like request.
+    #[test]
+    fn dispatch_classified_raw_event_not_http_error() {
+        let response = valid_http_response();
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = http_dispatcher(host_dispatcher);
+
+        let event = ClassifiedEvent::new(EVENT_BODY.to_vec());
+        let result = dispatcher.dispatch_classified_event(MODULE_ID, &context(), event);
+        assert!(result.is_err());
+
+        let e = result.unwrap_err();
+        assert!(e.is::<NotHttpRequestError>());
+    }
+
     /// Tests failing to dispatch a raw event as an HTTP-like request.
     #[test]
     fn dispatch_raw_event_json_not_http_error() {
         let response = valid_http_response();
         let host_dispatcher = mock_host_dispatcher(response);
-        let dispatcher = HttpRequestDispatcher::new(host_dispatcher);
+        let dispatcher = http_dispatcher(host_dispatcher);
 
         let result = serde_json::to_vec(EVENT_BODY);
         assert!(result.is_ok());
         let body = result.unwrap();
 
-        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &body);
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
         assert!(result.is_err());
 
         let e = result.unwrap_err();
         assert!(e.is::<NotHttpRequestError>());
     }
+
+    /// Represents a `wascc_codec::capabilities::Dispatcher` whose actor fails to handle poison messages.
+    struct SqsWasccDispatcher {
+        dispatched: Arc<AtomicUsize>,
+    }
+
+    impl wascc_codec::capabilities::Dispatcher for SqsWasccDispatcher {
+        fn dispatch(
+            &self,
+            _actor: &str,
+            _op: &str,
+            msg: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            self.dispatched.fetch_add(1, Ordering::SeqCst);
+            let event: codec::SqsMessageEvent = deserialize(msg)?;
+            let response = if event.message.body == "POISON" {
+                codec::Response {
+                    error: Some(codec::FunctionError {
+                        error_type: "PoisonMessage".into(),
+                        error_message: ERROR_MESSAGE.into(),
+                        data: vec![],
+                    }),
+                    ..Default::default()
+                }
+            } else {
+                codec::Response::empty()
+            };
+            serialize(response)
+        }
+    }
+
+    /// Returns an Amazon SQS event with messages with the specified bodies.
+    fn sqs_event(queue_arn: &str, bodies: &[&str]) -> Vec<u8> {
+        let records: Vec<serde_json::Value> = bodies
+            .iter()
+            .enumerate()
+            .map(|(i, body)| {
+                serde_json::json!({
+                    "messageId": format!("MESSAGE_ID_{}", i),
+                    "body": body,
+                    "eventSource": "aws:sqs",
+                    "eventSourceARN": queue_arn,
+                })
+            })
+            .collect();
+        serde_json::to_vec(&serde_json::json!({ "Records": records })).unwrap()
+    }
+
+    /// Dispatches an Amazon SQS event, returning the batch item failures and the number of dispatches.
+    fn dispatch_sqs_event(body: &[u8]) -> anyhow::Result<(serde_json::Value, usize)> {
+        let dispatched = Arc::new(AtomicUsize::new(0));
+        let host_dispatcher: HostDispatcher =
+            Arc::new(std::sync::RwLock::new(Box::new(SqsWasccDispatcher {
+                dispatched: Arc::clone(&dispatched),
+            })));
+        let dispatcher = SqsEventDispatcher::new(host_dispatcher);
+
+        let response = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), body)?;
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        Ok((
+            response["batchItemFailures"].clone(),
+            dispatched.load(Ordering::SeqCst),
+        ))
+    }
+
+    #[test]
+    fn dispatch_sqs_event_ok() {
+        let body = sqs_event(
+            "arn:aws:sqs:us-east-2:123456789012:my-queue",
+            &["Hello", "world"],
+        );
+        let (failures, dispatched) = dispatch_sqs_event(&body).unwrap();
+        assert_eq!(serde_json::json!([]), failures);
+        assert_eq!(2, dispatched);
+    }
+
+    #[test]
+    fn dispatch_sqs_event_partial_failure() {
+        let body = sqs_event(
+            "arn:aws:sqs:us-east-2:123456789012:my-queue",
+            &["Hello", "POISON", "world"],
+        );
+        let (failures, dispatched) = dispatch_sqs_event(&body).unwrap();
+        assert_eq!(
+            serde_json::json!([{"itemIdentifier": "MESSAGE_ID_1"}]),
+            failures
+        );
+        assert_eq!(3, dispatched);
+    }
+
+    #[test]
+    fn dispatch_sqs_event_fifo_failure() {
+        let body = sqs_event(
+            "arn:aws:sqs:us-east-2:123456789012:my-queue.fifo",
+            &["Hello", "POISON", "world"],
+        );
+        let (failures, dispatched) = dispatch_sqs_event(&body).unwrap();
+        assert_eq!(
+            serde_json::json!([
+                {"itemIdentifier": "MESSAGE_ID_1"},
+                {"itemIdentifier": "MESSAGE_ID_2"},
+            ]),
+            failures
+        );
+        assert_eq!(2, dispatched);
+    }
+
+    #[test]
+    fn dispatch_sqs_event_not_sqs_error() {
+        let result = dispatch_sqs_event(EVENT_BODY);
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        assert!(e.is::<NotSqsEventError>());
+    }
+
+    /// Represents a `wascc_codec::capabilities::Dispatcher` whose actor fails to handle poison records.
+    struct StreamWasccDispatcher {
+        dispatched: Arc<AtomicUsize>,
+    }
+
+    impl wascc_codec::capabilities::Dispatcher for StreamWasccDispatcher {
+        fn dispatch(
+            &self,
+            _actor: &str,
+            op: &str,
+            msg: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            self.dispatched.fetch_add(1, Ordering::SeqCst);
+            let poison = match op {
+                codec::OP_HANDLE_KINESIS_RECORD => {
+                    let event: codec::KinesisRecordEvent = deserialize(msg)?;
+                    event.record.data == b"POISON"
+                }
+                codec::OP_HANDLE_DYNAMODB_RECORD => {
+                    let event: codec::DynamoDbRecordEvent = deserialize(msg)?;
+                    event.record.new_image.get("Message")
+                        == Some(&codec::AttributeValue::S("POISON".into()))
+                }
+                _ => return Err(anyhow!("Unexpected operation: {}", op).into()),
+            };
+            let response = if poison {
+                codec::Response {
+                    error: Some(codec::FunctionError {
+                        error_type: "PoisonRecord".into(),
+                        error_message: ERROR_MESSAGE.into(),
+                        data: vec![],
+                    }),
+                    ..Default::default()
+                }
+            } else {
+                codec::Response::empty()
+            };
+            serialize(response)
+        }
+    }
+
+    /// Dispatches a stream event, returning the batch item failures and the number of dispatches.
+    fn dispatch_stream_event(body: &[u8]) -> anyhow::Result<(serde_json::Value, usize)> {
+        let dispatched = Arc::new(AtomicUsize::new(0));
+        let host_dispatcher: HostDispatcher =
+            Arc::new(std::sync::RwLock::new(Box::new(StreamWasccDispatcher {
+                dispatched: Arc::clone(&dispatched),
+            })));
+        let dispatcher = StreamEventDispatcher::new(host_dispatcher);
+
+        let response = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), body)?;
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        Ok((
+            response["batchItemFailures"].clone(),
+            dispatched.load(Ordering::SeqCst),
+        ))
+    }
+
+    /// Returns an Amazon Kinesis event with records with the specified data.
+    fn kinesis_event(data: &[&str]) -> Vec<u8> {
+        let records: Vec<serde_json::Value> = data
+            .iter()
+            .enumerate()
+            .map(|(i, data)| {
+                serde_json::json!({
+                    "kinesis": {
+                        "partitionKey": "1",
+                        "sequenceNumber": format!("SEQUENCE_NUMBER_{}", i),
+                        "data": base64::encode(data),
+                    },
+                    "eventSource": "aws:kinesis",
+                })
+            })
+            .collect();
+        serde_json::to_vec(&serde_json::json!({ "Records": records })).unwrap()
+    }
+
+    #[test]
+    fn dispatch_kinesis_event_ok() {
+        let body = kinesis_event(&["Hello", "world"]);
+        let (failures, dispatched) = dispatch_stream_event(&body).unwrap();
+        assert_eq!(serde_json::json!([]), failures);
+        assert_eq!(2, dispatched);
+    }
+
+    #[test]
+    fn dispatch_kinesis_event_stops_at_failure() {
+        let body = kinesis_event(&["Hello", "POISON", "world"]);
+        let (failures, dispatched) = dispatch_stream_event(&body).unwrap();
+        assert_eq!(
+            serde_json::json!([{"itemIdentifier": "SEQUENCE_NUMBER_1"}]),
+            failures
+        );
+        assert_eq!(2, dispatched);
+    }
+
+    #[test]
+    fn dispatch_dynamodb_event_stops_at_failure() {
+        let records: Vec<serde_json::Value> = ["Hello", "POISON", "world"]
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                serde_json::json!({
+                    "eventName": "INSERT",
+                    "eventSource": "aws:dynamodb",
+                    "dynamodb": {
+                        "Keys": {"Id": {"N": i.to_string()}},
+                        "NewImage": {"Message": {"S": message}},
+                        "SequenceNumber": format!("SEQUENCE_NUMBER_{}", i),
+                    },
+                })
+            })
+            .collect();
+        let body = serde_json::to_vec(&serde_json::json!({ "Records": records })).unwrap();
+
+        let (failures, dispatched) = dispatch_stream_event(&body).unwrap();
+        assert_eq!(
+            serde_json::json!([{"itemIdentifier": "SEQUENCE_NUMBER_1"}]),
+            failures
+        );
+        assert_eq!(2, dispatched);
+    }
+
+    #[test]
+    fn dispatch_stream_event_not_stream_error() {
+        let body = serde_json::to_vec(&valid_alb_target_group_request()).unwrap();
+        let result = dispatch_stream_event(&body);
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        assert!(e.is::<NotStreamEventError>());
+    }
+
+    #[test]
+    fn dispatch_websocket_event_ok() {
+        let mock = Arc::new(MockWasccDispatcher::new(codec::WebSocketResponse {
+            status_code: 200,
+            body: b"pong".to_vec(),
+        }));
+        let host_dispatcher: HostDispatcher = Arc::new(std::sync::RwLock::new(Box::new(
+            SharedWasccDispatcher(Arc::clone(&mock)),
+        )));
+        let dispatcher = WebSocketEventDispatcher::new(host_dispatcher);
+
+        let mut request = valid_websocket_request("$default", "MESSAGE");
+        request["body"] = "ping".into();
+        let body = serde_json::to_vec(&request).unwrap();
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
+        assert!(result.is_ok());
+
+        let response: serde_json::Value = serde_json::from_slice(&result.unwrap()).unwrap();
+        assert_eq!(
+            serde_json::json!({"statusCode": 200, "body": "pong", "isBase64Encoded": false}),
+            response
+        );
+
+        assert_eq!(
+            Some(codec::OP_HANDLE_WEBSOCKET_EVENT.into()),
+            *mock.op.read().unwrap()
+        );
+        let event: codec::WebSocketEvent =
+            deserialize(mock.msg.read().unwrap().as_ref().unwrap()).unwrap();
+        assert_eq!("$default", event.route_key);
+        assert_eq!("L0SM9cOFvHcCIhw=", event.connection_id);
+        assert_eq!(b"ping".to_vec(), event.body);
+        assert_eq!(context(), event.context);
+    }
+
+    #[test]
+    fn dispatch_websocket_event_not_websocket_error() {
+        let response = codec::WebSocketResponse::ok();
+        let host_dispatcher = mock_host_dispatcher(response);
+        let dispatcher = WebSocketEventDispatcher::new(host_dispatcher);
+
+        let body = serde_json::to_vec(&valid_api_gateway_proxy_request()).unwrap();
+        let result = dispatcher.dispatch_invocation_event(MODULE_ID, &context(), &body);
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        assert!(e.is::<NotWebSocketEventError>());
+    }
 }
@@ -0,0 +1,335 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+
+const EXTENSION_IDENTIFIER_HEADER_NAME: &str = "Lambda-Extension-Identifier";
+const EXTENSION_NAME_HEADER_NAME: &str = "Lambda-Extension-Name";
+
+/// The name the runtime registers as an internal extension under.
+pub(crate) const EXTENSION_NAME: &str = "wascc-runtime";
+
+/// Represents an AWS Lambda Extensions API client.
+pub(crate) trait ExtensionClient {
+    /// Registers an internal extension for the `INVOKE` event.
+    /// Internal extensions can't register for the `SHUTDOWN` event.
+    /// Returns the extension identifier.
+    fn register(&self) -> anyhow::Result<String>;
+
+    /// Returns the next AWS Lambda extension event.
+    fn next_event(&self, extension_id: &str) -> anyhow::Result<ExtensionEvent>;
+}
+
+/// Represents an AWS Lambda extension event.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "eventType", rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum ExtensionEvent {
+    /// A function invocation.
+    #[serde(rename_all = "camelCase")]
+    Invoke {
+        #[serde(default)]
+        request_id: String,
+        #[serde(default)]
+        deadline_ms: u64,
+    },
+}
+
+/// Represents an AWS Lambda Extensions API client.
+pub(crate) struct RuntimeExtensionClient {
+    endpoint: String,
+    http_client: reqwest::blocking::Client,
+    name: String,
+    user_agent: String,
+}
+
+impl RuntimeExtensionClient {
+    /// Creates a new `RuntimeExtensionClient` with the specified AWS Lambda runtime API endpoint.
+    pub fn new(endpoint: &str) -> anyhow::Result<Self> {
+        Self::with_name(endpoint, EXTENSION_NAME)
+    }
+
+    /// Creates a new `RuntimeExtensionClient` with the specified AWS Lambda runtime API endpoint
+    /// that registers an extension with the specified name.
+    pub fn with_name(endpoint: &str, name: &str) -> anyhow::Result<Self> {
+        // The long-poll for the next extension event must not time out.
+        let http_client = reqwest::blocking::Client::builder().timeout(None).build()?;
+
+        Ok(Self {
+            endpoint: endpoint.into(),
+            http_client,
+            name: name.into(),
+            user_agent: format!("AWS_Lambda_waSCC/{}", env!("CARGO_PKG_VERSION")),
+        })
+    }
+
+    fn register_path() -> String {
+        "2020-01-01/extension/register".into()
+    }
+
+    fn next_event_path() -> String {
+        "2020-01-01/extension/event/next".into()
+    }
+}
+
+impl ExtensionClient for RuntimeExtensionClient {
+    /// Registers an internal extension for the `INVOKE` event.
+    fn register(&self) -> anyhow::Result<String> {
+        // https://docs.aws.amazon.com/lambda/latest/dg/runtimes-extensions-api.html#extensions-registration-api-a
+        let url = format!("{}/{}", self.endpoint, Self::register_path());
+        let resp = self
+            .http_client
+            .post(&url)
+            .header(USER_AGENT, self.user_agent.clone())
+            .header(EXTENSION_NAME_HEADER_NAME, self.name.as_str())
+            .json(&serde_json::json!({
+                "events": ["INVOKE"],
+            }))
+            .send()?;
+        let status = resp.status();
+        info!(
+            "POST {} {} {}",
+            url,
+            status.as_str(),
+            status.canonical_reason().unwrap_or("Unknown")
+        );
+        if !status.is_success() {
+            return Err(anyhow!("Extension registration failed: {}", status));
+        }
+
+        match resp.headers().get(EXTENSION_IDENTIFIER_HEADER_NAME) {
+            Some(extension_id) => Ok(extension_id.to_str()?.into()),
+            None => Err(anyhow!("No extension identifier")),
+        }
+    }
+
+    /// Returns the next AWS Lambda extension event.
+    fn next_event(&self, extension_id: &str) -> anyhow::Result<ExtensionEvent> {
+        // https://docs.aws.amazon.com/lambda/latest/dg/runtimes-extensions-api.html#extensions-api-next
+        let url = format!("{}/{}", self.endpoint, Self::next_event_path());
+        let resp = self
+            .http_client
+            .get(&url)
+            .header(USER_AGENT, self.user_agent.clone())
+            .header(EXTENSION_IDENTIFIER_HEADER_NAME, extension_id)
+            .send()?;
+        let status = resp.status();
+        debug!(
+            "GET {} {} {}",
+            url,
+            status.as_str(),
+            status.canonical_reason().unwrap_or("Unknown")
+        );
+        if !status.is_success() {
+            return Err(anyhow!("Extension event failed: {}", status));
+        }
+
+        Ok(resp.json()?)
+    }
+}
+
+/// Listens for AWS Lambda extension events.
+pub(crate) struct ExtensionListener {
+    client: Box<dyn ExtensionClient + Send>,
+    extension_id: String,
+}
+
+impl ExtensionListener {
+    /// Registers an extension using the specified client.
+    /// Registration must complete before the runtime polls for its first invocation event.
+    pub fn register(client: Box<dyn ExtensionClient + Send>) -> anyhow::Result<Self> {
+        let extension_id = client.register()?;
+
+        Ok(Self {
+            client,
+            extension_id,
+        })
+    }
+
+    /// Returns the extension identifier.
+    pub fn extension_id(&self) -> &str {
+        self.extension_id.as_str()
+    }
+
+    /// Acknowledges `INVOKE` events by polling for the next event.
+    /// The AWS Lambda machinery waits for every registered extension to poll before completing an invocation.
+    /// Returns only on error.
+    pub fn run(&self) -> anyhow::Result<()> {
+        loop {
+            match self.client.next_event(&self.extension_id)? {
+                ExtensionEvent::Invoke {
+                    request_id,
+                    deadline_ms,
+                } => {
+                    debug!("Extension invoke {} (deadline {})", request_id, deadline_ms);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use std::sync::RwLock;
+
+    const EXTENSION_ID: &str = "EXTENSION_ID";
+
+    /// Returns the server's endpoint.
+    fn endpoint(server: &Server) -> String {
+        let ep = server.url_str("");
+        ep.trim_end_matches('/').into()
+    }
+
+    /// Represents a mock Extensions API client that returns a list of events.
+    struct MockExtensionClient {
+        events: RwLock<Vec<serde_json::Value>>,
+    }
+
+    impl ExtensionClient for MockExtensionClient {
+        /// Registers an extension.
+        fn register(&self) -> anyhow::Result<String> {
+            Ok(EXTENSION_ID.into())
+        }
+
+        /// Returns the next AWS Lambda extension event.
+        fn next_event(&self, _extension_id: &str) -> anyhow::Result<ExtensionEvent> {
+            match self.events.write().unwrap().pop() {
+                Some(event) => Ok(serde_json::from_value(event)?),
+                None => Err(anyhow!("No more events")),
+            }
+        }
+    }
+
+    #[test]
+    fn runtime_extension_client_register() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!("/{}", RuntimeExtensionClient::register_path())),
+                request::headers(contains(entry("lambda-extension-name", EXTENSION_NAME))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "events": ["INVOKE"],
+                })))),
+            ])
+            .respond_with(
+                status_code(200).insert_header("Lambda-Extension-Identifier", EXTENSION_ID),
+            ),
+        );
+
+        let client = RuntimeExtensionClient::new(&endpoint(&server)).unwrap();
+        let result = client.register();
+        assert!(result.is_ok());
+        assert_eq!(EXTENSION_ID, result.unwrap());
+    }
+
+    #[test]
+    fn runtime_extension_client_register_error() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!("/{}", RuntimeExtensionClient::register_path())),
+            ])
+            .respond_with(status_code(403)),
+        );
+
+        let client = RuntimeExtensionClient::new(&endpoint(&server)).unwrap();
+        let result = client.register();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn runtime_extension_client_next_event_invoke() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("GET"),
+                request::path(format!("/{}", RuntimeExtensionClient::next_event_path())),
+                request::headers(contains(entry("lambda-extension-identifier", EXTENSION_ID))),
+            ])
+            .respond_with(
+                status_code(200).body(
+                    serde_json::json!({
+                        "eventType": "INVOKE",
+                        "requestId": "REQUEST_ID",
+                        "deadlineMs": 1581512138111u64,
+                        "invokedFunctionArn": "arn:aws:lambda:us-west-2:123456789012:function:FUNCTION",
+                    })
+                    .to_string(),
+                ),
+            ),
+        );
+
+        let client = RuntimeExtensionClient::new(&endpoint(&server)).unwrap();
+        let result = client.next_event(EXTENSION_ID);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            ExtensionEvent::Invoke {
+                request_id,
+                deadline_ms,
+            } => {
+                assert_eq!("REQUEST_ID", request_id);
+                assert_eq!(1581512138111, deadline_ms);
+            }
+        }
+    }
+
+    #[test]
+    fn extension_listener_run() {
+        let client = MockExtensionClient {
+            events: RwLock::new(vec![
+                serde_json::json!({
+                    "eventType": "INVOKE",
+                    "requestId": "REQUEST_ID_2",
+                    "deadlineMs": 2,
+                }),
+                serde_json::json!({
+                    "eventType": "INVOKE",
+                    "requestId": "REQUEST_ID_1",
+                    "deadlineMs": 1,
+                    "invokedFunctionArn": "arn:aws:lambda:us-west-2:123456789012:function:FUNCTION",
+                }),
+            ]),
+        };
+
+        let listener = ExtensionListener::register(Box::new(client)).unwrap();
+        assert_eq!(EXTENSION_ID, listener.extension_id());
+        // Events are acknowledged until the client runs out of them.
+        let result = listener.run();
+        assert!(result.is_err());
+        assert_eq!("No more events", result.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn extension_listener_run_unknown_event() {
+        let client = MockExtensionClient {
+            events: RwLock::new(vec![serde_json::json!({
+                "eventType": "SHUTDOWN",
+                "shutdownReason": "spindown",
+                "deadlineMs": 1,
+            })]),
+        };
+
+        let listener = ExtensionListener::register(Box::new(client)).unwrap();
+        let result = listener.run();
+        assert!(result.is_err());
+    }
+}
@@ -17,13 +17,318 @@
 //
 
 use aws_lambda_events::event::{alb, apigw};
+use serde::Serialize;
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use crate::compression::ContentEncoding;
+
+/// The request header that lists the content encodings acceptable to the client.
+const ACCEPT_ENCODING_HEADER: &str = "accept-encoding";
+
+/// The response header that describes the encoding of a compressed body.
+const CONTENT_ENCODING_HEADER: &str = "content-encoding";
+
+/// The header that describes the size of a body.
+const CONTENT_LENGTH_HEADER: &str = "content-length";
+
+/// The header that describes the media type of a body.
+const CONTENT_TYPE_HEADER: &str = "content-type";
+
+/// The response header that lists the request headers that select a response.
+const VARY_HEADER: &str = "vary";
+
+/// The request header that carries cookies.
+const COOKIE_HEADER: &str = "cookie";
+
+/// The response header that sets a cookie.
+const SET_COOKIE_HEADER: &str = "set-cookie";
+
+/// Separates the values of a repeated header in an actor's HTTP request or response.
+const HEADER_VALUE_SEPARATOR: char = '\n';
+
+/// Separates the cookies in an actor's HTTP request's `Cookie` header.
+const COOKIE_SEPARATOR: &str = "; ";
+
+/// The prefix of the well-known request headers that carry the request context to an actor.
+const CONTEXT_HEADER_PREFIX: &str = "x-wascc-";
+
+/// The request header that carries the API's ID.
+pub(crate) const API_ID_HEADER: &str = "x-wascc-api-id";
+
+/// The request header that carries the authorizer's output, e.g. JWT claims, as JSON.
+pub(crate) const AUTHORIZER_HEADER: &str = "x-wascc-authorizer";
+
+/// The request header that carries the path parameters as a JSON object.
+const PATH_PARAMETERS_HEADER: &str = "x-wascc-path-parameters";
+
+/// The request header that carries the request's ID.
+pub(crate) const REQUEST_ID_HEADER: &str = "x-wascc-request-id";
+
+/// The request header that carries the API Gateway resource path, e.g. `/pets/{id}`.
+const RESOURCE_PATH_HEADER: &str = "x-wascc-resource-path";
+
+/// The request header that carries the API Gateway v2 route key, e.g. `GET /pets/{id}`.
+const ROUTE_KEY_HEADER: &str = "x-wascc-route-key";
+
+/// The request header that carries the client's source IP address.
+pub(crate) const SOURCE_IP_HEADER: &str = "x-wascc-source-ip";
+
+/// The request header that carries the API stage.
+const STAGE_HEADER: &str = "x-wascc-stage";
+
+/// The request header that carries the stage variables as a JSON object.
+const STAGE_VARIABLES_HEADER: &str = "x-wascc-stage-variables";
+
+/// The request header that carries the ALB target group's ARN.
+const TARGET_GROUP_ARN_HEADER: &str = "x-wascc-target-group-arn";
+
+/// The configuration value listing the binary media types.
+const BINARY_MEDIA_TYPES_KEY: &str = "WASCC_BINARY_MEDIA_TYPES";
+
+/// The configuration value enabling compression of response bodies.
+const COMPRESSION_KEY: &str = "WASCC_HTTP_COMPRESSION";
+
+/// The configuration value holding the minimum size, in bytes, of a response body to compress.
+const COMPRESSION_THRESHOLD_KEY: &str = "WASCC_HTTP_COMPRESSION_THRESHOLD";
+
+/// The default minimum size, in bytes, of a response body to compress.
+const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// The configuration value enabling simple responses to HTTP API 2.0 authorizer requests.
+const AUTHORIZER_SIMPLE_RESPONSES_KEY: &str = "WASCC_AUTHORIZER_SIMPLE_RESPONSES";
+
+/// The configuration value holding a base path to strip from API Gateway request paths.
+const BASE_PATH_KEY: &str = "WASCC_HTTP_BASE_PATH";
+
+/// The configuration value enabling use of the `{proxy}` path parameter as the request path.
+const PROXY_PATH_KEY: &str = "WASCC_HTTP_PROXY_PATH";
+
+/// The configuration value enabling stripping of the stage from API Gateway request paths.
+const STRIP_STAGE_KEY: &str = "WASCC_HTTP_STRIP_STAGE";
+
+/// The path parameter of a greedy `{proxy+}` resource or route.
+const PROXY_PATH_PARAMETER: &str = "proxy";
+
+/// The name of an API Gateway HTTP API's default stage, which never appears in paths.
+const DEFAULT_STAGE: &str = "$default";
+
+/// Represents the settings of the HTTP request provider.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HttpSettings {
+    /// Media types whose response bodies are always base64 encoded, e.g. `image/*`.
+    binary_media_types: Vec<String>,
+    /// A base path, e.g. `/api/v1`, stripped from API Gateway request paths.
+    base_path: Option<String>,
+    /// Whether or not the `{proxy}` path parameter, if present, is used as the request path.
+    proxy_path: bool,
+    /// Whether or not the stage is stripped from API Gateway request paths.
+    strip_stage: bool,
+    /// Whether or not response bodies are compressed.
+    compression: bool,
+    /// The minimum size, in bytes, of a response body to compress.
+    compression_threshold: usize,
+    /// Whether or not HTTP API 2.0 authorizer requests get simple responses rather than IAM policies.
+    authorizer_simple_responses: bool,
+}
+
+impl HttpSettings {
+    /// Creates a new `HttpSettings` from the specified capability configuration values.
+    pub fn from_config(values: &HashMap<String, String>) -> Self {
+        Self {
+            binary_media_types: values
+                .get(BINARY_MEDIA_TYPES_KEY)
+                .map(|s| {
+                    s.split(',')
+                        .map(|t| t.trim().to_ascii_lowercase())
+                        .filter(|t| !t.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            base_path: values
+                .get(BASE_PATH_KEY)
+                .map(|s| s.trim().trim_matches('/'))
+                .filter(|s| !s.is_empty())
+                .map(|s| format!("/{}", s)),
+            proxy_path: flag(values, PROXY_PATH_KEY),
+            strip_stage: flag(values, STRIP_STAGE_KEY),
+            compression: flag(values, COMPRESSION_KEY),
+            compression_threshold: values
+                .get(COMPRESSION_THRESHOLD_KEY)
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(DEFAULT_COMPRESSION_THRESHOLD),
+            authorizer_simple_responses: flag(values, AUTHORIZER_SIMPLE_RESPONSES_KEY),
+        }
+    }
+
+    /// Returns whether or not HTTP API 2.0 authorizer requests get simple responses.
+    pub fn authorizer_simple_responses(&self) -> bool {
+        self.authorizer_simple_responses
+    }
+
+    /// Returns an API Gateway request path rewritten so that an actor sees the same paths wherever it is deployed.
+    /// The `{proxy}` path parameter, if used and present, takes precedence.
+    /// Otherwise any stage and then any base path are stripped from the start of the path.
+    fn request_path(
+        &self,
+        path: String,
+        stage: Option<&str>,
+        path_parameters: &HashMap<String, String>,
+    ) -> String {
+        if self.proxy_path {
+            if let Some(proxy) = path_parameters.get(PROXY_PATH_PARAMETER) {
+                return format!("/{}", proxy.trim_start_matches('/'));
+            }
+        }
+
+        let mut path = path.as_str();
+        if self.strip_stage {
+            if let Some(stage) = stage.filter(|s| !s.is_empty() && *s != DEFAULT_STAGE) {
+                path = strip_path_prefix(path, &format!("/{}", stage));
+            }
+        }
+        if let Some(base_path) = &self.base_path {
+            path = strip_path_prefix(path, base_path);
+        }
+
+        path.into()
+    }
+
+    /// Returns an actor's HTTP response with its body's encoding determined by its `Content-Type`.
+    /// The body is compressed if enabled and the request's `Accept-Encoding` header allows it.
+    pub fn response(
+        &self,
+        mut response: wascc_codec::http::Response,
+        accept_encoding: Option<&str>,
+    ) -> HttpResponse {
+        let content_type = header_value(&response.header, CONTENT_TYPE_HEADER)
+            .map(|value| value.to_ascii_lowercase());
+        let mut is_binary = self.is_binary(content_type.as_deref(), &response.body);
+
+        let encoding = accept_encoding
+            .and_then(|a| self.content_encoding(content_type.as_deref(), &response, a));
+        if let Some(encoding) = encoding {
+            match encoding.compress(&response.body) {
+                Ok(body) => {
+                    debug!(
+                        "Compressed response body from {} to {} bytes using {}",
+                        response.body.len(),
+                        body.len(),
+                        encoding.name()
+                    );
+                    response.body = body;
+                    compressed_headers(&mut response.header, encoding);
+                    // A compressed body is always binary.
+                    is_binary = true;
+                }
+                Err(e) => warn!("Failed to compress response body: {}", e),
+            }
+        }
+
+        HttpResponse {
+            response,
+            is_binary,
+        }
+    }
+
+    /// Returns the content encoding with which to compress a response, if any.
+    /// Only textual bodies at least as large as the threshold that are not already encoded are compressed.
+    fn content_encoding(
+        &self,
+        content_type: Option<&str>,
+        response: &wascc_codec::http::Response,
+        accept_encoding: &str,
+    ) -> Option<ContentEncoding> {
+        if !self.compression
+            || response.body.len() < self.compression_threshold
+            || header_value(&response.header, CONTENT_ENCODING_HEADER).is_some()
+            || !content_type.map(is_text_media_type).unwrap_or(false)
+        {
+            return None;
+        }
+
+        ContentEncoding::negotiate(accept_encoding)
+    }
+
+    /// Returns whether or not a response body with the specified content type is base64 encoded.
+    /// A body with a binary media type or a non-textual content type is binary.
+    /// A body with no content type is binary only if it is not valid UTF-8.
+    fn is_binary(&self, content_type: Option<&str>, body: &[u8]) -> bool {
+        match content_type {
+            Some(content_type) => {
+                self.binary_media_types
+                    .iter()
+                    .any(|t| media_type_matches(t, content_type))
+                    || !is_text_media_type(content_type)
+            }
+            None => std::str::from_utf8(body).is_err(),
+        }
+    }
+}
+
+/// Represents an actor's HTTP response and whether or not its body is binary.
+#[derive(Debug)]
+pub(crate) struct HttpResponse {
+    response: wascc_codec::http::Response,
+    is_binary: bool,
+}
+
+impl HttpResponse {
+    /// Returns the actor's HTTP response and whether or not its body is binary.
+    pub fn into_parts(self) -> (wascc_codec::http::Response, bool) {
+        (self.response, self.is_binary)
+    }
+}
+
+/// Represents the request context passed to an actor in well-known request headers.
+#[derive(Debug, Default)]
+pub(crate) struct RequestContextHeaders(Vec<(&'static str, String)>);
+
+impl RequestContextHeaders {
+    /// Adds a context value, if present.
+    pub fn add(mut self, name: &'static str, value: Option<String>) -> Self {
+        if let Some(value) = value {
+            self.0.push((name, value));
+        }
+        self
+    }
+
+    /// Adds a map of context values as a JSON object, if not empty.
+    pub fn add_map<V: Serialize>(self, name: &'static str, map: &HashMap<String, V>) -> Self {
+        if map.is_empty() {
+            return self;
+        }
+        self.add_json(name, Some(map))
+    }
+
+    /// Adds a context value as JSON, if present.
+    pub fn add_json<T: Serialize>(self, name: &'static str, value: Option<&T>) -> Self {
+        let value = value.and_then(|v| serde_json::to_string(v).ok());
+        self.add(name, value)
+    }
+
+    /// Adds the context to an actor's request headers.
+    /// Any client-supplied header with the well-known prefix is first removed so that actors can trust the context.
+    pub fn apply(self, header: &mut HashMap<String, String>) {
+        header.retain(|name, _| !name.to_ascii_lowercase().starts_with(CONTEXT_HEADER_PREFIX));
+        for (name, value) in self.0 {
+            header.insert(name.into(), value);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct AlbTargetGroupRequestWrapper(alb::AlbTargetGroupRequest);
 
+impl AlbTargetGroupRequestWrapper {
+    /// Returns whether or not the request arrived with multi-value headers enabled on the target group.
+    pub fn is_multi_value(&self) -> bool {
+        !self.0.multi_value_headers.is_empty()
+            || !self.0.multi_value_query_string_parameters.is_empty()
+    }
+}
+
 impl From<alb::AlbTargetGroupRequest> for AlbTargetGroupRequestWrapper {
     /// Converts an ALB target group request to an instance of the wrapper type.
     fn from(request: alb::AlbTargetGroupRequest) -> Self {
@@ -36,7 +341,17 @@ impl TryFrom<AlbTargetGroupRequestWrapper> for wascc_codec::http::Request {
 
     /// Attempts conversion of an ALB target group request to an actor's HTTP request.
     fn try_from(request: AlbTargetGroupRequestWrapper) -> anyhow::Result<Self> {
-        let query_string = query_string(request.0.query_string_parameters);
+        let query_string = query_string(
+            request.0.query_string_parameters,
+            request.0.multi_value_query_string_parameters,
+        );
+        let mut header = request_headers(request.0.headers, request.0.multi_value_headers);
+        RequestContextHeaders::default()
+            .add(
+                TARGET_GROUP_ARN_HEADER,
+                request.0.request_context.elb.target_group_arn,
+            )
+            .apply(&mut header);
 
         Ok(wascc_codec::http::Request {
             method: request
@@ -48,7 +363,7 @@ impl TryFrom<AlbTargetGroupRequestWrapper> for wascc_codec::http::Request {
                 .path
                 .ok_or_else(|| anyhow!("Missing path in ALB target group request"))?,
             query_string,
-            header: request.0.headers,
+            header,
             body: match request.0.body {
                 Some(s) if request.0.is_base64_encoded => base64::decode(s)?,
                 Some(s) => s.into_bytes(),
@@ -75,17 +390,41 @@ impl From<alb::AlbTargetGroupResponse> for AlbTargetGroupResponseWrapper {
     }
 }
 
-impl TryFrom<wascc_codec::http::Response> for AlbTargetGroupResponseWrapper {
+impl AlbTargetGroupResponseWrapper {
+    /// Attempts conversion of an actor's HTTP response to an ALB response
+    /// for a target group with multi-value headers enabled.
+    pub fn try_from_multi_value(response: HttpResponse) -> anyhow::Result<Self> {
+        let (response, is_binary) = response.into_parts();
+        let (body, is_base64_encoded) = encode_body(response.body, is_binary);
+
+        Ok(alb::AlbTargetGroupResponse {
+            status_code: response.status_code as i64,
+            status_description: Some(response.status),
+            headers: HashMap::new(),
+            multi_value_headers: response
+                .header
+                .iter()
+                .map(|(name, value)| (name.clone(), header_values(value)))
+                .collect(),
+            body,
+            is_base64_encoded,
+        }
+        .into())
+    }
+}
+
+impl TryFrom<HttpResponse> for AlbTargetGroupResponseWrapper {
     type Error = anyhow::Error;
 
     /// Attempts conversion of an actor's HTTP response to an ALB response.
-    fn try_from(response: wascc_codec::http::Response) -> anyhow::Result<Self> {
-        let (body, is_base64_encoded) = body_string(response.body);
+    fn try_from(response: HttpResponse) -> anyhow::Result<Self> {
+        let (response, is_binary) = response.into_parts();
+        let (body, is_base64_encoded) = encode_body(response.body, is_binary);
 
         Ok(alb::AlbTargetGroupResponse {
             status_code: response.status_code as i64,
             status_description: Some(response.status),
-            headers: response.header,
+            headers: joined_headers(response.header),
             multi_value_headers: HashMap::new(),
             body,
             is_base64_encoded,
@@ -97,6 +436,20 @@ impl TryFrom<wascc_codec::http::Response> for AlbTargetGroupResponseWrapper {
 #[derive(Debug)]
 pub(crate) struct ApiGatewayProxyRequestWrapper(apigw::ApiGatewayProxyRequest);
 
+impl ApiGatewayProxyRequestWrapper {
+    /// Returns the request with its path rewritten according to the specified settings.
+    pub fn rewrite_path(mut self, settings: &HttpSettings) -> Self {
+        if let Some(path) = self.0.path.take() {
+            self.0.path = Some(settings.request_path(
+                path,
+                self.0.request_context.stage.as_deref(),
+                &self.0.path_parameters,
+            ));
+        }
+        self
+    }
+}
+
 impl From<apigw::ApiGatewayProxyRequest> for ApiGatewayProxyRequestWrapper {
     /// Converts an API Gateway proxy request to an instance of the wrapper type.
     fn from(request: apigw::ApiGatewayProxyRequest) -> Self {
@@ -109,7 +462,22 @@ impl TryFrom<ApiGatewayProxyRequestWrapper> for wascc_codec::http::Request {
 
     /// Attempts conversion of an API Gateway proxy request to an actor's HTTP request.
     fn try_from(request: ApiGatewayProxyRequestWrapper) -> anyhow::Result<Self> {
-        let query_string = query_string(request.0.query_string_parameters);
+        let query_string = query_string(
+            request.0.query_string_parameters,
+            request.0.multi_value_query_string_parameters,
+        );
+        let mut header = request_headers(request.0.headers, request.0.multi_value_headers);
+        let context = request.0.request_context;
+        RequestContextHeaders::default()
+            .add(REQUEST_ID_HEADER, context.request_id)
+            .add(API_ID_HEADER, context.apiid)
+            .add(STAGE_HEADER, context.stage)
+            .add(SOURCE_IP_HEADER, context.identity.source_ip)
+            .add(RESOURCE_PATH_HEADER, context.resource_path)
+            .add_map(PATH_PARAMETERS_HEADER, &request.0.path_parameters)
+            .add_map(STAGE_VARIABLES_HEADER, &request.0.stage_variables)
+            .add_map(AUTHORIZER_HEADER, &context.authorizer)
+            .apply(&mut header);
 
         Ok(wascc_codec::http::Request {
             method: request
@@ -121,7 +489,7 @@ impl TryFrom<ApiGatewayProxyRequestWrapper> for wascc_codec::http::Request {
                 .path
                 .ok_or_else(|| anyhow!("Missing path in API Gateway proxy request"))?,
             query_string,
-            header: request.0.headers,
+            header,
             body: match request.0.body {
                 Some(s) if request.0.is_base64_encoded.unwrap_or(false) => base64::decode(s)?,
                 Some(s) => s.into_bytes(),
@@ -148,17 +516,19 @@ impl From<apigw::ApiGatewayProxyResponse> for ApiGatewayProxyResponseWrapper {
     }
 }
 
-impl TryFrom<wascc_codec::http::Response> for ApiGatewayProxyResponseWrapper {
+impl TryFrom<HttpResponse> for ApiGatewayProxyResponseWrapper {
     type Error = anyhow::Error;
 
     /// Attempts conversion of an actor's HTTP response to an API Gateway proxy response.
-    fn try_from(response: wascc_codec::http::Response) -> anyhow::Result<Self> {
-        let (body, is_base64_encoded) = body_string(response.body);
+    fn try_from(response: HttpResponse) -> anyhow::Result<Self> {
+        let (response, is_binary) = response.into_parts();
+        let (body, is_base64_encoded) = encode_body(response.body, is_binary);
+        let (headers, multi_value_headers) = response_headers(response.header);
 
         Ok(apigw::ApiGatewayProxyResponse {
             status_code: response.status_code as i64,
-            headers: response.header,
-            multi_value_headers: HashMap::new(),
+            headers,
+            multi_value_headers,
             body,
             is_base64_encoded: Some(is_base64_encoded),
         }
@@ -169,6 +539,20 @@ impl TryFrom<wascc_codec::http::Response> for ApiGatewayProxyResponseWrapper {
 #[derive(Debug)]
 pub(crate) struct ApiGatewayV2ProxyRequestWrapper(apigw::ApiGatewayV2httpRequest);
 
+impl ApiGatewayV2ProxyRequestWrapper {
+    /// Returns the request with its path rewritten according to the specified settings.
+    pub fn rewrite_path(mut self, settings: &HttpSettings) -> Self {
+        if let Some(path) = self.0.request_context.http.path.take() {
+            self.0.request_context.http.path = Some(settings.request_path(
+                path,
+                self.0.request_context.stage.as_deref(),
+                &self.0.path_parameters,
+            ));
+        }
+        self
+    }
+}
+
 impl From<apigw::ApiGatewayV2httpRequest> for ApiGatewayV2ProxyRequestWrapper {
     /// Converts an API Gateway v2 proxy request to an instance of the wrapper type.
     fn from(request: apigw::ApiGatewayV2httpRequest) -> Self {
@@ -181,23 +565,32 @@ impl TryFrom<ApiGatewayV2ProxyRequestWrapper> for wascc_codec::http::Request {
 
     /// Attempts conversion of an API Gateway v2 proxy request to an actor's HTTP request.
     fn try_from(request: ApiGatewayV2ProxyRequestWrapper) -> anyhow::Result<Self> {
-        let query_string = query_string(request.0.query_string_parameters);
+        let query_string = query_string(request.0.query_string_parameters, HashMap::new());
+        let mut header = request.0.headers;
+        add_cookie_header(&mut header, request.0.cookies.unwrap_or_default());
+        let context = request.0.request_context;
+        RequestContextHeaders::default()
+            .add(REQUEST_ID_HEADER, context.request_id)
+            .add(API_ID_HEADER, context.apiid)
+            .add(STAGE_HEADER, context.stage)
+            .add(SOURCE_IP_HEADER, context.http.source_ip)
+            .add(ROUTE_KEY_HEADER, context.route_key)
+            .add_map(PATH_PARAMETERS_HEADER, &request.0.path_parameters)
+            .add_map(STAGE_VARIABLES_HEADER, &request.0.stage_variables)
+            .add_json(AUTHORIZER_HEADER, context.authorizer.as_ref())
+            .apply(&mut header);
 
         Ok(wascc_codec::http::Request {
-            method: request
-                .0
-                .request_context
+            method: context
                 .http
                 .method
                 .ok_or_else(|| anyhow!("Missing method in API Gateway v2 proxy request"))?,
-            path: request
-                .0
-                .request_context
+            path: context
                 .http
                 .path
                 .ok_or_else(|| anyhow!("Missing path in API Gateway v2 proxy request"))?,
             query_string,
-            header: request.0.headers,
+            header,
             body: match request.0.body {
                 Some(s) if request.0.is_base64_encoded => base64::decode(s)?,
                 Some(s) => s.into_bytes(),
@@ -224,20 +617,23 @@ impl From<apigw::ApiGatewayV2httpResponse> for ApiGatewayV2ProxyResponseWrapper
     }
 }
 
-impl TryFrom<wascc_codec::http::Response> for ApiGatewayV2ProxyResponseWrapper {
+impl TryFrom<HttpResponse> for ApiGatewayV2ProxyResponseWrapper {
     type Error = anyhow::Error;
 
     /// Attempts conversion of an actor's HTTP response to an API Gateway v2 proxy response.
-    fn try_from(response: wascc_codec::http::Response) -> anyhow::Result<Self> {
-        let (body, is_base64_encoded) = body_string(response.body);
+    fn try_from(response: HttpResponse) -> anyhow::Result<Self> {
+        let (response, is_binary) = response.into_parts();
+        let (body, is_base64_encoded) = encode_body(response.body, is_binary);
+        let mut headers = response.header;
+        let cookies = remove_set_cookie_headers(&mut headers);
 
         Ok(apigw::ApiGatewayV2httpResponse {
             status_code: response.status_code as i64,
-            headers: response.header,
+            headers: joined_headers(headers),
             multi_value_headers: HashMap::new(),
             body,
             is_base64_encoded: Some(is_base64_encoded),
-            cookies: Vec::new(),
+            cookies,
         }
         .into())
     }
@@ -245,25 +641,247 @@ impl TryFrom<wascc_codec::http::Response> for ApiGatewayV2ProxyResponseWrapper {
 
 /// Returns a string representation of the specified bytes and
 /// a flag indicating whether or not the string is base64 encoded.
-fn body_string(bytes: Vec<u8>) -> (Option<String>, bool) {
+/// Bytes that are not valid UTF-8 are base64 encoded.
+pub(crate) fn body_string(bytes: Vec<u8>) -> (Option<String>, bool) {
+    let is_binary = std::str::from_utf8(&bytes).is_err();
+    encode_body(bytes, is_binary)
+}
+
+/// Returns a string representation of the specified bytes, base64 encoded if binary, and
+/// a flag indicating whether or not the string is base64 encoded.
+pub(crate) fn encode_body(bytes: Vec<u8>, is_binary: bool) -> (Option<String>, bool) {
     if bytes.is_empty() {
         return (None, false);
     }
 
-    match std::str::from_utf8(&bytes) {
-        Ok(s) => (Some(s.into()), false),
-        Err(_) => (Some(base64::encode(bytes)), true),
+    if is_binary {
+        return (Some(base64::encode(bytes)), true);
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => (Some(s), false),
+        Err(e) => (Some(base64::encode(e.into_bytes())), true),
+    }
+}
+
+/// Returns whether or not the media type of a `Content-Type` header value is textual.
+fn is_text_media_type(media_type: &str) -> bool {
+    let (type_, subtype) = split_media_type(media_type);
+    type_ == "text"
+        || subtype == "json"
+        || subtype == "xml"
+        || subtype == "javascript"
+        || subtype == "x-www-form-urlencoded"
+        || subtype.ends_with("+json")
+        || subtype.ends_with("+xml")
+}
+
+/// Returns whether or not a media type matches a binary media type, which may contain wildcards, e.g. `image/*`.
+fn media_type_matches(binary_media_type: &str, media_type: &str) -> bool {
+    let (type_, subtype) = split_media_type(media_type);
+    let (binary_type, binary_subtype) = split_media_type(binary_media_type);
+    (binary_type == "*" || binary_type == type_)
+        && (binary_subtype == "*" || binary_subtype == subtype)
+}
+
+/// Splits a media type into its type and subtype, ignoring any parameters.
+fn split_media_type(media_type: &str) -> (&str, &str) {
+    let media_type = media_type.split(';').next().unwrap_or_default().trim();
+    let mut parts = media_type.splitn(2, '/');
+    (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+    )
+}
+
+/// Returns the value of a header, ignoring the case of its name.
+pub(crate) fn header_value<'a>(
+    headers: &'a HashMap<String, String>,
+    name: &str,
+) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Returns the value of an actor's HTTP request's `Accept-Encoding` header, if present.
+pub(crate) fn accept_encoding(request: &wascc_codec::http::Request) -> Option<String> {
+    header_value(&request.header, ACCEPT_ENCODING_HEADER).map(String::from)
+}
+
+/// Updates an actor's HTTP response headers to describe a body compressed using the specified encoding.
+fn compressed_headers(headers: &mut HashMap<String, String>, encoding: ContentEncoding) {
+    headers.retain(|name, _| !name.eq_ignore_ascii_case(CONTENT_LENGTH_HEADER));
+    headers.insert(CONTENT_ENCODING_HEADER.into(), encoding.name().into());
+    // Caches must key the response on the request's Accept-Encoding header.
+    add_vary_header(headers, "Accept-Encoding");
+}
+
+/// Adds a request header name to an actor's HTTP response's `Vary` header, if not already present.
+pub(crate) fn add_vary_header(headers: &mut HashMap<String, String>, name: &str) {
+    let vary = headers
+        .keys()
+        .find(|n| n.eq_ignore_ascii_case(VARY_HEADER))
+        .cloned();
+    match vary.and_then(|vary| headers.get_mut(&vary)) {
+        Some(value) => {
+            if !value
+                .split(&[',', HEADER_VALUE_SEPARATOR][..])
+                .any(|v| v.trim().eq_ignore_ascii_case(name) || v.trim() == "*")
+            {
+                value.push_str(&format!(", {}", name));
+            }
+        }
+        None => {
+            headers.insert(VARY_HEADER.into(), name.into());
+        }
     }
 }
 
 /// Returns a string representation of the specified query string parameters.
-fn query_string(qs: HashMap<String, String>) -> String {
-    url::form_urlencoded::Serializer::new(String::new())
-        .extend_pairs(qs.iter())
-        .finish()
+/// Multi-value parameters, when present, take precedence as they include every value of a repeated key.
+pub(crate) fn query_string(
+    qs: HashMap<String, String>,
+    multi_value_qs: HashMap<String, Vec<String>>,
+) -> String {
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    if multi_value_qs.is_empty() {
+        serializer.extend_pairs(qs.iter());
+    } else {
+        for (key, values) in &multi_value_qs {
+            for value in values {
+                serializer.append_pair(key, value);
+            }
+        }
+    }
+
+    serializer.finish()
 }
 
-// TODO Handle multi_value_query_string_parameters.
+/// Returns an actor's HTTP request headers from the specified request headers.
+/// Multi-value headers, when present, take precedence and the values of a repeated header are
+/// newline-separated, as in an actor's response.
+/// Repeated `Cookie` headers are instead combined into a single `Cookie` header.
+pub(crate) fn request_headers(
+    headers: HashMap<String, String>,
+    multi_value_headers: HashMap<String, Vec<String>>,
+) -> HashMap<String, String> {
+    if multi_value_headers.is_empty() {
+        return headers;
+    }
+
+    multi_value_headers
+        .into_iter()
+        .map(|(name, values)| {
+            let value = if name.eq_ignore_ascii_case(COOKIE_HEADER) {
+                values.join(COOKIE_SEPARATOR)
+            } else {
+                values.join(&HEADER_VALUE_SEPARATOR.to_string())
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Returns the values of an actor's HTTP request or response header.
+/// A repeated header is a single header with newline-separated values.
+fn header_values(value: &str) -> Vec<String> {
+    value
+        .split(HEADER_VALUE_SEPARATOR)
+        .map(String::from)
+        .collect()
+}
+
+/// Returns an actor's HTTP response headers for a response with a single value per header.
+/// The values of a repeated header are combined into a comma-separated list, except for `Set-Cookie`,
+/// whose values can themselves contain commas, so only its first value is kept.
+pub(crate) fn joined_headers(headers: HashMap<String, String>) -> HashMap<String, String> {
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            let values = header_values(&value);
+            let value = if name.eq_ignore_ascii_case(SET_COOKIE_HEADER) {
+                if values.len() > 1 {
+                    warn!(
+                        "Only the first of {} {} response header values can be returned",
+                        values.len(),
+                        name
+                    );
+                }
+                values.into_iter().next().unwrap_or_default()
+            } else {
+                values.join(", ")
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Adds request cookies, which are sent separately from the other headers, to an actor's `cookie` header.
+pub(crate) fn add_cookie_header(header: &mut HashMap<String, String>, cookies: Vec<String>) {
+    if cookies.is_empty() {
+        return;
+    }
+
+    let mut cookie = cookies.join(COOKIE_SEPARATOR);
+    if let Some(value) = header.remove(COOKIE_HEADER) {
+        cookie = format!("{}{}{}", value, COOKIE_SEPARATOR, cookie);
+    }
+    header.insert(COOKIE_HEADER.into(), cookie);
+}
+
+/// Removes an actor's `set-cookie` response headers, returning each cookie to be returned separately.
+pub(crate) fn remove_set_cookie_headers(headers: &mut HashMap<String, String>) -> Vec<String> {
+    let names: Vec<String> = headers
+        .keys()
+        .filter(|name| name.eq_ignore_ascii_case(SET_COOKIE_HEADER))
+        .cloned()
+        .collect();
+
+    names
+        .iter()
+        .filter_map(|name| headers.remove(name))
+        .flat_map(|value| header_values(&value))
+        .collect()
+}
+
+/// Returns whether or not a configuration value is set to `true`.
+fn flag(values: &HashMap<String, String>, key: &str) -> bool {
+    values
+        .get(key)
+        .map(|v| v.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Returns a path with a prefix removed, if the prefix matches whole path segments.
+fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> &'a str {
+    if !path.starts_with(prefix) {
+        return path;
+    }
+    match &path[prefix.len()..] {
+        "" => "/",
+        rest if rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+/// Returns an actor's HTTP response headers split into single-value and multi-value headers.
+fn response_headers(
+    headers: HashMap<String, String>,
+) -> (HashMap<String, String>, HashMap<String, Vec<String>>) {
+    let mut single_value_headers = HashMap::new();
+    let mut multi_value_headers = HashMap::new();
+    for (name, value) in headers {
+        let values = header_values(&value);
+        if values.len() > 1 {
+            multi_value_headers.insert(name, values);
+        } else {
+            single_value_headers.insert(name, value);
+        }
+    }
+
+    (single_value_headers, multi_value_headers)
+}
 
 #[cfg(test)]
 mod tests {
@@ -271,6 +889,11 @@ mod tests {
     use crate::tests_common::*;
     use std::convert::TryInto;
 
+    /// Returns an actor's HTTP response converted using the default settings.
+    fn http_response(response: wascc_codec::http::Response) -> HttpResponse {
+        HttpSettings::default().response(response, None)
+    }
+
     /// Returns an empty `AlbTargetGroupRequest`.
     fn empty_alb_target_group_request() -> alb::AlbTargetGroupRequest {
         alb::AlbTargetGroupRequest {
@@ -398,7 +1021,8 @@ mod tests {
 
     #[test]
     fn alb_target_group_response_wrapper_good() {
-        let result: Result<AlbTargetGroupResponseWrapper, _> = valid_http_response().try_into();
+        let result: Result<AlbTargetGroupResponseWrapper, _> =
+            http_response(valid_http_response()).try_into();
         assert!(result.is_ok());
 
         let result = result.unwrap().0;
@@ -429,7 +1053,8 @@ mod tests {
 
     #[test]
     fn api_gateway_proxy_response_wrapper_good() {
-        let result: Result<ApiGatewayProxyResponseWrapper, _> = valid_http_response().try_into();
+        let result: Result<ApiGatewayProxyResponseWrapper, _> =
+            http_response(valid_http_response()).try_into();
         assert!(result.is_ok());
 
         let result = result.unwrap().0;
@@ -459,7 +1084,8 @@ mod tests {
 
     #[test]
     fn api_gatewayv2_proxy_response_wrapper_good() {
-        let result: Result<ApiGatewayV2ProxyResponseWrapper, _> = valid_http_response().try_into();
+        let result: Result<ApiGatewayV2ProxyResponseWrapper, _> =
+            http_response(valid_http_response()).try_into();
         assert!(result.is_ok());
 
         let result = result.unwrap().0;
@@ -471,6 +1097,126 @@ mod tests {
         assert_eq!(Some(false), result.is_base64_encoded);
     }
 
+    #[test]
+    fn alb_target_group_request_wrapper_context() {
+        let mut request = valid_alb_target_group_request();
+        request.request_context.elb.target_group_arn = Some(
+            "arn:aws:elasticloadbalancing:us-east-1:123456789012:targetgroup/lambda/abc".into(),
+        );
+
+        let result: wascc_codec::http::Request = AlbTargetGroupRequestWrapper::from(request)
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            "arn:aws:elasticloadbalancing:us-east-1:123456789012:targetgroup/lambda/abc",
+            result.header.get("x-wascc-target-group-arn").unwrap()
+        );
+    }
+
+    #[test]
+    fn api_gateway_proxy_request_wrapper_context() {
+        let mut request = valid_api_gateway_proxy_request();
+        request
+            .headers
+            .insert("X-Wascc-Authorizer".into(), "spoofed".into());
+        request.path_parameters.insert("id".into(), "42".into());
+        request.request_context.request_id = Some("c6af9ac6".into());
+        request.request_context.apiid = Some("1234567890".into());
+        request.request_context.stage = Some("prod".into());
+        request.request_context.resource_path = Some("/pets/{id}".into());
+        request.request_context.identity.source_ip = Some("192.0.2.1".into());
+        request.request_context.authorizer.insert(
+            "claims".into(),
+            serde_json::json!({"sub": "abc", "email": "alice@example.com"}),
+        );
+
+        let result: wascc_codec::http::Request = ApiGatewayProxyRequestWrapper::from(request)
+            .try_into()
+            .unwrap();
+        assert_eq!("c6af9ac6", result.header.get("x-wascc-request-id").unwrap());
+        assert_eq!("1234567890", result.header.get("x-wascc-api-id").unwrap());
+        assert_eq!("prod", result.header.get("x-wascc-stage").unwrap());
+        assert_eq!(
+            "/pets/{id}",
+            result.header.get("x-wascc-resource-path").unwrap()
+        );
+        assert_eq!("192.0.2.1", result.header.get("x-wascc-source-ip").unwrap());
+        assert_eq!(
+            r#"{"id":"42"}"#,
+            result.header.get("x-wascc-path-parameters").unwrap()
+        );
+        assert!(!result.header.contains_key("x-wascc-stage-variables"));
+        assert!(!result.header.contains_key("X-Wascc-Authorizer"));
+        let authorizer: serde_json::Value =
+            serde_json::from_str(result.header.get("x-wascc-authorizer").unwrap()).unwrap();
+        assert_eq!("alice@example.com", authorizer["claims"]["email"]);
+    }
+
+    #[test]
+    fn api_gatewayv2_proxy_request_wrapper_context() {
+        let mut request = valid_api_gatewayv2_proxy_request();
+        request.stage_variables.insert("env".into(), "test".into());
+        request.request_context.route_key = Some("GET /pets/{id}".into());
+        request.request_context.http.source_ip = Some("192.0.2.1".into());
+        let mut claims = HashMap::new();
+        claims.insert("sub".into(), "abc".into());
+        request.request_context.authorizer =
+            Some(apigw::ApiGatewayV2httpRequestContextAuthorizerDescription {
+                jwt: apigw::ApiGatewayV2httpRequestContextAuthorizerJwtDescription {
+                    claims,
+                    scopes: None,
+                },
+            });
+
+        let result: wascc_codec::http::Request = ApiGatewayV2ProxyRequestWrapper::from(request)
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            "GET /pets/{id}",
+            result.header.get("x-wascc-route-key").unwrap()
+        );
+        assert_eq!("192.0.2.1", result.header.get("x-wascc-source-ip").unwrap());
+        assert_eq!(
+            r#"{"env":"test"}"#,
+            result.header.get("x-wascc-stage-variables").unwrap()
+        );
+        let authorizer: serde_json::Value =
+            serde_json::from_str(result.header.get("x-wascc-authorizer").unwrap()).unwrap();
+        assert_eq!("abc", authorizer["jwt"]["claims"]["sub"]);
+    }
+
+    #[test]
+    fn api_gatewayv2_proxy_request_wrapper_cookies() {
+        let mut request = valid_api_gatewayv2_proxy_request();
+        request.cookies = Some(vec!["a=1".into(), "b=2".into()]);
+
+        let result: wascc_codec::http::Request = ApiGatewayV2ProxyRequestWrapper::from(request)
+            .try_into()
+            .unwrap();
+        assert_eq!("a=1; b=2", result.header.get("cookie").unwrap());
+    }
+
+    #[test]
+    fn api_gatewayv2_proxy_response_wrapper_cookies() {
+        let mut response = valid_http_response();
+        response.header.insert(
+            "Set-Cookie".into(),
+            "a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT; Secure\nb=2".into(),
+        );
+
+        let result: ApiGatewayV2ProxyResponseWrapper = http_response(response).try_into().unwrap();
+        let result = result.0;
+        assert_eq!(1, result.headers.len());
+        assert!(result.headers.contains_key("server"));
+        assert_eq!(
+            vec![
+                "a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT; Secure".to_string(),
+                "b=2".to_string()
+            ],
+            result.cookies
+        );
+    }
+
     #[test]
     fn body_string_empty() {
         assert_eq!((None, false), body_string(vec![]));
@@ -492,22 +1238,306 @@ mod tests {
         );
     }
 
+    #[test]
+    fn encode_body_text_not_utf8() {
+        assert_eq!(
+            (Some("gYKD".into()), true),
+            encode_body(vec![0x81, 0x82, 0x83], false)
+        );
+    }
+
+    #[test]
+    fn http_settings_content_type() {
+        let mut values = HashMap::new();
+        values.insert(
+            BINARY_MEDIA_TYPES_KEY.into(),
+            "application/pdf, text/csv".into(),
+        );
+        let settings = HttpSettings::from_config(&values);
+
+        let body = b"abc";
+        assert!(!settings.is_binary(None, body));
+        assert!(!settings.is_binary(Some("text/html; charset=utf-8"), body));
+        assert!(!settings.is_binary(Some("application/json"), body));
+        assert!(!settings.is_binary(Some("application/vnd.api+json"), body));
+        assert!(settings.is_binary(Some("image/png"), body));
+        assert!(settings.is_binary(Some("application/octet-stream"), body));
+        assert!(settings.is_binary(Some("application/pdf"), body));
+        assert!(settings.is_binary(Some("text/csv"), body));
+        assert!(!settings.is_binary(Some("text/plain"), body));
+    }
+
+    #[test]
+    fn http_settings_request_path() {
+        let mut parameters = HashMap::new();
+        let settings = HttpSettings::default();
+        assert_eq!(
+            "/prod/pets",
+            settings.request_path("/prod/pets".into(), Some("prod"), &parameters)
+        );
+
+        let mut values = HashMap::new();
+        values.insert(STRIP_STAGE_KEY.into(), "true".into());
+        values.insert(BASE_PATH_KEY.into(), "api/v1/".into());
+        let settings = HttpSettings::from_config(&values);
+        assert_eq!(
+            "/pets",
+            settings.request_path("/prod/api/v1/pets".into(), Some("prod"), &parameters)
+        );
+        assert_eq!(
+            "/",
+            settings.request_path("/api/v1".into(), Some("prod"), &parameters)
+        );
+        assert_eq!(
+            "/api/v10/pets",
+            settings.request_path("/api/v10/pets".into(), None, &parameters)
+        );
+        assert_eq!(
+            "/production/pets",
+            settings.request_path("/production/pets".into(), Some("prod"), &parameters)
+        );
+        assert_eq!(
+            "/$default/pets",
+            settings.request_path("/$default/pets".into(), Some("$default"), &parameters)
+        );
+
+        parameters.insert("proxy".into(), "pets/42".into());
+        assert_eq!(
+            "/pets",
+            settings.request_path("/prod/api/v1/pets".into(), Some("prod"), &parameters)
+        );
+        values.insert(PROXY_PATH_KEY.into(), "true".into());
+        let settings = HttpSettings::from_config(&values);
+        assert_eq!(
+            "/pets/42",
+            settings.request_path("/prod/api/v1/pets/42".into(), Some("prod"), &parameters)
+        );
+    }
+
+    #[test]
+    fn api_gateway_proxy_request_wrapper_rewrite_path() {
+        let mut request = valid_api_gateway_proxy_request();
+        request.path = Some("/prod/hello".into());
+        request.request_context.stage = Some("prod".into());
+        let mut values = HashMap::new();
+        values.insert(STRIP_STAGE_KEY.into(), "TRUE".into());
+        let settings = HttpSettings::from_config(&values);
+
+        let result: wascc_codec::http::Request = ApiGatewayProxyRequestWrapper::from(request)
+            .rewrite_path(&settings)
+            .try_into()
+            .unwrap();
+        assert_eq!("/hello", result.path);
+    }
+
+    #[test]
+    fn http_settings_compression() {
+        let mut values = HashMap::new();
+        values.insert(COMPRESSION_KEY.into(), "true".into());
+        values.insert(COMPRESSION_THRESHOLD_KEY.into(), "100".into());
+        let settings = HttpSettings::from_config(&values);
+
+        let response = |content_type: &str, size: usize| {
+            let mut response = valid_http_response();
+            response
+                .header
+                .insert("Content-Type".into(), content_type.into());
+            response
+                .header
+                .insert("Content-Length".into(), size.to_string());
+            response.header.insert("Vary".into(), "Origin".into());
+            response.body = vec![b'a'; size];
+            response
+        };
+
+        let (result, is_binary) = settings
+            .response(response("application/json", 200), Some("gzip, deflate"))
+            .into_parts();
+        assert!(is_binary);
+        assert!(result.body.len() < 200);
+        assert_eq!("gzip", result.header.get("content-encoding").unwrap());
+        assert_eq!(
+            "Origin, Accept-Encoding",
+            result.header.get("Vary").unwrap()
+        );
+        assert!(!result.header.contains_key("Content-Length"));
+
+        let result: ApiGatewayProxyResponseWrapper = settings
+            .response(response("text/html", 200), Some("br"))
+            .try_into()
+            .unwrap();
+        let result: apigw::ApiGatewayProxyResponse = result.into();
+        assert_eq!(Some(true), result.is_base64_encoded);
+        assert_eq!("br", result.headers.get("content-encoding").unwrap());
+
+        // No acceptable encoding.
+        let (result, is_binary) = settings
+            .response(response("application/json", 200), Some("identity"))
+            .into_parts();
+        assert!(!is_binary);
+        assert_eq!(200, result.body.len());
+
+        // Below the threshold.
+        let (result, _) = settings
+            .response(response("application/json", 99), Some("gzip"))
+            .into_parts();
+        assert_eq!(99, result.body.len());
+
+        // Not compressible.
+        let (result, _) = settings
+            .response(response("image/png", 200), Some("gzip"))
+            .into_parts();
+        assert!(!result.header.contains_key("content-encoding"));
+
+        // Not enabled.
+        let (result, _) = HttpSettings::default()
+            .response(response("application/json", 200), Some("gzip"))
+            .into_parts();
+        assert_eq!(200, result.body.len());
+    }
+
+    #[test]
+    fn http_settings_binary_media_type_wildcard() {
+        let mut values = HashMap::new();
+        values.insert(BINARY_MEDIA_TYPES_KEY.into(), "text/*".into());
+        let settings = HttpSettings::from_config(&values);
+
+        assert!(settings.is_binary(Some("text/plain"), b"abc"));
+        assert!(!settings.is_binary(Some("application/json"), b"abc"));
+    }
+
+    #[test]
+    fn api_gateway_proxy_response_wrapper_binary() {
+        // Binary data that happens to be valid UTF-8.
+        let mut response = valid_http_response();
+        response.body = vec![0x50, 0x4e, 0x47, 0x0d, 0x0a];
+        response
+            .header
+            .insert("Content-Type".into(), "image/png".into());
+
+        let result: ApiGatewayProxyResponseWrapper = http_response(response).try_into().unwrap();
+        let result = result.0;
+        assert_eq!(Some("UE5HDQo=".into()), result.body);
+        assert_eq!(Some(true), result.is_base64_encoded);
+    }
+
     #[test]
     fn query_string_empty() {
-        assert_eq!("", query_string(HashMap::new()));
+        assert_eq!("", query_string(HashMap::new(), HashMap::new()));
     }
 
     #[test]
     fn query_string_single() {
         let mut m = HashMap::new();
         m.insert("key1".into(), "value1".into());
-        assert_eq!("key1=value1", query_string(m));
+        assert_eq!("key1=value1", query_string(m, HashMap::new()));
     }
 
     #[test]
     fn query_string_single_with_special() {
         let mut m = HashMap::new();
         m.insert("key1".into(), "val e1".into());
-        assert_eq!("key1=val+e1", query_string(m));
+        assert_eq!("key1=val+e1", query_string(m, HashMap::new()));
+    }
+
+    #[test]
+    fn query_string_multi_value() {
+        let mut m = HashMap::new();
+        m.insert("tag".into(), "b".into());
+        let mut mv = HashMap::new();
+        mv.insert("tag".into(), vec!["a".into(), "b".into()]);
+        assert_eq!("tag=a&tag=b", query_string(m, mv));
+    }
+
+    #[test]
+    fn alb_target_group_request_wrapper_multi_value() {
+        let mut request = valid_alb_target_group_request();
+        request.query_string_parameters = HashMap::new();
+        request.headers = HashMap::new();
+        request
+            .multi_value_query_string_parameters
+            .insert("tag".into(), vec!["a".into(), "b".into()]);
+        request.multi_value_headers.insert(
+            "accept".into(),
+            vec!["text/html".into(), "application/json".into()],
+        );
+
+        let request = AlbTargetGroupRequestWrapper::from(request);
+        assert!(request.is_multi_value());
+
+        let result: wascc_codec::http::Request = request.try_into().unwrap();
+        assert_eq!("tag=a&tag=b", result.query_string);
+        assert_eq!(
+            "text/html\napplication/json",
+            result.header.get("accept").unwrap()
+        );
+    }
+
+    #[test]
+    fn api_gateway_proxy_request_wrapper_multi_value_cookie() {
+        let mut request = valid_api_gateway_proxy_request();
+        request
+            .multi_value_headers
+            .insert("Cookie".into(), vec!["a=1; b=2".into(), "c=3, 4".into()]);
+
+        let result: wascc_codec::http::Request = ApiGatewayProxyRequestWrapper::from(request)
+            .try_into()
+            .unwrap();
+        assert_eq!("a=1; b=2; c=3, 4", result.header.get("Cookie").unwrap());
+    }
+
+    #[test]
+    fn alb_target_group_response_wrapper_multi_value() {
+        let mut response = valid_http_response();
+        response
+            .header
+            .insert("set-cookie".into(), "a=1\nb=2".into());
+
+        let result = AlbTargetGroupResponseWrapper::try_from_multi_value(http_response(response))
+            .unwrap()
+            .0;
+        assert!(result.headers.is_empty());
+        assert_eq!(2, result.multi_value_headers.len());
+        assert_eq!(
+            &vec!["test".to_string()],
+            result.multi_value_headers.get("server").unwrap()
+        );
+        assert_eq!(
+            &vec!["a=1".to_string(), "b=2".to_string()],
+            result.multi_value_headers.get("set-cookie").unwrap()
+        );
+    }
+
+    #[test]
+    fn alb_target_group_response_wrapper_set_cookie_commas() {
+        let mut response = valid_http_response();
+        response.header.insert(
+            "Set-Cookie".into(),
+            "a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT\nb=2".into(),
+        );
+
+        let result: AlbTargetGroupResponseWrapper = http_response(response).try_into().unwrap();
+        assert_eq!(
+            "a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT",
+            result.0.headers.get("Set-Cookie").unwrap()
+        );
+    }
+
+    #[test]
+    fn api_gateway_proxy_response_wrapper_multi_value() {
+        let mut response = valid_http_response();
+        response
+            .header
+            .insert("set-cookie".into(), "a=1\nb=2".into());
+
+        let result: ApiGatewayProxyResponseWrapper = http_response(response).try_into().unwrap();
+        let result = result.0;
+        assert_eq!(1, result.headers.len());
+        assert_eq!("test", result.headers.get("server").unwrap());
+        assert_eq!(1, result.multi_value_headers.len());
+        assert_eq!(
+            &vec!["a=1".to_string(), "b=2".to_string()],
+            result.multi_value_headers.get("set-cookie").unwrap()
+        );
     }
 }
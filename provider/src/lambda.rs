@@ -16,9 +16,28 @@
 // waSCC AWS Lambda Runtime Providers
 //
 
+use rand::Rng;
 use reqwest::header::USER_AGENT;
 
+use std::collections::HashMap;
+use std::io::{BufReader, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use crate::classify::EventSource;
+use crate::dispatch::{
+    ActorBusyError, ActorError, DeadlineExceededError, DispatcherError, NotHttpRequestError,
+};
+
+const CLIENT_CONTEXT_HEADER_NAME: &str = "Lambda-Runtime-Client-Context";
+const COGNITO_IDENTITY_HEADER_NAME: &str = "Lambda-Runtime-Cognito-Identity";
+const DEADLINE_HEADER_NAME: &str = "Lambda-Runtime-Deadline-Ms";
+const ERROR_BODY_HEADER_NAME: &str = "Lambda-Runtime-Function-Error-Body";
+const ERROR_TYPE_HEADER_NAME: &str = "Lambda-Runtime-Function-Error-Type";
+const FUNCTION_ARN_HEADER_NAME: &str = "Lambda-Runtime-Invoked-Function-Arn";
 const REQUEST_ID_HEADER_NAME: &str = "Lambda-Runtime-Aws-Request-Id";
+const RESPONSE_MODE_HEADER_NAME: &str = "Lambda-Runtime-Function-Response-Mode";
 const TRACE_ID_HEADER_NAME: &str = "Lambda-Runtime-Trace-Id";
 
 /// Represents an AWS Lambda runtime client.
@@ -32,30 +51,237 @@ pub(crate) trait Client {
     /// Sends an invocation error to the AWS Lambda runtime.
     fn send_invocation_response(&self, resp: InvocationResponse) -> anyhow::Result<()>;
 
+    /// Starts streaming an invocation response to the AWS Lambda runtime.
+    fn stream_invocation_response(
+        &self,
+        request_id: &str,
+        content_type: Option<&str>,
+    ) -> anyhow::Result<Box<dyn ResponseStream>>;
+
     /// Sends an initialization error to the AWS Lambda runtime.
     fn send_initialization_error(&self, error: InitializationError) -> anyhow::Result<()>;
 }
 
+/// Represents an invocation response being streamed to the AWS Lambda runtime.
+pub(crate) trait ResponseStream: Send {
+    /// Sends a chunk of the response body.
+    fn send_chunk(&mut self, chunk: &[u8]) -> anyhow::Result<()>;
+
+    /// Completes the response.
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+
+    /// Completes the response, reporting an error in the trailing headers.
+    fn finish_with_error(self: Box<Self>, error: InvocationError) -> anyhow::Result<()>;
+}
+
 /// Represents an AWS Lambda initialization error reporter.
 pub trait InitializationErrorReporter {
     /// Sends an initialization error to the AWS Lambda runtime.
     fn send_initialization_error(&self, error: anyhow::Error) -> anyhow::Result<()>;
 }
 
+/// An AWS Lambda runtime API error.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum RuntimeApiError {
+    /// The request ID was invalid (400).
+    #[error("Invalid request ID: {}", message)]
+    InvalidRequestId { message: String },
+
+    /// The request was forbidden (403).
+    #[error("Forbidden: {}", message)]
+    Forbidden { message: String },
+
+    /// The payload was too large (413).
+    #[error("Payload too large: {}", message)]
+    PayloadTooLarge { message: String },
+
+    /// The container is in an unrecoverable state (500).
+    /// The runtime must exit.
+    #[error("Container error: {}", message)]
+    ContainerError { message: String },
+
+    /// Any other unsuccessful status.
+    #[error("Unexpected status {}: {}", status, message)]
+    UnexpectedStatus { status: u16, message: String },
+}
+
+impl RuntimeApiError {
+    /// Returns the error for the specified unsuccessful status and response body.
+    fn from_status(status: reqwest::StatusCode, message: String) -> Self {
+        match status.as_u16() {
+            400 => RuntimeApiError::InvalidRequestId { message },
+            403 => RuntimeApiError::Forbidden { message },
+            413 => RuntimeApiError::PayloadTooLarge { message },
+            500 => RuntimeApiError::ContainerError { message },
+            status => RuntimeApiError::UnexpectedStatus { status, message },
+        }
+    }
+
+    /// Returns whether or not the runtime must exit.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, RuntimeApiError::ContainerError { .. })
+    }
+}
+
+/// Represents the retry and timeout settings of an AWS Lambda runtime client.
+#[derive(Clone)]
+pub(crate) struct ClientSettings {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    post_timeout: Option<Duration>,
+}
+
+impl ClientSettings {
+    /// Creates a new `ClientSettings` from the specified capability configuration values.
+    /// Any value not configured takes its default.
+    pub fn from_config(values: &HashMap<String, String>) -> Self {
+        let default = Self::default();
+        let value = |key: &str| values.get(key).and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            max_attempts: value("WASCC_RUNTIME_API_MAX_ATTEMPTS")
+                .map(|v| v.max(1) as u32)
+                .unwrap_or(default.max_attempts),
+            base_delay: value("WASCC_RUNTIME_API_BACKOFF_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            max_delay: value("WASCC_RUNTIME_API_MAX_BACKOFF_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_delay),
+            post_timeout: match value("WASCC_RUNTIME_API_POST_TIMEOUT_MS") {
+                Some(0) => None,
+                Some(v) => Some(Duration::from_millis(v)),
+                None => default.post_timeout,
+            },
+        }
+    }
+
+    /// Returns the delay before the specified retry attempt.
+    /// The delay grows exponentially up to the maximum and is fully jittered.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .checked_mul(1 << attempt.min(16))
+            .map_or(self.max_delay, |d| d.min(self.max_delay));
+        let ceiling_ms = ceiling.as_millis() as u64;
+        if ceiling_ms == 0 {
+            return ceiling;
+        }
+
+        Duration::from_millis(rand::thread_rng().gen_range(0, ceiling_ms + 1))
+    }
+}
+
+impl Default for ClientSettings {
+    /// Returns the default value for `ClientSettings`.
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            post_timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
 /// Represents an AWS Lambda runtime client.
 pub(crate) struct RuntimeClient {
     endpoint: String,
     http_client: reqwest::blocking::Client,
+    settings: ClientSettings,
     user_agent: String,
 }
 
 impl RuntimeClient {
     /// Creates a new `RuntimeClient` with the specified AWS Lambda runtime API endpoint.
-    pub fn new(endpoint: &str) -> Self {
-        Self {
+    pub fn new(endpoint: &str) -> anyhow::Result<Self> {
+        Self::with_settings(endpoint, ClientSettings::default())
+    }
+
+    /// Creates a new `RuntimeClient` with the specified AWS Lambda runtime API endpoint and settings.
+    pub fn with_settings(endpoint: &str, settings: ClientSettings) -> anyhow::Result<Self> {
+        // The long-poll for the next invocation event must not time out.
+        let http_client = reqwest::blocking::Client::builder().timeout(None).build()?;
+
+        Ok(Self {
             endpoint: endpoint.into(),
-            http_client: reqwest::blocking::Client::new(),
+            http_client,
+            settings,
             user_agent: format!("AWS_Lambda_waSCC/{}", env!("CARGO_PKG_VERSION")),
+        })
+    }
+
+    /// Returns a POST request builder for the specified URL.
+    fn post(&self, url: &str) -> reqwest::blocking::RequestBuilder {
+        let builder = self
+            .http_client
+            .post(url)
+            .header(USER_AGENT, self.user_agent.clone());
+
+        match self.settings.post_timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+
+    /// Sends the request built by the specified function,
+    /// retrying transient failures with exponential backoff.
+    /// Connection errors, timeouts and server errors other than container errors are considered transient.
+    /// As the AWS Lambda runtime may already have received a POST request that timed out,
+    /// POST requests are only retried after connection errors and server errors.
+    /// Unsuccessful responses are returned as `RuntimeApiError`s.
+    fn send<F>(&self, url: &str, request: F) -> anyhow::Result<reqwest::blocking::Response>
+    where
+        F: Fn() -> reqwest::blocking::RequestBuilder,
+    {
+        let resp = self.send_with_retry(url, request)?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
+        }
+
+        let message = resp.text().unwrap_or_default();
+        Err(RuntimeApiError::from_status(status, message).into())
+    }
+
+    /// Sends the request built by the specified function,
+    /// retrying transient failures with exponential backoff.
+    fn send_with_retry<F>(
+        &self,
+        url: &str,
+        request: F,
+    ) -> anyhow::Result<reqwest::blocking::Response>
+    where
+        F: Fn() -> reqwest::blocking::RequestBuilder,
+    {
+        let mut attempt = 1;
+        loop {
+            let last_attempt = attempt >= self.settings.max_attempts;
+            let request = request().build()?;
+            let idempotent = request.method() != reqwest::Method::POST;
+            match self.http_client.execute(request) {
+                Ok(resp)
+                    if resp.status().is_server_error()
+                        && resp.status() != reqwest::StatusCode::INTERNAL_SERVER_ERROR
+                        && !last_attempt =>
+                {
+                    warn!("{} {} (attempt {})", url, resp.status(), attempt);
+                }
+                Ok(resp) => return Ok(resp),
+                Err(e)
+                    if (idempotent || e.is_connect())
+                        && !e.is_builder()
+                        && !e.is_redirect()
+                        && !last_attempt =>
+                {
+                    warn!("{} {} (attempt {})", url, e, attempt);
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            thread::sleep(self.settings.backoff(attempt));
+            attempt += 1;
         }
     }
 
@@ -81,11 +307,11 @@ impl Client for RuntimeClient {
     fn next_invocation_event(&self) -> anyhow::Result<Option<InvocationEvent>> {
         // https://docs.aws.amazon.com/lambda/latest/dg/runtimes-api.html#runtimes-api-next
         let url = format!("{}/{}", self.endpoint, Self::next_invocation_event_path());
-        let mut resp = self
-            .http_client
-            .get(&url)
-            .header(USER_AGENT, self.user_agent.clone())
-            .send()?;
+        let mut resp = self.send(&url, || {
+            self.http_client
+                .get(&url)
+                .header(USER_AGENT, self.user_agent.clone())
+        })?;
         let status = resp.status();
         info!(
             "GET {} {} {}",
@@ -93,9 +319,6 @@ impl Client for RuntimeClient {
             status.as_str(),
             status.canonical_reason().unwrap()
         );
-        if !status.is_success() {
-            return Ok(None);
-        }
 
         let mut buf: Vec<u8> = vec![];
         resp.copy_to(&mut buf)?;
@@ -107,6 +330,27 @@ impl Client for RuntimeClient {
         if let Some(trace_id) = resp.headers().get(TRACE_ID_HEADER_NAME) {
             builder = builder.trace_id(trace_id.to_str()?);
         }
+        if let Some(deadline) = resp.headers().get(DEADLINE_HEADER_NAME) {
+            match deadline.to_str().ok().and_then(|d| d.parse().ok()) {
+                Some(deadline_ms) => builder = builder.deadline_ms(deadline_ms),
+                None => warn!("Invalid {} header: {:?}", DEADLINE_HEADER_NAME, deadline),
+            }
+        }
+        if let Some(function_arn) = resp.headers().get(FUNCTION_ARN_HEADER_NAME) {
+            builder = builder.invoked_function_arn(function_arn.to_str()?);
+        }
+        if let Some(client_context) = resp.headers().get(CLIENT_CONTEXT_HEADER_NAME) {
+            match serde_json::from_slice(client_context.as_bytes()) {
+                Ok(client_context) => builder = builder.client_context(client_context),
+                Err(e) => warn!("Invalid {} header: {}", CLIENT_CONTEXT_HEADER_NAME, e),
+            }
+        }
+        if let Some(identity) = resp.headers().get(COGNITO_IDENTITY_HEADER_NAME) {
+            match serde_json::from_slice(identity.as_bytes()) {
+                Ok(identity) => builder = builder.identity(identity),
+                Err(e) => warn!("Invalid {} header: {}", COGNITO_IDENTITY_HEADER_NAME, e),
+            }
+        }
 
         Ok(Some(builder.build()))
     }
@@ -119,15 +363,12 @@ impl Client for RuntimeClient {
             self.endpoint,
             Self::invocation_error_path(error.request_id())
         );
-        let resp = self
-            .http_client
-            .post(&url)
-            .header(USER_AGENT, self.user_agent.clone())
-            .json(&serde_json::json!({
-                "errorMessage": error.error_message(),
-                "errorType": "InvocationError",
-            }))
-            .send()?;
+        let payload = error.payload();
+        let resp = self.send(&url, || {
+            self.post(&url)
+                .header(ERROR_TYPE_HEADER_NAME, error.error_type())
+                .json(&payload)
+        })?;
         let status = resp.status();
         info!(
             "POST {} {} {}",
@@ -147,12 +388,7 @@ impl Client for RuntimeClient {
             self.endpoint,
             Self::invocation_response_path(resp.request_id())
         );
-        let resp = self
-            .http_client
-            .post(&url)
-            .header(USER_AGENT, self.user_agent.clone())
-            .body(resp.body().to_owned())
-            .send()?;
+        let resp = self.send(&url, || self.post(&url).body(resp.body().to_owned()))?;
         let status = resp.status();
         info!(
             "POST {} {} {}",
@@ -164,19 +400,67 @@ impl Client for RuntimeClient {
         Ok(())
     }
 
+    /// Starts streaming an invocation response to the AWS Lambda runtime.
+    /// The response is sent with chunked transfer encoding and is not retried.
+    fn stream_invocation_response(
+        &self,
+        request_id: &str,
+        content_type: Option<&str>,
+    ) -> anyhow::Result<Box<dyn ResponseStream>> {
+        // https://docs.aws.amazon.com/lambda/latest/dg/runtimes-custom.html#runtimes-custom-response-streaming
+        let url = url::Url::parse(&format!(
+            "{}/{}",
+            self.endpoint,
+            Self::invocation_response_path(request_id)
+        ))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("Invalid endpoint: {}", self.endpoint))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let mut stream = TcpStream::connect((host, port))?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(self.settings.post_timeout)?;
+        let head = format!(
+            "POST {} HTTP/1.1\r\n\
+             Host: {}:{}\r\n\
+             {}: {}\r\n\
+             {}: streaming\r\n\
+             Content-Type: {}\r\n\
+             Transfer-Encoding: chunked\r\n\
+             Trailer: {}, {}\r\n\r\n",
+            url.path(),
+            host,
+            port,
+            USER_AGENT,
+            self.user_agent,
+            RESPONSE_MODE_HEADER_NAME,
+            content_type.unwrap_or("application/octet-stream"),
+            ERROR_TYPE_HEADER_NAME,
+            ERROR_BODY_HEADER_NAME
+        );
+        stream.write_all(head.as_bytes())?;
+        info!("POST {} (streaming)", url);
+
+        Ok(Box::new(ChunkedResponseStream {
+            stream,
+            url: url.to_string(),
+        }))
+    }
+
     /// Sends an initialization error to the AWS Lambda runtime.
     fn send_initialization_error(&self, error: InitializationError) -> anyhow::Result<()> {
         // https://docs.aws.amazon.com/lambda/latest/dg/runtimes-api.html#runtimes-api-initerror
         let url = format!("{}/{}", self.endpoint, Self::initialization_error_path());
-        let resp = self
-            .http_client
-            .post(&url)
-            .header(USER_AGENT, self.user_agent.clone())
-            .json(&serde_json::json!({
-                "errorMessage": error.error_message(),
-                "errorType": "InitializationError",
-            }))
-            .send()?;
+        let payload = serde_json::json!({
+            "errorMessage": error.error_message(),
+            "errorType": "InitializationError",
+        });
+        let resp = self.send(&url, || {
+            self.post(&url)
+                .header(ERROR_TYPE_HEADER_NAME, "InitializationError")
+                .json(&payload)
+        })?;
         let status = resp.status();
         info!(
             "POST {} {} {}",
@@ -196,8 +480,75 @@ impl InitializationErrorReporter for RuntimeClient {
     }
 }
 
+/// Represents an invocation response streamed using chunked transfer encoding.
+struct ChunkedResponseStream {
+    stream: TcpStream,
+    url: String,
+}
+
+impl ChunkedResponseStream {
+    /// Writes the last chunk and any trailing headers, then reads the AWS Lambda runtime's response.
+    fn complete(mut self, trailers: &[(&str, String)]) -> anyhow::Result<()> {
+        let mut last = String::from("0\r\n");
+        for (name, value) in trailers {
+            last.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        last.push_str("\r\n");
+        self.stream.write_all(last.as_bytes())?;
+
+        let response = http1::read_response(&mut BufReader::new(self.stream))?;
+        let status = reqwest::StatusCode::from_u16(response.status)
+            .map_err(|_| anyhow!("Invalid response status: {}", response.status))?;
+        info!(
+            "POST {} {} {}",
+            self.url,
+            status.as_str(),
+            status.canonical_reason().unwrap_or("Unknown")
+        );
+        if status.is_success() {
+            return Ok(());
+        }
+
+        Err(
+            RuntimeApiError::from_status(status, String::from_utf8_lossy(&response.body).into())
+                .into(),
+        )
+    }
+}
+
+impl ResponseStream for ChunkedResponseStream {
+    /// Sends a chunk of the response body.
+    fn send_chunk(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        // An empty chunk would end the response.
+        if chunk.is_empty() {
+            return Ok(());
+        }
+
+        self.stream
+            .write_all(format!("{:X}\r\n", chunk.len()).as_bytes())?;
+        self.stream.write_all(chunk)?;
+        self.stream.write_all(b"\r\n")?;
+
+        Ok(())
+    }
+
+    /// Completes the response.
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        self.complete(&[])
+    }
+
+    /// Completes the response, reporting an error in the trailing headers.
+    fn finish_with_error(self: Box<Self>, error: InvocationError) -> anyhow::Result<()> {
+        let body = base64::encode(serde_json::to_vec(&error.payload())?);
+        self.complete(&[
+            (ERROR_TYPE_HEADER_NAME, error.error_type().into()),
+            (ERROR_BODY_HEADER_NAME, body),
+        ])
+    }
+}
+
 /// Returns a new `InitializationErrorReporter` implementation.
-pub fn initerr_reporter(endpoint: &str) -> impl InitializationErrorReporter {
+pub fn initerr_reporter(endpoint: &str) -> anyhow::Result<impl InitializationErrorReporter> {
     RuntimeClient::new(endpoint)
 }
 
@@ -205,6 +556,10 @@ pub fn initerr_reporter(endpoint: &str) -> impl InitializationErrorReporter {
 #[derive(Clone)]
 pub(crate) struct InvocationEvent {
     body: Vec<u8>,
+    client_context: Option<codec::ClientContext>,
+    deadline_ms: Option<u64>,
+    identity: Option<codec::CognitoIdentity>,
+    invoked_function_arn: Option<String>,
     request_id: Option<String>,
     trace_id: Option<String>,
 }
@@ -215,6 +570,26 @@ impl InvocationEvent {
         self.body.as_ref()
     }
 
+    /// Returns any client context.
+    pub fn client_context(&self) -> Option<&codec::ClientContext> {
+        self.client_context.as_ref()
+    }
+
+    /// Returns any deadline, in milliseconds since the Unix epoch.
+    pub fn deadline_ms(&self) -> Option<u64> {
+        self.deadline_ms
+    }
+
+    /// Returns any Cognito identity.
+    pub fn identity(&self) -> Option<&codec::CognitoIdentity> {
+        self.identity.as_ref()
+    }
+
+    /// Returns any invoked function ARN.
+    pub fn invoked_function_arn(&self) -> Option<&str> {
+        self.invoked_function_arn.as_deref()
+    }
+
     /// Returns any request ID.
     pub fn request_id(&self) -> Option<&str> {
         self.request_id.as_deref()
@@ -224,11 +599,33 @@ impl InvocationEvent {
     pub fn trace_id(&self) -> Option<&str> {
         self.trace_id.as_deref()
     }
+
+    /// Returns the invocation context for this event, its source and the specified function settings.
+    pub fn context(&self, settings: &FunctionSettings, source: EventSource) -> codec::Context {
+        codec::Context {
+            request_id: self.request_id().unwrap_or_default().into(),
+            deadline_ms: self.deadline_ms().unwrap_or_default(),
+            invoked_function_arn: self.invoked_function_arn().unwrap_or_default().into(),
+            trace_id: self.trace_id().map(|t| t.into()),
+            client_context: self.client_context().cloned(),
+            identity: self.identity().cloned(),
+            function_name: settings.function_name.clone(),
+            function_version: settings.function_version.clone(),
+            memory_limit_in_mb: settings.memory_limit_in_mb,
+            log_group_name: settings.log_group_name.clone(),
+            log_stream_name: settings.log_stream_name.clone(),
+            event_source: source.name().into(),
+        }
+    }
 }
 
 /// Builds an `InvocationEvent`.
 pub(crate) struct InvocationEventBuilder {
     body: Vec<u8>,
+    client_context: Option<codec::ClientContext>,
+    deadline_ms: Option<u64>,
+    identity: Option<codec::CognitoIdentity>,
+    invoked_function_arn: Option<String>,
     request_id: Option<String>,
     trace_id: Option<String>,
 }
@@ -238,11 +635,39 @@ impl InvocationEventBuilder {
     pub fn new(body: Vec<u8>) -> Self {
         Self {
             body,
+            client_context: None,
+            deadline_ms: None,
+            identity: None,
+            invoked_function_arn: None,
             request_id: None,
             trace_id: None,
         }
     }
 
+    /// Adds the specified client context to the builder.
+    pub fn client_context(mut self, client_context: codec::ClientContext) -> Self {
+        self.client_context = Some(client_context);
+        self
+    }
+
+    /// Adds the specified deadline to the builder.
+    pub fn deadline_ms(mut self, deadline_ms: u64) -> Self {
+        self.deadline_ms = Some(deadline_ms);
+        self
+    }
+
+    /// Adds the specified Cognito identity to the builder.
+    pub fn identity(mut self, identity: codec::CognitoIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Adds the specified invoked function ARN to the builder.
+    pub fn invoked_function_arn(mut self, invoked_function_arn: &str) -> Self {
+        self.invoked_function_arn = Some(invoked_function_arn.into());
+        self
+    }
+
     /// Adds the specified request ID to the builder.
     pub fn request_id(mut self, request_id: &str) -> Self {
         self.request_id = Some(request_id.into());
@@ -259,12 +684,43 @@ impl InvocationEventBuilder {
     pub fn build(self) -> InvocationEvent {
         InvocationEvent {
             body: self.body,
+            client_context: self.client_context,
+            deadline_ms: self.deadline_ms,
+            identity: self.identity,
+            invoked_function_arn: self.invoked_function_arn,
             request_id: self.request_id,
             trace_id: self.trace_id,
         }
     }
 }
 
+/// Represents the static settings of the function being run.
+#[derive(Clone, Default)]
+pub(crate) struct FunctionSettings {
+    function_name: String,
+    function_version: String,
+    memory_limit_in_mb: u32,
+    log_group_name: String,
+    log_stream_name: String,
+}
+
+impl FunctionSettings {
+    /// Creates a new `FunctionSettings` from the specified capability configuration values.
+    pub fn from_config(values: &HashMap<String, String>) -> Self {
+        let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+
+        Self {
+            function_name: value("AWS_LAMBDA_FUNCTION_NAME"),
+            function_version: value("AWS_LAMBDA_FUNCTION_VERSION"),
+            memory_limit_in_mb: value("AWS_LAMBDA_FUNCTION_MEMORY_SIZE")
+                .parse()
+                .unwrap_or_default(),
+            log_group_name: value("AWS_LAMBDA_LOG_GROUP_NAME"),
+            log_stream_name: value("AWS_LAMBDA_LOG_STREAM_NAME"),
+        }
+    }
+}
+
 /// Represents an invocation response.
 pub(crate) struct InvocationResponse {
     body: Vec<u8>,
@@ -297,7 +753,18 @@ pub(crate) struct InvocationError {
     request_id: String,
 }
 
+/// An error reduced to its type and a short message.
+#[derive(thiserror::Error, Debug)]
+#[error("{}", error_message)]
+struct CompactError {
+    error_type: String,
+    error_message: String,
+}
+
 impl InvocationError {
+    /// The maximum length of a compact error's message.
+    const COMPACT_MESSAGE_LEN: usize = 1024;
+
     /// Creates a new `InvocationError` with the specified error and request ID.
     pub fn new(error: anyhow::Error, request_id: &str) -> Self {
         Self {
@@ -311,6 +778,96 @@ impl InvocationError {
         self.error.to_string()
     }
 
+    /// Returns the error type.
+    /// The error type is sent in a header, so an actor's error type
+    /// that isn't printable ASCII is replaced by the default error type.
+    pub fn error_type(&self) -> &str {
+        if let Some(e) = self.error.downcast_ref::<ActorError>() {
+            if e.error_type()
+                .bytes()
+                .all(|b| b == b' ' || b.is_ascii_graphic())
+            {
+                return e.error_type();
+            }
+        }
+        if let Some(e) = self.error.downcast_ref::<CompactError>() {
+            return e.error_type.as_str();
+        }
+        if let Some(RuntimeApiError::PayloadTooLarge { .. }) =
+            self.error.downcast_ref::<RuntimeApiError>()
+        {
+            return "Function.ResponseSizeTooLarge";
+        }
+        if let Some(e) = self.error.downcast_ref::<DispatcherError>() {
+            return match e {
+                DispatcherError::NotDispatched { .. } => "Actor.NotDispatched",
+                DispatcherError::RequestSerialization { .. } => "Runtime.RequestSerialization",
+                DispatcherError::ResponseDeserialization { .. } => {
+                    "Runtime.ResponseDeserialization"
+                }
+            };
+        }
+        if self.error.is::<DeadlineExceededError>() {
+            return "Runtime.Timeout";
+        }
+        if self.error.is::<ActorBusyError>() {
+            return "Actor.Busy";
+        }
+        if self.error.is::<NotHttpRequestError>() {
+            return "Runtime.NotHttpRequest";
+        }
+
+        "InvocationError"
+    }
+
+    /// Returns any additional error data.
+    pub fn data(&self) -> Option<&serde_json::Value> {
+        self.error
+            .downcast_ref::<ActorError>()
+            .and_then(|e| e.data())
+    }
+
+    /// Returns the messages of the error's chain of causes.
+    pub fn stack_trace(&self) -> Vec<String> {
+        self.error.chain().skip(1).map(|e| e.to_string()).collect()
+    }
+
+    /// Returns the JSON payload to send to the AWS Lambda runtime.
+    pub fn payload(&self) -> serde_json::Value {
+        let mut payload = serde_json::json!({
+            "errorMessage": self.error_message(),
+            "errorType": self.error_type(),
+            "stackTrace": self.stack_trace(),
+        });
+        if let Some(data) = self.data() {
+            payload["data"] = data.clone();
+        }
+
+        payload
+    }
+
+    /// Returns a copy of this error small enough to be accepted by the AWS Lambda runtime.
+    /// The error message is truncated and the stack trace and any data are dropped.
+    pub fn compact(&self) -> Self {
+        let mut error_message = self.error_message();
+        if error_message.len() > Self::COMPACT_MESSAGE_LEN {
+            let mut len = Self::COMPACT_MESSAGE_LEN;
+            while !error_message.is_char_boundary(len) {
+                len -= 1;
+            }
+            error_message.truncate(len);
+        }
+
+        Self::new(
+            CompactError {
+                error_type: self.error_type().into(),
+                error_message,
+            }
+            .into(),
+            &self.request_id,
+        )
+    }
+
     /// Returns the request ID.
     pub fn request_id(&self) -> &str {
         self.request_id.as_str()
@@ -339,6 +896,7 @@ mod tests {
     use super::*;
     use crate::tests_common::*;
     use httptest::{matchers::*, responders::*, Expectation, Server};
+    use std::io::Read;
 
     /// Returns the server's endpoint.
     fn endpoint(server: &Server) -> String {
@@ -357,11 +915,35 @@ mod tests {
             .respond_with(status_code(400)),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result = client.next_invocation_event();
-        assert!(result.is_ok());
-        let event = result.unwrap();
-        assert!(event.is_none());
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        match e.downcast_ref::<RuntimeApiError>() {
+            Some(RuntimeApiError::InvalidRequestId { .. }) => {}
+            _ => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn runtime_client_next_invocation_event_container_error() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("GET"),
+                request::path(format!("/{}", RuntimeClient::next_invocation_event_path()))
+            ])
+            .respond_with(status_code(500)),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let result = client.next_invocation_event();
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        assert!(e.is::<RuntimeApiError>());
+        assert!(e.downcast_ref::<RuntimeApiError>().unwrap().is_fatal());
     }
 
     #[test]
@@ -375,7 +957,7 @@ mod tests {
             .respond_with(status_code(200)),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result = client.next_invocation_event();
         assert!(result.is_ok());
         let event = result.unwrap();
@@ -397,7 +979,7 @@ mod tests {
             .respond_with(status_code(200).body(EVENT_BODY)),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result = client.next_invocation_event();
         assert!(result.is_ok());
         let event = result.unwrap();
@@ -424,7 +1006,7 @@ mod tests {
             ),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result = client.next_invocation_event();
         assert!(result.is_ok());
         let event = result.unwrap();
@@ -437,6 +1019,221 @@ mod tests {
         assert_eq!(TRACE_ID, event.trace_id().unwrap());
     }
 
+    #[test]
+    fn runtime_client_next_invocation_event_with_context() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("GET"),
+                request::path(format!("/{}", RuntimeClient::next_invocation_event_path()))
+            ])
+            .respond_with(
+                status_code(200)
+                    .body(EVENT_BODY)
+                    .append_header(REQUEST_ID_HEADER_NAME, REQUEST_ID)
+                    .append_header(DEADLINE_HEADER_NAME, "1542409706888")
+                    .append_header(FUNCTION_ARN_HEADER_NAME, FUNCTION_ARN)
+                    .append_header(
+                        CLIENT_CONTEXT_HEADER_NAME,
+                        r#"{"client":{"app_title":"TITLE"},"custom":{"key1":"value1"}}"#,
+                    )
+                    .append_header(
+                        COGNITO_IDENTITY_HEADER_NAME,
+                        r#"{"cognitoIdentityId":"ID","cognitoIdentityPoolId":"POOL_ID"}"#,
+                    ),
+            ),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let result = client.next_invocation_event();
+        assert!(result.is_ok());
+        let event = result.unwrap();
+        assert!(event.is_some());
+        let event = event.unwrap();
+        assert_eq!(Some(1_542_409_706_888), event.deadline_ms());
+        assert_eq!(Some(FUNCTION_ARN), event.invoked_function_arn());
+        assert!(event.client_context().is_some());
+        assert_eq!("TITLE", event.client_context().unwrap().client.app_title);
+        assert!(event.identity().is_some());
+        assert_eq!(
+            "POOL_ID",
+            event.identity().unwrap().cognito_identity_pool_id
+        );
+
+        let mut values = HashMap::new();
+        values.insert("AWS_LAMBDA_FUNCTION_NAME".into(), "NAME".into());
+        values.insert("AWS_LAMBDA_FUNCTION_MEMORY_SIZE".into(), "128".into());
+        let context = event.context(&FunctionSettings::from_config(&values), EventSource::Sqs);
+        assert_eq!(REQUEST_ID, context.request_id);
+        assert_eq!(1_542_409_706_888, context.deadline_ms);
+        assert_eq!("NAME", context.function_name);
+        assert_eq!(128, context.memory_limit_in_mb);
+        assert!(context.function_version.is_empty());
+        assert_eq!("aws:sqs", context.event_source);
+    }
+
+    #[test]
+    fn runtime_client_next_invocation_event_invalid_deadline() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("GET"),
+                request::path(format!("/{}", RuntimeClient::next_invocation_event_path()))
+            ])
+            .respond_with(
+                status_code(200)
+                    .body(EVENT_BODY)
+                    .append_header(REQUEST_ID_HEADER_NAME, REQUEST_ID)
+                    .append_header(DEADLINE_HEADER_NAME, "NOT_A_NUMBER"),
+            ),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let result = client.next_invocation_event();
+        assert!(result.is_ok());
+        let event = result.unwrap();
+        assert!(event.is_some());
+        let event = event.unwrap();
+        assert_eq!(Some(REQUEST_ID), event.request_id());
+        assert!(event.deadline_ms().is_none());
+    }
+
+    /// Returns client settings that retry quickly.
+    fn fast_retry_settings() -> ClientSettings {
+        let mut values = HashMap::new();
+        values.insert("WASCC_RUNTIME_API_MAX_ATTEMPTS".into(), "3".into());
+        values.insert("WASCC_RUNTIME_API_BACKOFF_MS".into(), "1".into());
+        ClientSettings::from_config(&values)
+    }
+
+    #[test]
+    fn runtime_client_next_invocation_event_retry() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("GET"),
+                request::path(format!("/{}", RuntimeClient::next_invocation_event_path()))
+            ])
+            .times(2..=2)
+            .respond_with(cycle![
+                status_code(503),
+                status_code(200)
+                    .body(EVENT_BODY)
+                    .append_header(REQUEST_ID_HEADER_NAME, REQUEST_ID),
+            ]),
+        );
+
+        let client =
+            RuntimeClient::with_settings(&endpoint(&server), fast_retry_settings()).unwrap();
+        let result = client.next_invocation_event();
+        assert!(result.is_ok());
+        let event = result.unwrap();
+        assert!(event.is_some());
+        assert_eq!(Some(REQUEST_ID), event.unwrap().request_id());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_response_connection_error() {
+        // Nothing is listening on this port once the listener has been dropped.
+        let ep = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+
+        let client = RuntimeClient::with_settings(&ep, fast_retry_settings()).unwrap();
+        let result = client
+            .send_invocation_response(InvocationResponse::new(RESPONSE_BODY.to_vec(), REQUEST_ID));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_response_timeout() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!(
+                    "/{}",
+                    RuntimeClient::invocation_response_path(REQUEST_ID)
+                )),
+            ])
+            .times(1)
+            .respond_with(delay_and_then(Duration::from_millis(500), status_code(200))),
+        );
+
+        let mut settings = fast_retry_settings();
+        settings.post_timeout = Some(Duration::from_millis(50));
+        let client = RuntimeClient::with_settings(&endpoint(&server), settings).unwrap();
+        let result = client
+            .send_invocation_response(InvocationResponse::new(RESPONSE_BODY.to_vec(), REQUEST_ID));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_response_payload_too_large() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!(
+                    "/{}",
+                    RuntimeClient::invocation_response_path(REQUEST_ID)
+                )),
+            ])
+            .respond_with(status_code(413)),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let result = client
+            .send_invocation_response(InvocationResponse::new(RESPONSE_BODY.to_vec(), REQUEST_ID));
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        match e.downcast_ref::<RuntimeApiError>() {
+            Some(RuntimeApiError::PayloadTooLarge { .. }) => {}
+            _ => panic!("unexpected error: {}", e),
+        }
+        assert_eq!(
+            "Function.ResponseSizeTooLarge",
+            InvocationError::new(e, REQUEST_ID).error_type()
+        );
+    }
+
+    #[test]
+    fn invocation_error_compact() {
+        let error = ActorError::from(codec::FunctionError {
+            error_type: "OrderNotFound".into(),
+            error_message: "x".repeat(10_000),
+            data: b"42".to_vec(),
+        });
+        let error = InvocationError::new(error.into(), REQUEST_ID).compact();
+        assert_eq!("OrderNotFound", error.error_type());
+        assert_eq!(
+            InvocationError::COMPACT_MESSAGE_LEN,
+            error.error_message().len()
+        );
+        assert!(error.data().is_none());
+        assert!(error.stack_trace().is_empty());
+        assert_eq!(REQUEST_ID, error.request_id());
+    }
+
+    #[test]
+    fn client_settings_backoff() {
+        let settings = ClientSettings::default();
+        for attempt in 1..20 {
+            assert!(settings.backoff(attempt) <= settings.max_delay);
+        }
+
+        let mut values = HashMap::new();
+        values.insert("WASCC_RUNTIME_API_POST_TIMEOUT_MS".into(), "0".into());
+        let settings = ClientSettings::from_config(&values);
+        assert!(settings.post_timeout.is_none());
+        assert_eq!(
+            ClientSettings::default().max_attempts,
+            settings.max_attempts
+        );
+    }
+
     #[test]
     fn runtime_client_send_invocation_response() {
         let server = Server::run();
@@ -452,7 +1249,7 @@ mod tests {
             .respond_with(status_code(200)),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result = client
             .send_invocation_response(InvocationResponse::new(RESPONSE_BODY.to_vec(), REQUEST_ID));
         assert!(result.is_ok());
@@ -472,12 +1269,303 @@ mod tests {
             .respond_with(status_code(200)),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result =
             client.send_invocation_error(InvocationError::new(anyhow!(ERROR_MESSAGE), REQUEST_ID));
         assert!(result.is_ok());
     }
 
+    /// The AWS Lambda runtime's response to a successfully streamed response.
+    const ACCEPTED: &str = "HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n";
+
+    /// Runs a stand-in AWS Lambda runtime that accepts a single streamed response and replies with the specified response.
+    /// Returns the endpoint and a handle that yields the raw request.
+    fn streaming_server(response: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !(request.ends_with(b"\r\n\r\n") && request.windows(5).any(|w| w == b"\r\n0\r\n"))
+            {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (endpoint, handle)
+    }
+
+    #[test]
+    fn runtime_client_stream_invocation_response() {
+        let (endpoint, handle) = streaming_server(ACCEPTED);
+
+        let client = RuntimeClient::new(&endpoint).unwrap();
+        let result = client.stream_invocation_response(REQUEST_ID, Some("text/event-stream"));
+        assert!(result.is_ok());
+        let mut stream = result.unwrap();
+        assert!(stream.send_chunk(b"Hello").is_ok());
+        assert!(stream.send_chunk(b"").is_ok());
+        assert!(stream.send_chunk(b" world").is_ok());
+        assert!(stream.finish().is_ok());
+
+        let request = handle.join().unwrap();
+        assert!(request.starts_with(&format!(
+            "POST /{} HTTP/1.1\r\n",
+            RuntimeClient::invocation_response_path(REQUEST_ID)
+        )));
+        assert!(request.contains("Lambda-Runtime-Function-Response-Mode: streaming\r\n"));
+        assert!(request.contains("Content-Type: text/event-stream\r\n"));
+        assert!(request.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(request.ends_with("\r\n\r\n5\r\nHello\r\n6\r\n world\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn runtime_client_stream_invocation_response_error() {
+        let (endpoint, handle) = streaming_server(ACCEPTED);
+
+        let client = RuntimeClient::new(&endpoint).unwrap();
+        let mut stream = client.stream_invocation_response(REQUEST_ID, None).unwrap();
+        assert!(stream.send_chunk(b"Hello").is_ok());
+        let result =
+            stream.finish_with_error(InvocationError::new(anyhow!(ERROR_MESSAGE), REQUEST_ID));
+        assert!(result.is_ok());
+
+        let request = handle.join().unwrap();
+        assert!(request.contains("Content-Type: application/octet-stream\r\n"));
+        assert!(request.contains(
+            "Trailer: Lambda-Runtime-Function-Error-Type, Lambda-Runtime-Function-Error-Body\r\n"
+        ));
+        let body = base64::encode(
+            serde_json::to_vec(&serde_json::json!({
+                "errorMessage": ERROR_MESSAGE,
+                "errorType": "InvocationError",
+                "stackTrace": [],
+            }))
+            .unwrap(),
+        );
+        assert!(request.ends_with(&format!(
+            "0\r\nLambda-Runtime-Function-Error-Type: InvocationError\r\n\
+             Lambda-Runtime-Function-Error-Body: {}\r\n\r\n",
+            body
+        )));
+    }
+
+    #[test]
+    fn runtime_client_stream_invocation_response_payload_too_large() {
+        let (endpoint, handle) =
+            streaming_server("HTTP/1.1 413 Payload Too Large\r\nContent-Length: 0\r\n\r\n");
+
+        let client = RuntimeClient::new(&endpoint).unwrap();
+        let mut stream = client.stream_invocation_response(REQUEST_ID, None).unwrap();
+        assert!(stream.send_chunk(b"Hello").is_ok());
+        let result = stream.finish();
+        assert!(result.is_err());
+        match result.err().unwrap().downcast_ref::<RuntimeApiError>() {
+            Some(RuntimeApiError::PayloadTooLarge { .. }) => {}
+            _ => panic!("unexpected error"),
+        }
+
+        assert!(handle.join().is_ok());
+    }
+
+    #[test]
+    fn runtime_client_stream_invocation_response_chunked_error() {
+        let (endpoint, handle) = streaming_server(
+            "HTTP/1.1 400 Bad Request\r\nTransfer-Encoding: chunked\r\n\r\n\
+             D\r\nInvalid reque\r\n2\r\nst\r\n0\r\n\r\n",
+        );
+
+        let client = RuntimeClient::new(&endpoint).unwrap();
+        let mut stream = client.stream_invocation_response(REQUEST_ID, None).unwrap();
+        assert!(stream.send_chunk(b"Hello").is_ok());
+        let result = stream.finish();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Invalid request"));
+
+        assert!(handle.join().is_ok());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_error_timeout() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!(
+                    "/{}",
+                    RuntimeClient::invocation_error_path(REQUEST_ID)
+                )),
+                request::headers(contains(entry(
+                    "lambda-runtime-function-error-type",
+                    "Runtime.Timeout"
+                ))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "errorMessage": "Guest MODULE_ID failed to respond within 100 ms",
+                    "errorType": "Runtime.Timeout",
+                    "stackTrace": [],
+                })))),
+            ])
+            .respond_with(status_code(200)),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let error = DeadlineExceededError::new(MODULE_ID, std::time::Duration::from_millis(100));
+        let result = client.send_invocation_error(InvocationError::new(error.into(), REQUEST_ID));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_error_not_dispatched() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!(
+                    "/{}",
+                    RuntimeClient::invocation_error_path(REQUEST_ID)
+                )),
+                request::headers(contains(entry(
+                    "lambda-runtime-function-error-type",
+                    "Actor.NotDispatched"
+                ))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "errorMessage": "Guest MODULE_ID failed to handle OP: ERROR",
+                    "errorType": "Actor.NotDispatched",
+                    "stackTrace": ["ERROR"],
+                })))),
+            ])
+            .respond_with(status_code(200)),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let error = DispatcherError::NotDispatched {
+            actor: MODULE_ID.into(),
+            op: "OP".into(),
+            source: anyhow!(ERROR_MESSAGE),
+        };
+        let result = client.send_invocation_error(InvocationError::new(error.into(), REQUEST_ID));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_error_actor_error() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!(
+                    "/{}",
+                    RuntimeClient::invocation_error_path(REQUEST_ID)
+                )),
+                request::headers(contains(entry(
+                    "lambda-runtime-function-error-type",
+                    "OrderNotFound"
+                ))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "errorMessage": ERROR_MESSAGE,
+                    "errorType": "OrderNotFound",
+                    "stackTrace": [],
+                    "data": {"orderId": 42},
+                })))),
+            ])
+            .respond_with(status_code(200)),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let error = ActorError::from(codec::FunctionError {
+            error_type: "OrderNotFound".into(),
+            error_message: ERROR_MESSAGE.into(),
+            data: br#"{"orderId":42}"#.to_vec(),
+        });
+        let result = client.send_invocation_error(InvocationError::new(error.into(), REQUEST_ID));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn runtime_client_send_invocation_error_invalid_error_type() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path(format!(
+                    "/{}",
+                    RuntimeClient::invocation_error_path(REQUEST_ID)
+                )),
+                request::headers(contains(entry(
+                    "lambda-runtime-function-error-type",
+                    "InvocationError"
+                ))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "errorMessage": ERROR_MESSAGE,
+                    "errorType": "InvocationError",
+                    "stackTrace": [],
+                })))),
+            ])
+            .respond_with(status_code(200)),
+        );
+
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
+        let error = ActorError::from(codec::FunctionError {
+            error_type: "Order\nNotFound".into(),
+            error_message: ERROR_MESSAGE.into(),
+            data: vec![],
+        });
+        let result = client.send_invocation_error(InvocationError::new(error.into(), REQUEST_ID));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn invocation_error_error_type() {
+        let error = DispatcherError::RequestSerialization {
+            source: anyhow!(ERROR_MESSAGE),
+        };
+        assert_eq!(
+            "Runtime.RequestSerialization",
+            InvocationError::new(error.into(), REQUEST_ID).error_type()
+        );
+
+        let error = DispatcherError::ResponseDeserialization {
+            source: anyhow!(ERROR_MESSAGE),
+        };
+        assert_eq!(
+            "Runtime.ResponseDeserialization",
+            InvocationError::new(error.into(), REQUEST_ID).error_type()
+        );
+
+        assert_eq!(
+            "Runtime.NotHttpRequest",
+            InvocationError::new(NotHttpRequestError.into(), REQUEST_ID).error_type()
+        );
+
+        for error_type in &[
+            "Order\r\nNotFound",
+            "OrderNotFound\u{7f}",
+            "CommandeIntrouvée",
+        ] {
+            let error = ActorError::from(codec::FunctionError {
+                error_type: (*error_type).into(),
+                error_message: ERROR_MESSAGE.into(),
+                data: vec![],
+            });
+            assert_eq!(
+                "InvocationError",
+                InvocationError::new(error.into(), REQUEST_ID).error_type()
+            );
+        }
+
+        let error = anyhow!(ERROR_MESSAGE).context("CONTEXT");
+        let error = InvocationError::new(error, REQUEST_ID);
+        assert_eq!("InvocationError", error.error_type());
+        assert_eq!(vec![ERROR_MESSAGE.to_string()], error.stack_trace());
+    }
+
     #[test]
     fn runtime_client_send_initialization_error() {
         let server = Server::run();
@@ -489,7 +1577,7 @@ mod tests {
             .respond_with(status_code(200)),
         );
 
-        let client = RuntimeClient::new(&endpoint(&server));
+        let client = RuntimeClient::new(&endpoint(&server)).unwrap();
         let result = <RuntimeClient as Client>::send_initialization_error(
             &client,
             InitializationError::new(anyhow!(ERROR_MESSAGE)),
@@ -508,7 +1596,7 @@ mod tests {
             .respond_with(status_code(200)),
         );
 
-        let reporter = initerr_reporter(&endpoint(&server));
+        let reporter = initerr_reporter(&endpoint(&server)).unwrap();
         let result = reporter.send_initialization_error(anyhow!(ERROR_MESSAGE));
         assert!(result.is_ok());
     }
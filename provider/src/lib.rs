@@ -22,12 +22,29 @@ extern crate anyhow;
 extern crate log;
 
 pub use crate::lambda::{initerr_reporter, InitializationErrorReporter};
-pub use crate::provider::{default_http_request_provider, default_raw_event_provider};
+pub use crate::provider::{
+    default_http_request_provider, default_raw_event_provider, default_sqs_provider,
+    default_stream_provider, default_websocket_provider, EventRouter,
+};
+pub use crate::telemetry::default_telemetry_provider;
 
+mod authorizer;
+mod batch;
+mod classify;
+mod compression;
+mod cors;
 mod dispatch;
+mod extension;
+mod function_url;
 mod http;
 mod lambda;
 mod provider;
+mod route;
+mod sigv4;
+mod sqs;
+mod stream;
+mod telemetry;
+mod websocket;
 
 /// Represents a shared host dispatcher.
 pub(crate) type HostDispatcher =
@@ -44,11 +61,16 @@ mod tests_common {
     use std::collections::HashMap;
     use std::error::Error;
     use std::sync::{Arc, RwLock};
+    use std::time::Duration;
 
     use super::HostDispatcher;
+    use crate::cors::ActorCorsSettings;
+    use crate::dispatch::HttpRequestDispatcher;
+    use crate::http::HttpSettings;
 
     pub(crate) const ERROR_MESSAGE: &str = "ERROR";
     pub(crate) const EVENT_BODY: &'static [u8] = b"EVENT_BODY";
+    pub(crate) const FUNCTION_ARN: &str = "arn:aws:lambda:us-west-2:123456789012:function:FUNCTION";
     pub(crate) const MODULE_ID: &str = "MODULE_ID";
     pub(crate) const REQUEST_ID: &str = "REQUEST_ID";
     pub(crate) const RESPONSE_BODY: &'static [u8] = b"RESPONSE_BODY";
@@ -92,6 +114,17 @@ mod tests_common {
         }
     }
 
+    /// Represents a mock `wascc_codec::capabilities::Dispatcher` shared with the test.
+    pub(crate) struct SharedWasccDispatcher<T>(pub Arc<MockWasccDispatcher<T>>);
+
+    impl<T: Any + Serialize + Send + Sync> wascc_codec::capabilities::Dispatcher
+        for SharedWasccDispatcher<T>
+    {
+        fn dispatch(&self, actor: &str, op: &str, msg: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+            self.0.dispatch(actor, op, msg)
+        }
+    }
+
     /// Returns a boxed mock `wascc_codec::capabilities::Dispatcher`.
     pub(crate) fn boxed_mock_dispatcher<T: Any + Serialize + Send + Sync>(
         response: T,
@@ -106,6 +139,15 @@ mod tests_common {
         Arc::new(RwLock::new(boxed_mock_dispatcher(response)))
     }
 
+    /// Returns an HTTP request dispatcher with the default settings and no CORS.
+    pub(crate) fn http_dispatcher(host_dispatcher: HostDispatcher) -> HttpRequestDispatcher {
+        HttpRequestDispatcher::new(
+            host_dispatcher,
+            HttpSettings::default(),
+            ActorCorsSettings::default(),
+        )
+    }
+
     /// Represents a `wascc_codec::capabilities::Dispatcher` that returns an error.
     pub(crate) struct ErrorWasccDispatcher {}
 
@@ -137,6 +179,37 @@ mod tests_common {
         Arc::new(RwLock::new(boxed_error_dispatcher()))
     }
 
+    /// Returns a test invocation context.
+    pub(crate) fn context() -> codec::Context {
+        codec::Context {
+            request_id: REQUEST_ID.into(),
+            trace_id: Some(TRACE_ID.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Represents a `wascc_codec::capabilities::Dispatcher` that takes a long time to return an error.
+    pub(crate) struct SlowWasccDispatcher {
+        delay: Duration,
+    }
+
+    impl wascc_codec::capabilities::Dispatcher for SlowWasccDispatcher {
+        fn dispatch(
+            &self,
+            _actor: &str,
+            _op: &str,
+            _msg: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn Error>> {
+            std::thread::sleep(self.delay);
+            Err(anyhow!(ERROR_MESSAGE).into())
+        }
+    }
+
+    /// Returns a `HostDispatcher` that takes the specified time to return an error.
+    pub(crate) fn slow_host_dispatcher(delay: Duration) -> HostDispatcher {
+        Arc::new(RwLock::new(Box::new(SlowWasccDispatcher { delay })))
+    }
+
     /// Returns a query string map for a request.
     fn request_query_string() -> HashMap<String, String> {
         let mut qs = HashMap::new();
@@ -260,4 +333,94 @@ mod tests_common {
             is_base64_encoded: false,
         }
     }
+
+    /// Returns a valid API Gateway WebSocket API event for the specified route.
+    pub(crate) fn valid_websocket_request(route_key: &str, event_type: &str) -> serde_json::Value {
+        serde_json::json!({
+            "requestContext": {
+                "routeKey": route_key,
+                "eventType": event_type,
+                "extendedRequestId": "abc",
+                "requestTime": "09/Feb/2019:11:11:11 +0000",
+                "messageDirection": "IN",
+                "stage": "prod",
+                "connectedAt": 1549710671000u64,
+                "requestTimeEpoch": 1549710671000u64,
+                "requestId": "abc",
+                "domainName": "abcdefg.execute-api.us-east-1.amazonaws.com",
+                "connectionId": "L0SM9cOFvHcCIhw=",
+                "apiId": "abcdefg",
+            },
+            "isBase64Encoded": false,
+        })
+    }
+
+    /// Returns a valid API Gateway REST API `TOKEN` authorizer request.
+    pub(crate) fn valid_token_authorizer_request() -> serde_json::Value {
+        serde_json::json!({
+            "type": "TOKEN",
+            "authorizationToken": "Bearer abc",
+            "methodArn": "arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/pets",
+        })
+    }
+
+    /// Returns a valid API Gateway HTTP API authorizer request in the 2.0 payload format.
+    pub(crate) fn valid_http_api_authorizer_request() -> serde_json::Value {
+        serde_json::json!({
+            "version": "2.0",
+            "type": "REQUEST",
+            "routeArn": "arn:aws:execute-api:us-east-1:123456789012:abcdef123/$default/POST/pets",
+            "identitySource": ["Bearer abc"],
+            "routeKey": "POST /pets",
+            "rawPath": "/pets",
+            "rawQueryString": "a=1",
+            "headers": {"authorization": "Bearer abc"},
+            "requestContext": {
+                "accountId": "123456789012",
+                "apiId": "abcdef123",
+                "domainName": "abcdef123.execute-api.us-east-1.amazonaws.com",
+                "http": {
+                    "method": "POST",
+                    "path": "/pets",
+                    "protocol": "HTTP/1.1",
+                    "sourceIp": "192.0.2.1",
+                    "userAgent": "agent",
+                },
+                "requestId": "id",
+                "routeKey": "POST /pets",
+                "stage": "$default",
+            },
+        })
+    }
+
+    /// Returns a valid Lambda function URL request.
+    pub(crate) fn valid_function_url_request() -> serde_json::Value {
+        serde_json::json!({
+            "version": "2.0",
+            "routeKey": "$default",
+            "rawPath": "/my/path",
+            "rawQueryString": "",
+            "headers": {"accept": "application/json"},
+            "requestContext": {
+                "accountId": "anonymous",
+                "apiId": "abcdefg",
+                "domainName": "abcdefg.lambda-url.us-east-1.on.aws",
+                "domainPrefix": "abcdefg",
+                "http": {
+                    "method": "POST",
+                    "path": "/my/path",
+                    "protocol": "HTTP/1.1",
+                    "sourceIp": "192.0.2.1",
+                    "userAgent": "agent",
+                },
+                "requestId": "id",
+                "routeKey": "$default",
+                "stage": "$default",
+                "time": "12/Mar/2020:19:03:58 +0000",
+                "timeEpoch": 1583348638390u64,
+            },
+            "body": "Hello world",
+            "isBase64Encoded": false,
+        })
+    }
 }
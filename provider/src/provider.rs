@@ -18,17 +18,36 @@
 
 use wascc_codec::capabilities::CapabilityProvider;
 use wascc_codec::core::{CapabilityConfiguration, OP_BIND_ACTOR};
-use wascc_codec::deserialize;
+use wascc_codec::{deserialize, serialize};
 
 use std::any::Any;
+use std::collections::HashMap;
 use std::env;
+use std::iter;
 use std::marker::PhantomData;
+use std::mem;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-
-use crate::dispatch::{HttpRequestDispatcher, InvocationEventDispatcher, RawEventDispatcher};
-use crate::lambda::{Client, InvocationError, InvocationResponse, RuntimeClient};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use signal_hook::iterator::Signals;
+
+use crate::classify::ClassifiedEvent;
+use crate::cors::{ActorCorsSettings, CorsSettings};
+use crate::dispatch::{
+    DeadlineDispatcher, HttpRequestDispatcher, InvocationEventDispatcher, RawEventDispatcher,
+    SqsEventDispatcher, StreamEventDispatcher, WebSocketEventDispatcher,
+};
+use crate::extension::{ExtensionClient, ExtensionListener, RuntimeExtensionClient};
+use crate::http::HttpSettings;
+use crate::lambda::{
+    Client, ClientSettings, FunctionSettings, InvocationError, InvocationResponse, ResponseStream,
+    RuntimeApiError, RuntimeClient,
+};
+use crate::route::RoutingTable;
+use crate::sigv4::Credentials;
+use crate::websocket::ManagementApiClient;
 use crate::HostDispatcher;
 
 //
@@ -96,33 +115,147 @@ impl StopperW for Stopper {
     }
 }
 
+/// Represents an actor bound to one of the capability providers sharing a router.
+struct BoundActor {
+    dispatcher: Arc<dyn InvocationEventDispatcher + Send + Sync>,
+    host_dispatcher: HostDispatcher,
+}
+
+/// Dispatches invocation events to each bound actor using the dispatcher of the provider it's bound to.
+#[derive(Clone, Default)]
+struct ActorDispatchers(Arc<RwLock<HashMap<String, BoundActor>>>);
+
+impl ActorDispatchers {
+    /// Binds an actor to the specified dispatchers of its capability provider.
+    fn bind(
+        &self,
+        actor: &str,
+        host_dispatcher: HostDispatcher,
+        dispatcher: impl InvocationEventDispatcher + Send + Sync + 'static,
+    ) {
+        self.0.write().unwrap().insert(
+            actor.into(),
+            BoundActor {
+                dispatcher: Arc::new(dispatcher),
+                host_dispatcher,
+            },
+        );
+    }
+
+    /// Returns the host dispatcher of each of the specified actors that is bound.
+    fn host_dispatchers(&self, actors: &[String]) -> Vec<(String, HostDispatcher)> {
+        let lock = self.0.read().unwrap();
+        actors
+            .iter()
+            .filter_map(|actor| {
+                lock.get(actor)
+                    .map(|bound| (actor.clone(), Arc::clone(&bound.host_dispatcher)))
+            })
+            .collect()
+    }
+}
+
+impl InvocationEventDispatcher for ActorDispatchers {
+    /// Attempts to dispatch a Lambda invocation event, returning an invocation response.
+    /// The event is dispatched by the provider that the actor is bound to.
+    fn dispatch_invocation_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let dispatcher = match self.0.read().unwrap().get(actor) {
+            Some(bound) => Arc::clone(&bound.dispatcher),
+            None => return Err(anyhow!("Actor {} is not bound", actor)),
+        };
+
+        dispatcher.dispatch_invocation_event(actor, context, event)
+    }
+
+    /// Attempts to dispatch a classified Lambda invocation event, returning an invocation response.
+    /// The event is dispatched by the provider that the actor is bound to.
+    fn dispatch_classified_event(
+        &self,
+        actor: &str,
+        context: &codec::Context,
+        event: ClassifiedEvent,
+    ) -> anyhow::Result<Vec<u8>> {
+        let dispatcher = match self.0.read().unwrap().get(actor) {
+            Some(bound) => Arc::clone(&bound.dispatcher),
+            None => return Err(anyhow!("Actor {} is not bound", actor)),
+        };
+
+        dispatcher.dispatch_classified_event(actor, context, event)
+    }
+}
+
+/// Routes the invocation events from a single poller to the actors bound to
+/// any of the capability providers sharing it.
+struct Router<C, S> {
+    actors: ActorDispatchers,
+    poller: RwLock<Option<Arc<Poller<C, S>>>>,
+    routes: Arc<RwLock<RoutingTable>>,
+    stopper: S,
+}
+
+impl<C, S> Router<C, S> {
+    /// Creates a new, empty `Router`.
+    fn new(stopper: S) -> Self {
+        Self {
+            actors: ActorDispatchers::default(),
+            poller: RwLock::new(None),
+            routes: Arc::new(RwLock::new(RoutingTable::default())),
+            stopper,
+        }
+    }
+}
+
+/// Routes invocation events to the actors bound to any of the capability providers created with it.
+/// The Lambda event machinery is polled once for all of the providers, so events from different sources
+/// can be routed to actors bound to different capabilities, e.g. SQS messages to an `awslambda:sqs` actor
+/// and API Gateway requests to a `wascc:http_server` actor.
+#[derive(Clone)]
+pub struct EventRouter(Arc<Router<RuntimeClient, Stopper>>);
+
+impl EventRouter {
+    /// Creates a new, empty `EventRouter`.
+    pub fn new() -> Self {
+        Self(Arc::new(Router::new(Stopper::new())))
+    }
+}
+
+impl Default for EventRouter {
+    /// Returns a new, empty `EventRouter`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Represents a waSCC AWS Lambda runtime provider.
 struct LambdaProvider<S, CF, C, DF, D> {
     host_dispatcher: HostDispatcher,
-    stopper: S,
+    router: Arc<Router<C, S>>,
     client_factory: CF,
-    client_type: PhantomData<C>,
     dispatcher_factory: DF,
     dispatcher_type: PhantomData<D>,
 }
 
 impl<
-        S: Clone + Send + StopperR + 'static,
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
         CF: ClientFactory<C>,
-        C: Send + Client + 'static,
+        C: Send + Sync + Client + 'static,
         DF: DispatcherFactory<D>,
-        D: Send + InvocationEventDispatcher + 'static,
+        D: Send + Sync + InvocationEventDispatcher + 'static,
     > LambdaProvider<S, CF, C, DF, D>
 {
-    /// Creates a new, empty `LambdaProvider`.
-    pub fn new(stopper: S, client_factory: CF, dispatcher_factory: DF) -> Self {
+    /// Creates a new, empty `LambdaProvider` that routes invocation events with the specified router.
+    pub fn new(router: Arc<Router<C, S>>, client_factory: CF, dispatcher_factory: DF) -> Self {
         Self {
             host_dispatcher: Arc::new(RwLock::new(Box::new(
                 wascc_codec::capabilities::NullDispatcher::new(),
             ))),
-            stopper,
+            router,
             client_factory,
-            client_type: PhantomData,
             dispatcher_factory,
             dispatcher_type: PhantomData,
         }
@@ -149,6 +282,9 @@ impl<
             OP_BIND_ACTOR if actor == "system" => {
                 self.start_polling(deserialize(msg).map_err(|e| anyhow!("{}", e))?)?
             }
+            codec::OP_STREAM_RESPONSE_CHUNK => {
+                self.stream_response_chunk(deserialize(msg).map_err(|e| anyhow!("{}", e))?)?
+            }
             _ => return Err(anyhow!("Unsupported operation: {}/{}", op, actor)),
         }
 
@@ -156,10 +292,11 @@ impl<
     }
 
     /// Starts polling the Lambda event machinery.
+    /// A single poller, started by the first actor bound to any of the providers sharing the router,
+    /// routes invocation events to all bound actors.
     fn start_polling(&self, config: CapabilityConfiguration) -> anyhow::Result<()> {
         debug!("awslambda:provider start_polling");
 
-        let host_dispatcher = Arc::clone(&self.host_dispatcher);
         let endpoint = match config.values.get("AWS_LAMBDA_RUNTIME_API") {
             Some(ep) => format!("http://{}", ep),
             None => {
@@ -170,21 +307,158 @@ impl<
         };
 
         let module_id = config.module;
-        let stopper = self.stopper.clone();
+        self.router.actors.bind(
+            &module_id,
+            Arc::clone(&self.host_dispatcher),
+            self.dispatcher_factory
+                .new_dispatcher(Arc::clone(&self.host_dispatcher), &config.values),
+        );
+        self.router
+            .routes
+            .write()
+            .unwrap()
+            .add_actor(&module_id, &config.values);
+
+        let mut lock = self.router.poller.write().unwrap();
+        if lock.is_some() {
+            return Ok(());
+        }
 
-        let client = self.client_factory.new_client(&endpoint);
-        let poller = Poller::new(&module_id, client, stopper);
+        let settings = FunctionSettings::from_config(&config.values);
+        let stopper = self.router.stopper.clone();
 
-        let dispatcher = self.dispatcher_factory.new_dispatcher(host_dispatcher);
+        let client = self
+            .client_factory
+            .new_client(&endpoint, &ClientSettings::from_config(&config.values))?;
+        let poller = Arc::new(Poller::new(
+            Arc::clone(&self.router.routes),
+            settings,
+            client,
+            stopper,
+        ));
+        *lock = Some(Arc::clone(&poller));
+
+        // The Lambda machinery only signals the runtime on shutdown once an extension is registered.
+        // The extension must be registered before the poller starts.
+        if let Err(e) = handle_sigterm(
+            self.router.stopper.clone(),
+            self.router.actors.clone(),
+            Arc::clone(&self.router.routes),
+        ) {
+            warn!("Unable to handle SIGTERM: {}", e);
+        }
+        match self
+            .client_factory
+            .new_extension_client(&endpoint)
+            .and_then(ExtensionListener::register)
+        {
+            Ok(listener) => {
+                thread::spawn(move || {
+                    if let Err(e) = listener.run() {
+                        // The Lambda machinery will restart the runtime.
+                        error!("Extension failed: {}", e);
+                        std::process::exit(1);
+                    }
+                });
+            }
+            Err(e) => warn!("Unable to register extension: {}", e),
+        }
+
+        let dispatcher = DeadlineDispatcher::new(self.router.actors.clone());
 
         thread::spawn(move || {
-            info!("Starting poller for actor {}", module_id);
+            info!("Starting poller");
 
-            poller.run(dispatcher);
+            if let Err(e) = poller.run(dispatcher) {
+                // The Lambda machinery will restart the runtime.
+                error!("Poller failed: {}", e);
+                std::process::exit(1);
+            }
         });
 
         Ok(())
     }
+
+    /// Sends a chunk of an invocation response streamed by an actor.
+    fn stream_response_chunk(&self, chunk: codec::ResponseChunk) -> anyhow::Result<()> {
+        debug!("awslambda:provider stream_response_chunk");
+
+        match self.router.poller.read().unwrap().as_ref() {
+            Some(poller) => poller.stream_response_chunk(chunk),
+            None => Err(anyhow!("Not polling")),
+        }
+    }
+}
+
+/// The time the Lambda machinery allows for the shutdown of a runtime with an internal extension.
+const SHUTDOWN_DURATION: Duration = Duration::from_millis(500);
+
+/// Handles the `SIGTERM` signal that the Lambda machinery sends when the execution environment shuts down.
+/// Internal extensions can't receive the `SHUTDOWN` event, so this is the runtime's only notice of shutdown.
+fn handle_sigterm<S: StopperW + Send + 'static>(
+    stopper: S,
+    actors: ActorDispatchers,
+    routes: Arc<RwLock<RoutingTable>>,
+) -> anyhow::Result<()> {
+    let signals = Signals::new(iter::once(signal_hook::SIGTERM))?;
+
+    thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            let shutdown = sigterm_shutdown();
+            info!("Shutting down: {}", shutdown.reason);
+            let actors = actors.host_dispatchers(&routes.read().unwrap().actors());
+            if let Err(e) = handle_shutdown(&stopper, &actors, shutdown) {
+                error!("{}", e);
+            }
+            std::process::exit(0);
+        }
+    });
+
+    Ok(())
+}
+
+/// Returns the shutdown signalled by `SIGTERM`.
+fn sigterm_shutdown() -> codec::Shutdown {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    codec::Shutdown {
+        reason: "sigterm".into(),
+        deadline_ms: (now + SHUTDOWN_DURATION).as_millis() as u64,
+    }
+}
+
+/// Handles the shutdown of the AWS Lambda execution environment.
+/// The poller is stopped and each actor is given the chance to flush any buffered data.
+/// Each actor is passed with the host dispatcher of the provider it's bound to.
+/// Returns the first actor's error, if any.
+fn handle_shutdown(
+    stopper: &impl StopperW,
+    actors: &[(String, HostDispatcher)],
+    shutdown: codec::Shutdown,
+) -> anyhow::Result<()> {
+    stopper.stop()?;
+
+    let msg = serialize(shutdown).map_err(|e| anyhow!("{}", e))?;
+    let mut result = Ok(());
+    for (actor, host_dispatcher) in actors {
+        if let Err(e) =
+            host_dispatcher
+                .read()
+                .unwrap()
+                .dispatch(actor, codec::OP_HANDLE_SHUTDOWN, &msg)
+        {
+            let e = anyhow!("Guest {} failed to handle shutdown: {}", actor, e);
+            if result.is_ok() {
+                result = Err(e);
+            } else {
+                error!("{}", e);
+            }
+        }
+    }
+
+    result
 }
 
 /// Represents a waSCC AWS Lambda raw event provider.
@@ -194,26 +468,30 @@ struct LambdaRawEventProvider<S, CF: ClientFactory<C>, C: Client>(
     LambdaProvider<S, CF, C, RawEventDispatcherFactory, RawEventDispatcher>,
 );
 
-impl<S: Clone + Send + StopperR + 'static, CF: ClientFactory<C>, C: Send + Client + 'static>
-    LambdaRawEventProvider<S, CF, C>
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: ClientFactory<C>,
+        C: Send + Sync + Client + 'static,
+    > LambdaRawEventProvider<S, CF, C>
 {
-    /// Creates a new, empty `LambdaRawEventProvider`.
-    pub fn new(stopper: S, client_factory: CF) -> Self {
+    /// Creates a new, empty `LambdaRawEventProvider` that routes invocation events with the specified router.
+    pub fn new(router: Arc<Router<C, S>>, client_factory: CF) -> Self {
         Self(LambdaProvider::new(
-            stopper,
+            router,
             client_factory,
             RawEventDispatcherFactory::new(),
         ))
     }
 }
 
-/// Returns an instance of the default raw event capability provider.
-pub fn default_raw_event_provider() -> impl CapabilityProvider {
-    LambdaRawEventProvider::new(Stopper::new(), RuntimeClientFactory::new())
+/// Returns an instance of the default raw event capability provider
+/// that routes invocation events with the specified router.
+pub fn default_raw_event_provider(router: &EventRouter) -> impl CapabilityProvider {
+    LambdaRawEventProvider::new(Arc::clone(&router.0), RuntimeClientFactory::new())
 }
 
 impl<
-        S: Clone + Send + Sync + StopperR + 'static,
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
         CF: Any + Send + Sync + ClientFactory<C>,
         C: Any + Send + Sync + Client,
     > CapabilityProvider for LambdaRawEventProvider<S, CF, C>
@@ -247,40 +525,44 @@ impl<
     }
 }
 
-/// Represents a waSCC AWS Lambda HTTP request provider.
-/// This capability provider dispatches events from
-/// the AWS Lambda machinery as HTTP requests.
-struct LambdaHttpRequestProvider<S, CF: ClientFactory<C>, C: Client>(
-    LambdaProvider<S, CF, C, HttpRequestDispatcherFactory, HttpRequestDispatcher>,
+/// Represents a waSCC AWS Lambda Amazon SQS provider.
+/// This capability provider dispatches each message in an Amazon SQS event
+/// from the AWS Lambda machinery and reports any failed messages.
+struct LambdaSqsProvider<S, CF: ClientFactory<C>, C: Client>(
+    LambdaProvider<S, CF, C, SqsEventDispatcherFactory, SqsEventDispatcher>,
 );
 
-impl<S: Clone + Send + StopperR + 'static, CF: ClientFactory<C>, C: Send + Client + 'static>
-    LambdaHttpRequestProvider<S, CF, C>
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: ClientFactory<C>,
+        C: Send + Sync + Client + 'static,
+    > LambdaSqsProvider<S, CF, C>
 {
-    /// Creates a new, empty `LambdaHttpRequestProvider`.
-    pub fn new(stopper: S, client_factory: CF) -> Self {
+    /// Creates a new, empty `LambdaSqsProvider` that routes invocation events with the specified router.
+    pub fn new(router: Arc<Router<C, S>>, client_factory: CF) -> Self {
         Self(LambdaProvider::new(
-            stopper,
+            router,
             client_factory,
-            HttpRequestDispatcherFactory::new(),
+            SqsEventDispatcherFactory::new(),
         ))
     }
 }
 
-/// Returns an instance of the default HTTP request capability provider.
-pub fn default_http_request_provider() -> impl CapabilityProvider {
-    LambdaHttpRequestProvider::new(Stopper::new(), RuntimeClientFactory::new())
+/// Returns an instance of the default Amazon SQS capability provider
+/// that routes invocation events with the specified router.
+pub fn default_sqs_provider(router: &EventRouter) -> impl CapabilityProvider {
+    LambdaSqsProvider::new(Arc::clone(&router.0), RuntimeClientFactory::new())
 }
 
 impl<
-        S: Clone + Send + Sync + StopperR + 'static,
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
         CF: Any + Send + Sync + ClientFactory<C>,
         C: Any + Send + Sync + Client,
-    > CapabilityProvider for LambdaHttpRequestProvider<S, CF, C>
+    > CapabilityProvider for LambdaSqsProvider<S, CF, C>
 {
     /// Returns the capability ID in the formated `namespace:id`.
     fn capability_id(&self) -> &'static str {
-        "wascc:http_server"
+        "awslambda:sqs"
     }
 
     /// Called when the host runtime is ready and has configured a dispatcher.
@@ -301,6 +583,260 @@ impl<
         self.0.handle_call(actor, op, msg).map_err(|e| e.into())
     }
 
+    /// Returns the human-readable, friendly name of this capability provider.
+    fn name(&self) -> &'static str {
+        "waSCC AWS Lambda SQS provider"
+    }
+}
+
+/// Represents a waSCC AWS Lambda stream provider.
+/// This capability provider dispatches the records in Amazon Kinesis Data Streams
+/// and Amazon DynamoDB Streams events from the AWS Lambda machinery in order.
+struct LambdaStreamProvider<S, CF: ClientFactory<C>, C: Client>(
+    LambdaProvider<S, CF, C, StreamEventDispatcherFactory, StreamEventDispatcher>,
+);
+
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: ClientFactory<C>,
+        C: Send + Sync + Client + 'static,
+    > LambdaStreamProvider<S, CF, C>
+{
+    /// Creates a new, empty `LambdaStreamProvider` that routes invocation events with the specified router.
+    pub fn new(router: Arc<Router<C, S>>, client_factory: CF) -> Self {
+        Self(LambdaProvider::new(
+            router,
+            client_factory,
+            StreamEventDispatcherFactory::new(),
+        ))
+    }
+}
+
+/// Returns an instance of the default stream capability provider
+/// that routes invocation events with the specified router.
+pub fn default_stream_provider(router: &EventRouter) -> impl CapabilityProvider {
+    LambdaStreamProvider::new(Arc::clone(&router.0), RuntimeClientFactory::new())
+}
+
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: Any + Send + Sync + ClientFactory<C>,
+        C: Any + Send + Sync + Client,
+    > CapabilityProvider for LambdaStreamProvider<S, CF, C>
+{
+    /// Returns the capability ID in the formated `namespace:id`.
+    fn capability_id(&self) -> &'static str {
+        "awslambda:stream"
+    }
+
+    /// Called when the host runtime is ready and has configured a dispatcher.
+    fn configure_dispatch(
+        &self,
+        dispatcher: Box<dyn wascc_codec::capabilities::Dispatcher>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.configure_dispatch(dispatcher).map_err(|e| e.into())
+    }
+
+    /// Called by the host runtime when an actor is requesting a command be executed.
+    fn handle_call(
+        &self,
+        actor: &str,
+        op: &str,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.0.handle_call(actor, op, msg).map_err(|e| e.into())
+    }
+
+    /// Returns the human-readable, friendly name of this capability provider.
+    fn name(&self) -> &'static str {
+        "waSCC AWS Lambda stream provider"
+    }
+}
+
+/// The configuration value that overrides the API Gateway management API endpoint.
+const WEBSOCKET_API_ENDPOINT_KEY: &str = "WASCC_WEBSOCKET_API_ENDPOINT";
+
+/// Represents a waSCC AWS Lambda WebSocket provider.
+/// This capability provider dispatches API Gateway WebSocket API events from the AWS Lambda machinery
+/// and lets actors post messages to client connections.
+struct LambdaWebSocketProvider<S, CF: ClientFactory<C>, C: Client> {
+    provider: LambdaProvider<S, CF, C, WebSocketEventDispatcherFactory, WebSocketEventDispatcher>,
+    management_api_client: RwLock<Option<ManagementApiClient>>,
+}
+
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: ClientFactory<C>,
+        C: Send + Sync + Client + 'static,
+    > LambdaWebSocketProvider<S, CF, C>
+{
+    /// Creates a new, empty `LambdaWebSocketProvider` that routes invocation events with the specified router.
+    pub fn new(router: Arc<Router<C, S>>, client_factory: CF) -> Self {
+        Self {
+            provider: LambdaProvider::new(
+                router,
+                client_factory,
+                WebSocketEventDispatcherFactory::new(),
+            ),
+            management_api_client: RwLock::new(None),
+        }
+    }
+
+    /// Handles a call from an actor or the host.
+    fn handle_call(&self, actor: &str, op: &str, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match op {
+            OP_BIND_ACTOR if actor == "system" => {
+                let config: CapabilityConfiguration =
+                    deserialize(msg).map_err(|e| anyhow!("{}", e))?;
+                let endpoint = config.values.get(WEBSOCKET_API_ENDPOINT_KEY);
+                let mut lock = self.management_api_client.write().unwrap();
+                if lock.is_none() {
+                    *lock = Some(ManagementApiClient::from_env(endpoint.map(String::as_str)));
+                }
+                self.provider.handle_call(actor, op, msg)
+            }
+            codec::OP_POST_TO_CONNECTION => {
+                info!("awslambda:websocket post to connection from `{}`", actor);
+                match self.management_api_client.read().unwrap().as_ref() {
+                    Some(client) => client.post_to_connection(
+                        &Credentials::from_env()?,
+                        deserialize(msg).map_err(|e| anyhow!("{}", e))?,
+                    )?,
+                    None => return Err(anyhow!("No actor bound")),
+                }
+                Ok(vec![])
+            }
+            _ => self.provider.handle_call(actor, op, msg),
+        }
+    }
+}
+
+/// Returns an instance of the default WebSocket capability provider
+/// that routes invocation events with the specified router.
+pub fn default_websocket_provider(router: &EventRouter) -> impl CapabilityProvider {
+    LambdaWebSocketProvider::new(Arc::clone(&router.0), RuntimeClientFactory::new())
+}
+
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: Any + Send + Sync + ClientFactory<C>,
+        C: Any + Send + Sync + Client,
+    > CapabilityProvider for LambdaWebSocketProvider<S, CF, C>
+{
+    /// Returns the capability ID in the formated `namespace:id`.
+    fn capability_id(&self) -> &'static str {
+        "awslambda:websocket"
+    }
+
+    /// Called when the host runtime is ready and has configured a dispatcher.
+    fn configure_dispatch(
+        &self,
+        dispatcher: Box<dyn wascc_codec::capabilities::Dispatcher>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.provider
+            .configure_dispatch(dispatcher)
+            .map_err(|e| e.into())
+    }
+
+    /// Called by the host runtime when an actor is requesting a command be executed.
+    fn handle_call(
+        &self,
+        actor: &str,
+        op: &str,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        LambdaWebSocketProvider::handle_call(self, actor, op, msg).map_err(|e| e.into())
+    }
+
+    /// Returns the human-readable, friendly name of this capability provider.
+    fn name(&self) -> &'static str {
+        "waSCC AWS Lambda WebSocket provider"
+    }
+}
+
+/// Represents a waSCC AWS Lambda HTTP request provider.
+/// This capability provider dispatches events from
+/// the AWS Lambda machinery as HTTP requests.
+struct LambdaHttpRequestProvider<S, CF: ClientFactory<C>, C: Client> {
+    provider: LambdaProvider<S, CF, C, HttpRequestDispatcherFactory, HttpRequestDispatcher>,
+    cors: ActorCorsSettings,
+}
+
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: ClientFactory<C>,
+        C: Send + Sync + Client + 'static,
+    > LambdaHttpRequestProvider<S, CF, C>
+{
+    /// Creates a new, empty `LambdaHttpRequestProvider` that routes invocation events with the specified router.
+    pub fn new(router: Arc<Router<C, S>>, client_factory: CF) -> Self {
+        let cors = ActorCorsSettings::default();
+        Self {
+            provider: LambdaProvider::new(
+                router,
+                client_factory,
+                HttpRequestDispatcherFactory::new(Arc::clone(&cors)),
+            ),
+            cors,
+        }
+    }
+
+    /// Handles a call from an actor or the host.
+    /// Each bound actor's CORS settings are taken from its capability configuration values.
+    fn handle_call(&self, actor: &str, op: &str, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if op == OP_BIND_ACTOR && actor == "system" {
+            let config: CapabilityConfiguration = deserialize(msg).map_err(|e| anyhow!("{}", e))?;
+            match CorsSettings::from_config(&config.values) {
+                Ok(Some(cors)) => {
+                    info!("CORS enabled for actor {}", config.module);
+                    self.cors.write().unwrap().insert(config.module, cors);
+                }
+                Ok(None) => {}
+                Err(e) => error!("CORS disabled for actor {}: {}", config.module, e),
+            }
+        }
+
+        self.provider.handle_call(actor, op, msg)
+    }
+}
+
+/// Returns an instance of the default HTTP request capability provider
+/// that routes invocation events with the specified router.
+pub fn default_http_request_provider(router: &EventRouter) -> impl CapabilityProvider {
+    LambdaHttpRequestProvider::new(Arc::clone(&router.0), RuntimeClientFactory::new())
+}
+
+impl<
+        S: Clone + Send + Sync + StopperR + StopperW + 'static,
+        CF: Any + Send + Sync + ClientFactory<C>,
+        C: Any + Send + Sync + Client,
+    > CapabilityProvider for LambdaHttpRequestProvider<S, CF, C>
+{
+    /// Returns the capability ID in the formated `namespace:id`.
+    fn capability_id(&self) -> &'static str {
+        "wascc:http_server"
+    }
+
+    /// Called when the host runtime is ready and has configured a dispatcher.
+    fn configure_dispatch(
+        &self,
+        dispatcher: Box<dyn wascc_codec::capabilities::Dispatcher>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.provider
+            .configure_dispatch(dispatcher)
+            .map_err(|e| e.into())
+    }
+
+    /// Called by the host runtime when an actor is requesting a command be executed.
+    fn handle_call(
+        &self,
+        actor: &str,
+        op: &str,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        LambdaHttpRequestProvider::handle_call(self, actor, op, msg).map_err(|e| e.into())
+    }
+
     /// Returns the human-readable, friendly name of this capability provider.
     fn name(&self) -> &'static str {
         "waSCC AWS Lambda HTTP request provider"
@@ -310,7 +846,13 @@ impl<
 /// Creates `Client` instances.
 trait ClientFactory<C> {
     /// Creates a new `Client`.
-    fn new_client(&self, endpoint: &str) -> C;
+    fn new_client(&self, endpoint: &str, settings: &ClientSettings) -> anyhow::Result<C>;
+
+    /// Creates a new `ExtensionClient`.
+    fn new_extension_client(
+        &self,
+        endpoint: &str,
+    ) -> anyhow::Result<Box<dyn ExtensionClient + Send>>;
 }
 
 /// Creates `RuntimeClient` instances.
@@ -325,31 +867,59 @@ impl RuntimeClientFactory {
 
 impl ClientFactory<RuntimeClient> for RuntimeClientFactory {
     /// Creates a new `RuntimeClient`.
-    fn new_client(&self, endpoint: &str) -> RuntimeClient {
-        RuntimeClient::new(endpoint)
+    fn new_client(
+        &self,
+        endpoint: &str,
+        settings: &ClientSettings,
+    ) -> anyhow::Result<RuntimeClient> {
+        RuntimeClient::with_settings(endpoint, settings.clone())
+    }
+
+    /// Creates a new `RuntimeExtensionClient`.
+    fn new_extension_client(
+        &self,
+        endpoint: &str,
+    ) -> anyhow::Result<Box<dyn ExtensionClient + Send>> {
+        Ok(Box::new(RuntimeExtensionClient::new(endpoint)?))
     }
 }
 
 /// Creates `Dispatcher` instances.
 trait DispatcherFactory<D> {
     /// Creates a new `Dispatcher`.
-    fn new_dispatcher(&self, host_dispatcher: HostDispatcher) -> D;
+    /// The actor's capability configuration values are passed.
+    fn new_dispatcher(
+        &self,
+        host_dispatcher: HostDispatcher,
+        values: &HashMap<String, String>,
+    ) -> D;
 }
 
 /// Creates `HttpRequestDispatcher` instances.
-struct HttpRequestDispatcherFactory;
+struct HttpRequestDispatcherFactory {
+    cors: ActorCorsSettings,
+}
 
 impl HttpRequestDispatcherFactory {
     /// Returns new `HttpRequestDispatcherFactory` instances.
-    fn new() -> Self {
-        Self
+    /// Dispatchers share the specified CORS settings of each actor.
+    fn new(cors: ActorCorsSettings) -> Self {
+        Self { cors }
     }
 }
 
 impl DispatcherFactory<HttpRequestDispatcher> for HttpRequestDispatcherFactory {
     /// Creates a new `HttpRequestDispatcher`.
-    fn new_dispatcher(&self, host_dispatcher: HostDispatcher) -> HttpRequestDispatcher {
-        HttpRequestDispatcher::new(host_dispatcher)
+    fn new_dispatcher(
+        &self,
+        host_dispatcher: HostDispatcher,
+        values: &HashMap<String, String>,
+    ) -> HttpRequestDispatcher {
+        HttpRequestDispatcher::new(
+            host_dispatcher,
+            HttpSettings::from_config(values),
+            Arc::clone(&self.cors),
+        )
     }
 }
 
@@ -365,30 +935,124 @@ impl RawEventDispatcherFactory {
 
 impl DispatcherFactory<RawEventDispatcher> for RawEventDispatcherFactory {
     /// Creates a new `RawEventDispatcher`.
-    fn new_dispatcher(&self, host_dispatcher: HostDispatcher) -> RawEventDispatcher {
+    fn new_dispatcher(
+        &self,
+        host_dispatcher: HostDispatcher,
+        _values: &HashMap<String, String>,
+    ) -> RawEventDispatcher {
         RawEventDispatcher::new(host_dispatcher)
     }
 }
 
+/// Creates `SqsEventDispatcher` instances.
+struct SqsEventDispatcherFactory;
+
+impl SqsEventDispatcherFactory {
+    /// Returns new `SqsEventDispatcherFactory` instances.
+    fn new() -> Self {
+        Self
+    }
+}
+
+impl DispatcherFactory<SqsEventDispatcher> for SqsEventDispatcherFactory {
+    /// Creates a new `SqsEventDispatcher`.
+    fn new_dispatcher(
+        &self,
+        host_dispatcher: HostDispatcher,
+        _values: &HashMap<String, String>,
+    ) -> SqsEventDispatcher {
+        SqsEventDispatcher::new(host_dispatcher)
+    }
+}
+
+/// Creates `StreamEventDispatcher` instances.
+struct StreamEventDispatcherFactory;
+
+impl StreamEventDispatcherFactory {
+    /// Returns new `StreamEventDispatcherFactory` instances.
+    fn new() -> Self {
+        Self
+    }
+}
+
+impl DispatcherFactory<StreamEventDispatcher> for StreamEventDispatcherFactory {
+    /// Creates a new `StreamEventDispatcher`.
+    fn new_dispatcher(
+        &self,
+        host_dispatcher: HostDispatcher,
+        _values: &HashMap<String, String>,
+    ) -> StreamEventDispatcher {
+        StreamEventDispatcher::new(host_dispatcher)
+    }
+}
+
+/// Creates `WebSocketEventDispatcher` instances.
+struct WebSocketEventDispatcherFactory;
+
+impl WebSocketEventDispatcherFactory {
+    /// Returns new `WebSocketEventDispatcherFactory` instances.
+    fn new() -> Self {
+        Self
+    }
+}
+
+impl DispatcherFactory<WebSocketEventDispatcher> for WebSocketEventDispatcherFactory {
+    /// Creates a new `WebSocketEventDispatcher`.
+    fn new_dispatcher(
+        &self,
+        host_dispatcher: HostDispatcher,
+        _values: &HashMap<String, String>,
+    ) -> WebSocketEventDispatcher {
+        WebSocketEventDispatcher::new(host_dispatcher)
+    }
+}
+
+/// Represents the response stream of the invocation in flight.
+enum StreamState {
+    /// No chunk has been sent yet.
+    Idle,
+    /// The stream was started by the first chunk.
+    Open(Box<dyn ResponseStream>),
+    /// The invocation has completed.
+    Closed,
+}
+
+/// Represents the invocation in flight.
+struct InFlight {
+    request_id: String,
+    stream: Arc<Mutex<StreamState>>,
+}
+
 /// Polls the Lambda event machinery using the specified client.
 struct Poller<C, S> {
     client: C,
-    module_id: String,
+    in_flight: Mutex<Option<InFlight>>,
+    routes: Arc<RwLock<RoutingTable>>,
+    settings: FunctionSettings,
     stopper: S,
 }
 
 impl<C: Client, S: StopperR> Poller<C, S> {
     /// Creates a new `Poller`.
-    fn new(module_id: &str, client: C, stopper: S) -> Self {
+    fn new(
+        routes: Arc<RwLock<RoutingTable>>,
+        settings: FunctionSettings,
+        client: C,
+        stopper: S,
+    ) -> Self {
         Self {
             client,
-            module_id: module_id.into(),
+            in_flight: Mutex::new(None),
+            routes,
+            settings,
             stopper,
         }
     }
 
     /// Runs the poller until shutdown.
-    fn run(&self, dispatcher: impl InvocationEventDispatcher) {
+    /// Polling continues after a dispatch is abandoned at the invocation deadline.
+    /// Returns an error if the Lambda event machinery can no longer be polled.
+    fn run(&self, dispatcher: impl InvocationEventDispatcher) -> anyhow::Result<()> {
         loop {
             match self.stopper.stop() {
                 Err(e) => {
@@ -400,12 +1064,10 @@ impl<C: Client, S: StopperR> Poller<C, S> {
             }
 
             // Get next event.
+            // The client has already retried any transient failures.
             debug!("Poller get next event");
             let event = match self.client.next_invocation_event() {
-                Err(e) => {
-                    error!("{}", e);
-                    continue;
-                }
+                Err(e) => return Err(e),
                 Ok(evt) => match evt {
                     None => {
                         warn!("No event");
@@ -427,33 +1089,139 @@ impl<C: Client, S: StopperR> Poller<C, S> {
                 env::set_var("_X_AMZN_TRACE_ID", trace_id);
             }
 
-            match dispatcher.dispatch_invocation_event(&self.module_id, event.body()) {
-                Ok(body) => self.send_invocation_response(body, request_id),
-                Err(e) => {
+            *self.in_flight.lock().unwrap() = Some(InFlight {
+                request_id: request_id.into(),
+                stream: Arc::new(Mutex::new(StreamState::Idle)),
+            });
+
+            let event_body = ClassifiedEvent::new(event.body().to_vec());
+            let source = event_body.source();
+            info!("Invocation event from {}", source);
+            let context = event.context(&self.settings, source);
+            let actor = self.routes.read().unwrap().route(source).map(String::from);
+            let result = actor.and_then(|actor| {
+                dispatcher.dispatch_classified_event(&actor, &context, event_body)
+            });
+            let stream = self.complete_in_flight();
+            match (stream, result) {
+                (Some(stream), result) => {
+                    self.finish_response_stream(stream, result, request_id)?
+                }
+                (None, Ok(body)) => self.send_invocation_response(body, request_id)?,
+                (None, Err(e)) => {
                     error!("{}", e);
-                    self.send_invocation_error(e, request_id)
+                    self.send_invocation_error(e, request_id)?
                 }
             }
         }
+
+        Ok(())
+    }
+
+    /// Sends a chunk of a streamed invocation response.
+    /// The stream is started by the first chunk.
+    /// Only the invocation in flight can be streamed, and only the stream is locked while the chunk is sent.
+    fn stream_response_chunk(&self, chunk: codec::ResponseChunk) -> anyhow::Result<()> {
+        let stream = match &*self.in_flight.lock().unwrap() {
+            Some(in_flight) if in_flight.request_id == chunk.request_id => {
+                Arc::clone(&in_flight.stream)
+            }
+            _ => return Err(anyhow!("Invocation {} is not in flight", chunk.request_id)),
+        };
+
+        let mut stream = stream.lock().unwrap();
+        if let StreamState::Idle = *stream {
+            *stream = StreamState::Open(
+                self.client
+                    .stream_invocation_response(&chunk.request_id, chunk.content_type.as_deref())?,
+            );
+        }
+        match &mut *stream {
+            StreamState::Open(stream) => stream.send_chunk(&chunk.body),
+            _ => Err(anyhow!("Invocation {} has completed", chunk.request_id)),
+        }
+    }
+
+    /// Completes the invocation in flight so that no more chunks can be streamed.
+    /// Returns its response stream, if one was started.
+    fn complete_in_flight(&self) -> Option<Box<dyn ResponseStream>> {
+        let in_flight = self.in_flight.lock().unwrap().take()?;
+        let mut stream = in_flight.stream.lock().unwrap();
+        match mem::replace(&mut *stream, StreamState::Closed) {
+            StreamState::Open(stream) => Some(stream),
+            _ => None,
+        }
+    }
+
+    /// Completes a streamed invocation response.
+    /// Any response body returned by the actor is sent as the last chunk.
+    /// Returns an error if the runtime must exit.
+    fn finish_response_stream(
+        &self,
+        mut stream: Box<dyn ResponseStream>,
+        result: anyhow::Result<Vec<u8>>,
+        request_id: &str,
+    ) -> anyhow::Result<()> {
+        debug!("Poller finish response stream");
+        let result = match result {
+            Ok(body) => stream.send_chunk(&body).and_then(|_| stream.finish()),
+            Err(e) => {
+                error!("{}", e);
+                stream.finish_with_error(InvocationError::new(e, request_id))
+            }
+        };
+
+        result.or_else(|e| Self::handle_send_error(e, "invocation response stream"))
     }
 
     /// Sends an invocation error.
-    fn send_invocation_error(&self, e: anyhow::Error, request_id: &str) {
+    /// Returns an error if the runtime must exit.
+    fn send_invocation_error(&self, e: anyhow::Error, request_id: &str) -> anyhow::Result<()> {
         let err = InvocationError::new(e, request_id);
+        let compact = err.compact();
         debug!("Poller send error");
         match self.client.send_invocation_error(err) {
-            Ok(_) => {}
-            Err(e) => error!("Unable to send invocation error: {}", e),
+            Ok(_) => Ok(()),
+            Err(e) => match e.downcast::<RuntimeApiError>() {
+                Ok(RuntimeApiError::PayloadTooLarge { .. }) => {
+                    warn!("Invocation error too large, sending compact error");
+                    self.client
+                        .send_invocation_error(compact)
+                        .or_else(|e| Self::handle_send_error(e, "invocation error"))
+                }
+                Ok(e) => Self::handle_send_error(e.into(), "invocation error"),
+                Err(e) => Self::handle_send_error(e, "invocation error"),
+            },
         }
     }
 
     /// Sends an invocation response.
-    fn send_invocation_response(&self, body: Vec<u8>, request_id: &str) {
+    /// Returns an error if the runtime must exit.
+    fn send_invocation_response(&self, body: Vec<u8>, request_id: &str) -> anyhow::Result<()> {
         let resp = InvocationResponse::new(body, request_id);
         debug!("Poller send response");
         match self.client.send_invocation_response(resp) {
-            Ok(_) => {}
-            Err(e) => error!("Unable to send invocation response: {}", e),
+            Ok(_) => Ok(()),
+            Err(e) => match e.downcast::<RuntimeApiError>() {
+                Ok(e @ RuntimeApiError::PayloadTooLarge { .. }) => {
+                    warn!("Invocation response too large, sending error");
+                    self.send_invocation_error(e.into(), request_id)
+                }
+                Ok(e) => Self::handle_send_error(e.into(), "invocation response"),
+                Err(e) => Self::handle_send_error(e, "invocation response"),
+            },
+        }
+    }
+
+    /// Handles an error sending to the AWS Lambda runtime.
+    /// Returns the error if the runtime must exit.
+    fn handle_send_error(e: anyhow::Error, what: &str) -> anyhow::Result<()> {
+        match e.downcast_ref::<RuntimeApiError>() {
+            Some(api_error) if api_error.is_fatal() => Err(e),
+            _ => {
+                error!("Unable to send {}: {}", what, e);
+                Ok(())
+            }
         }
     }
 }
@@ -461,11 +1229,17 @@ impl<C: Client, S: StopperR> Poller<C, S> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::classify::EventSource;
+    use crate::extension::ExtensionEvent;
     use crate::lambda::{
         InitializationError, InvocationError, InvocationEvent, InvocationEventBuilder,
         InvocationResponse,
     };
+    use emulator::{Completion, Emulator, EmulatorSettings, Outcome};
     use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
     use wascc_codec::serialize;
 
     use crate::tests_common::*;
@@ -477,6 +1251,8 @@ mod tests {
         None,
         /// An invocation event.
         Event(InvocationEvent),
+        /// A sequence of invocation events.
+        Events(Vec<InvocationEvent>),
         /// An error.
         Error,
     }
@@ -499,18 +1275,87 @@ mod tests {
 
     impl ClientFactory<MockClient> for MockClientFactory {
         /// Creates a new `MockClient`.
-        fn new_client(&self, _endpoint: &str) -> MockClient {
-            MockClient::new(self.event_kind.clone(), self.stopper.clone())
+        fn new_client(
+            &self,
+            _endpoint: &str,
+            _settings: &ClientSettings,
+        ) -> anyhow::Result<MockClient> {
+            Ok(MockClient::new(
+                self.event_kind.clone(),
+                self.stopper.clone(),
+            ))
+        }
+
+        /// Creates a new `MockExtensionClient`.
+        fn new_extension_client(
+            &self,
+            _endpoint: &str,
+        ) -> anyhow::Result<Box<dyn ExtensionClient + Send>> {
+            Ok(Box::new(MockExtensionClient))
+        }
+    }
+
+    /// Represents a mock Extensions API client that can't register.
+    struct MockExtensionClient;
+
+    impl ExtensionClient for MockExtensionClient {
+        /// Registers an extension.
+        fn register(&self) -> anyhow::Result<String> {
+            Err(anyhow!(ERROR_MESSAGE))
+        }
+
+        /// Returns the next AWS Lambda extension event.
+        fn next_event(&self, _extension_id: &str) -> anyhow::Result<ExtensionEvent> {
+            Err(anyhow!(ERROR_MESSAGE))
         }
     }
 
-    /// Represents a mock Lambda runtime client that returns a single event.
+    /// Represents a mock Lambda runtime client that returns a single event or a sequence of events.
     struct MockClient {
         event_kind: EventKind,
         initialization_error: RwLock<Option<InitializationError>>,
         invocation_error: RwLock<Option<InvocationError>>,
         invocation_response: RwLock<Option<InvocationResponse>>,
+        next_calls: AtomicUsize,
+        send_errors: RwLock<Vec<RuntimeApiError>>,
         stopper: Stopper,
+        stream: Arc<RwLock<MockStream>>,
+    }
+
+    /// Records a mock streamed invocation response.
+    #[derive(Default)]
+    struct MockStream {
+        body: Vec<u8>,
+        error_type: Option<String>,
+        finished: bool,
+    }
+
+    /// Represents a mock invocation response stream.
+    struct MockResponseStream(Arc<RwLock<MockStream>>);
+
+    impl ResponseStream for MockResponseStream {
+        /// Sends a chunk of the response body.
+        fn send_chunk(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+            self.0.write().unwrap().body.extend_from_slice(chunk);
+
+            Ok(())
+        }
+
+        /// Completes the response.
+        fn finish(self: Box<Self>) -> anyhow::Result<()> {
+            self.0.write().unwrap().finished = true;
+
+            Ok(())
+        }
+
+        /// Completes the response, reporting an error in the trailing headers.
+        fn finish_with_error(self: Box<Self>, error: InvocationError) -> anyhow::Result<()> {
+            let mut lock = self.0.write().unwrap();
+            lock.error_type = Some(error.error_type().into());
+            lock.finished = true;
+
+            Ok(())
+        }
     }
 
     impl MockClient {
@@ -521,7 +1366,19 @@ mod tests {
                 initialization_error: RwLock::new(None),
                 invocation_error: RwLock::new(None),
                 invocation_response: RwLock::new(None),
+                next_calls: AtomicUsize::new(0),
+                send_errors: RwLock::new(vec![]),
                 stopper,
+                stream: Arc::new(RwLock::new(MockStream::default())),
+            }
+        }
+
+        /// Returns the next error to fail a send with, if any.
+        fn next_send_error(&self) -> anyhow::Result<()> {
+            let mut lock = self.send_errors.write().unwrap();
+            match lock.pop() {
+                Some(e) => Err(e.into()),
+                None => Ok(()),
             }
         }
     }
@@ -529,11 +1386,19 @@ mod tests {
     impl Client for MockClient {
         /// Returns the next AWS Lambda invocation event.
         fn next_invocation_event(&self) -> anyhow::Result<Option<InvocationEvent>> {
-            // Shutdown after one event.
+            let calls = self.next_calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+            // Shutdown after the last event.
+            if let EventKind::Events(events) = &self.event_kind {
+                if calls >= events.len() {
+                    <Stopper as StopperW>::stop(&self.stopper)?;
+                }
+                return Ok(events.get(calls - 1).cloned());
+            }
             <Stopper as StopperW>::stop(&self.stopper)?;
 
             match &self.event_kind {
-                EventKind::None => Ok(None),
+                EventKind::None | EventKind::Events(_) => Ok(None),
                 EventKind::Event(event) => Ok(Some(event.clone())),
                 EventKind::Error => Err(anyhow!(ERROR_MESSAGE)),
             }
@@ -545,7 +1410,7 @@ mod tests {
             let mut lock = self.invocation_error.write().unwrap();
             *lock = Some(error);
 
-            Ok(())
+            self.next_send_error()
         }
 
         /// Sends an invocation error to the AWS Lambda runtime.
@@ -554,7 +1419,16 @@ mod tests {
             let mut lock = self.invocation_response.write().unwrap();
             *lock = Some(response);
 
-            Ok(())
+            self.next_send_error()
+        }
+
+        /// Starts streaming an invocation response to the AWS Lambda runtime.
+        fn stream_invocation_response(
+            &self,
+            _request_id: &str,
+            _content_type: Option<&str>,
+        ) -> anyhow::Result<Box<dyn ResponseStream>> {
+            Ok(Box::new(MockResponseStream(Arc::clone(&self.stream))))
         }
 
         /// Sends an initialization error to the AWS Lambda runtime.
@@ -585,6 +1459,7 @@ mod tests {
     fn dispatcher() -> impl InvocationEventDispatcher {
         let response = codec::Response {
             body: RESPONSE_BODY.to_vec(),
+            error: None,
         };
         let host_dispatcher = mock_host_dispatcher(response);
         RawEventDispatcher::new(host_dispatcher)
@@ -596,17 +1471,82 @@ mod tests {
         RawEventDispatcher::new(host_dispatcher)
     }
 
+    /// Represents an event dispatcher whose actor streams its response.
+    struct StreamingDispatcher<C> {
+        poller: Arc<Poller<C, Stopper>>,
+        error: bool,
+    }
+
+    impl<C: Client> InvocationEventDispatcher for StreamingDispatcher<C> {
+        /// Streams a chunk of the response and returns the rest of it.
+        fn dispatch_invocation_event(
+            &self,
+            _actor: &str,
+            context: &codec::Context,
+            _event: &[u8],
+        ) -> anyhow::Result<Vec<u8>> {
+            let chunk = codec::ResponseChunk::new(&context.request_id, EVENT_BODY.to_vec());
+            self.poller.stream_response_chunk(chunk)?;
+
+            if self.error {
+                return Err(anyhow!(ERROR_MESSAGE));
+            }
+            Ok(RESPONSE_BODY.to_vec())
+        }
+    }
+
+    /// Returns a routing table that routes any event to the test actor.
+    fn routes() -> Arc<RwLock<RoutingTable>> {
+        let mut routes = RoutingTable::default();
+        routes.add_actor(MODULE_ID, &HashMap::new());
+        Arc::new(RwLock::new(routes))
+    }
+
     /// Returns a mock poller.
     fn mock_poller(event_kind: EventKind) -> Poller<MockClient, Stopper> {
         let stopper = Stopper::new();
 
         Poller::new(
-            MODULE_ID,
+            routes(),
+            FunctionSettings::default(),
             MockClient::new(event_kind, stopper.clone()),
             stopper,
         )
     }
 
+    /// Starts a runtime API emulator with the specified events.
+    /// Returns the emulator and a poller whose runtime client points at it.
+    fn emulated_poller(
+        events: Vec<serde_json::Value>,
+    ) -> (Emulator, Poller<RuntimeClient, Stopper>) {
+        let emulator = Emulator::new(events, EmulatorSettings::default());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        emulator.start(listener);
+
+        let poller = Poller::new(
+            routes(),
+            FunctionSettings::default(),
+            RuntimeClient::new(&endpoint).unwrap(),
+            Stopper::new(),
+        );
+
+        (emulator, poller)
+    }
+
+    /// Asserts that the runtime was told to exit once the emulator had no more events.
+    fn assert_drained(result: anyhow::Result<()>, emulator: &Emulator) {
+        assert!(result.is_err());
+        match result.unwrap_err().downcast_ref::<RuntimeApiError>() {
+            Some(RuntimeApiError::ContainerError { .. }) => {}
+            _ => panic!("unexpected error"),
+        }
+        assert_eq!(
+            Some(Completion::Drained),
+            emulator.wait_timeout(Duration::from_secs(5))
+        );
+    }
+
     /// Returns a `MockClientFactory`.
     fn mock_client_factory(event_kind: EventKind) -> impl ClientFactory<MockClient> {
         MockClientFactory::new(event_kind, Stopper::new())
@@ -636,7 +1576,8 @@ mod tests {
     #[test]
     fn poller_event_kind_none() {
         let poller = mock_poller(EventKind::None);
-        poller.run(dispatcher());
+        let result = poller.run(dispatcher());
+        assert!(result.is_ok());
 
         assert!(poller.client.initialization_error.read().unwrap().is_none());
         assert!(poller.client.invocation_response.read().unwrap().is_none());
@@ -647,7 +1588,8 @@ mod tests {
     #[test]
     fn poller_event_kind_event_no_request_id() {
         let poller = mock_poller(EventKind::Event(InvocationEvent::no_request_id()));
-        poller.run(dispatcher());
+        let result = poller.run(dispatcher());
+        assert!(result.is_ok());
 
         assert!(poller.client.initialization_error.read().unwrap().is_none());
         assert!(poller.client.invocation_response.read().unwrap().is_none());
@@ -658,7 +1600,8 @@ mod tests {
     #[test]
     fn poller_event_kind_event_with_request_id() {
         let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
-        poller.run(dispatcher());
+        let result = poller.run(dispatcher());
+        assert!(result.is_ok());
 
         assert!(poller.client.initialization_error.read().unwrap().is_none());
         assert!(poller.client.invocation_response.read().unwrap().is_some());
@@ -691,7 +1634,8 @@ mod tests {
     #[test]
     fn poller_event_kind_event_with_request_id_error_dispatcher() {
         let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
-        poller.run(error_dispatcher());
+        let result = poller.run(error_dispatcher());
+        assert!(result.is_ok());
 
         assert!(poller.client.initialization_error.read().unwrap().is_none());
         assert!(poller.client.invocation_response.read().unwrap().is_none());
@@ -709,20 +1653,408 @@ mod tests {
         );
     }
 
-    /// Tests that receiving an error sends no response or error.
+    /// Tests that a response that is too large is reported as an error.
+    #[test]
+    fn poller_event_kind_event_response_too_large() {
+        let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
+        *poller.client.send_errors.write().unwrap() = vec![RuntimeApiError::PayloadTooLarge {
+            message: ERROR_MESSAGE.into(),
+        }];
+        let result = poller.run(dispatcher());
+        assert!(result.is_ok());
+
+        assert!(poller.client.invocation_response.read().unwrap().is_some());
+        assert!(poller.client.invocation_error.read().unwrap().is_some());
+        assert_eq!(
+            "Function.ResponseSizeTooLarge",
+            poller
+                .client
+                .invocation_error
+                .read()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .error_type()
+        );
+    }
+
+    /// Tests that an error that is too large is sent again in compact form.
+    #[test]
+    fn poller_event_kind_event_error_too_large() {
+        let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
+        *poller.client.send_errors.write().unwrap() = vec![RuntimeApiError::PayloadTooLarge {
+            message: ERROR_MESSAGE.into(),
+        }];
+        let result = poller.run(error_dispatcher());
+        assert!(result.is_ok());
+
+        assert!(poller.client.invocation_response.read().unwrap().is_none());
+        assert!(poller.client.invocation_error.read().unwrap().is_some());
+        assert!(poller
+            .client
+            .invocation_error
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .stack_trace()
+            .is_empty());
+    }
+
+    /// Tests that a container error stops the poller.
+    #[test]
+    fn poller_event_kind_event_container_error() {
+        let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
+        *poller.client.send_errors.write().unwrap() = vec![RuntimeApiError::ContainerError {
+            message: ERROR_MESSAGE.into(),
+        }];
+        let result = poller.run(dispatcher());
+        assert!(result.is_err());
+    }
+
+    /// Tests that a streamed response is completed with the actor's response.
+    #[test]
+    fn poller_event_kind_event_streamed_response() {
+        let poller = Arc::new(mock_poller(EventKind::Event(
+            InvocationEvent::with_request_id(),
+        )));
+        let dispatcher = StreamingDispatcher {
+            poller: Arc::clone(&poller),
+            error: false,
+        };
+        let result = poller.run(dispatcher);
+        assert!(result.is_ok());
+
+        assert!(poller.client.invocation_response.read().unwrap().is_none());
+        assert!(poller.client.invocation_error.read().unwrap().is_none());
+        let stream = poller.client.stream.read().unwrap();
+        assert!(stream.finished);
+        assert!(stream.error_type.is_none());
+        assert_eq!([EVENT_BODY, RESPONSE_BODY].concat(), stream.body);
+    }
+
+    /// Tests that a streamed response is completed with the actor's error.
+    #[test]
+    fn poller_event_kind_event_streamed_response_error() {
+        let poller = Arc::new(mock_poller(EventKind::Event(
+            InvocationEvent::with_request_id(),
+        )));
+        let dispatcher = StreamingDispatcher {
+            poller: Arc::clone(&poller),
+            error: true,
+        };
+        let result = poller.run(dispatcher);
+        assert!(result.is_ok());
+
+        assert!(poller.client.invocation_response.read().unwrap().is_none());
+        assert!(poller.client.invocation_error.read().unwrap().is_none());
+        let stream = poller.client.stream.read().unwrap();
+        assert!(stream.finished);
+        assert_eq!(Some("InvocationError".into()), stream.error_type);
+        assert_eq!(EVENT_BODY, stream.body.as_slice());
+    }
+
+    /// Tests that the runtime client and poller handle events from the runtime API emulator.
+    #[test]
+    fn poller_emulator_responses() {
+        let (emulator, poller) = emulated_poller(vec![
+            serde_json::json!({"input": "Hello"}),
+            serde_json::json!({"input": "world"}),
+        ]);
+        assert_drained(poller.run(dispatcher()), &emulator);
+
+        let results = emulator.results();
+        assert_eq!(2, results.len());
+        for result in results {
+            assert_eq!(Outcome::Response(RESPONSE_BODY.to_vec()), result.outcome);
+        }
+    }
+
+    /// Tests that an actor's error is reported to the runtime API emulator.
+    #[test]
+    fn poller_emulator_error() {
+        let (emulator, poller) = emulated_poller(vec![serde_json::json!({"input": "Hello"})]);
+        assert_drained(poller.run(error_dispatcher()), &emulator);
+
+        let results = emulator.results();
+        assert_eq!(1, results.len());
+        match &results[0].outcome {
+            Outcome::Error { error_type, .. } => assert_eq!("Actor.NotDispatched", error_type),
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+    }
+
+    /// Tests that a streamed response is sent to the runtime API emulator.
+    #[test]
+    fn poller_emulator_streamed_response() {
+        let (emulator, poller) = emulated_poller(vec![serde_json::json!({"input": "Hello"})]);
+        let poller = Arc::new(poller);
+        let dispatcher = StreamingDispatcher {
+            poller: Arc::clone(&poller),
+            error: false,
+        };
+        assert_drained(poller.run(dispatcher), &emulator);
+
+        let results = emulator.results();
+        assert_eq!(1, results.len());
+        assert_eq!(
+            Outcome::Response([EVENT_BODY, RESPONSE_BODY].concat()),
+            results[0].outcome
+        );
+    }
+
+    /// Tests that a chunk can't start a stream for an invocation that isn't in flight.
+    #[test]
+    fn poller_stream_response_chunk_not_in_flight() {
+        let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
+        let chunk = codec::ResponseChunk::new(REQUEST_ID, EVENT_BODY.to_vec());
+        let result = poller.stream_response_chunk(chunk);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not in flight"));
+        assert!(poller.client.stream.read().unwrap().body.is_empty());
+    }
+
+    /// Tests that a chunk sent after its invocation completed is rejected.
+    #[test]
+    fn poller_stream_response_chunk_completed() {
+        let poller = Arc::new(mock_poller(EventKind::Event(
+            InvocationEvent::with_request_id(),
+        )));
+        let dispatcher = StreamingDispatcher {
+            poller: Arc::clone(&poller),
+            error: false,
+        };
+        assert!(poller.run(dispatcher).is_ok());
+
+        let chunk = codec::ResponseChunk::new(REQUEST_ID, EVENT_BODY.to_vec());
+        assert!(poller.stream_response_chunk(chunk).is_err());
+        assert_eq!(
+            [EVENT_BODY, RESPONSE_BODY].concat(),
+            poller.client.stream.read().unwrap().body
+        );
+    }
+
+    /// Tests that a busy stream doesn't block other chunks from being checked.
+    #[test]
+    fn poller_stream_response_chunk_stream_busy() {
+        let poller = mock_poller(EventKind::Event(InvocationEvent::with_request_id()));
+        let stream = Arc::new(Mutex::new(StreamState::Idle));
+        *poller.in_flight.lock().unwrap() = Some(InFlight {
+            request_id: REQUEST_ID.into(),
+            stream: Arc::clone(&stream),
+        });
+
+        let _busy = stream.lock().unwrap();
+        let chunk = codec::ResponseChunk::new("other", EVENT_BODY.to_vec());
+        assert!(poller.stream_response_chunk(chunk).is_err());
+    }
+
+    /// Tests that the poller reports a dispatch abandoned at the deadline and keeps polling,
+    /// reporting the next invocation of the actor as busy while the abandoned dispatch runs.
+    #[test]
+    fn poller_event_kind_events_deadline_exceeded() {
+        const NEXT_REQUEST_ID: &str = "NEXT_REQUEST_ID";
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let deadline_ms = (now + Duration::from_millis(300)).as_millis() as u64;
+        let event = InvocationEventBuilder::new(EVENT_BODY.to_vec())
+            .request_id(REQUEST_ID)
+            .deadline_ms(deadline_ms)
+            .build();
+        let next_event = InvocationEventBuilder::new(EVENT_BODY.to_vec())
+            .request_id(NEXT_REQUEST_ID)
+            .build();
+        let poller = mock_poller(EventKind::Events(vec![event, next_event]));
+        let host_dispatcher = slow_host_dispatcher(Duration::from_millis(500));
+        let dispatcher = DeadlineDispatcher::new(RawEventDispatcher::new(host_dispatcher));
+
+        let result = poller.run(dispatcher);
+        assert!(result.is_ok());
+        assert_eq!(2, poller.client.next_calls.load(Ordering::SeqCst));
+
+        let lock = poller.client.invocation_error.read().unwrap();
+        let error = lock.as_ref().unwrap();
+        assert_eq!("Actor.Busy", error.error_type());
+        assert_eq!(NEXT_REQUEST_ID, error.request_id());
+        assert!(poller.client.invocation_response.read().unwrap().is_none());
+    }
+
+    /// Tests that receiving an error stops the poller and sends no response or error.
     #[test]
     fn poller_event_kind_error() {
         let poller = mock_poller(EventKind::Error);
-        poller.run(dispatcher());
+        let result = poller.run(dispatcher());
+        assert!(result.is_err());
 
         assert!(poller.client.initialization_error.read().unwrap().is_none());
         assert!(poller.client.invocation_response.read().unwrap().is_none());
         assert!(poller.client.invocation_error.read().unwrap().is_none());
     }
 
+    /// Tests that an event is dispatched to the actor routed its event source.
+    #[test]
+    fn poller_routes_event_by_source() {
+        const SQS_ACTOR: &str = "SQS_ACTOR";
+        let body = br#"{"Records":[{"eventSource":"aws:sqs","body":"Hello"}]}"#;
+        let event = InvocationEventBuilder::new(body.to_vec())
+            .request_id(REQUEST_ID)
+            .build();
+        let poller = mock_poller(EventKind::Event(event));
+        let mut values = HashMap::new();
+        values.insert(crate::route::EVENT_SOURCES_KEY.into(), "aws:sqs".into());
+        poller.routes.write().unwrap().add_actor(SQS_ACTOR, &values);
+
+        let mock_dispatcher = Arc::new(MockWasccDispatcher::new(codec::Response::empty()));
+        let host_dispatcher: HostDispatcher = Arc::new(RwLock::new(Box::new(
+            SharedWasccDispatcher(Arc::clone(&mock_dispatcher)),
+        )));
+        let result = poller.run(RawEventDispatcher::new(host_dispatcher));
+        assert!(result.is_ok());
+
+        assert_eq!(
+            Some(SQS_ACTOR.into()),
+            *mock_dispatcher.actor.read().unwrap()
+        );
+        assert!(poller.client.invocation_response.read().unwrap().is_some());
+    }
+
+    /// Tests that each event is dispatched by the provider that its routed actor is bound to.
+    #[test]
+    fn poller_routes_event_to_bound_actor() {
+        const SQS_ACTOR: &str = "SQS_ACTOR";
+        let body = br#"{"Records":[{"eventSource":"aws:sqs","body":"Hello"}]}"#;
+        let sqs_event = InvocationEventBuilder::new(body.to_vec())
+            .request_id(REQUEST_ID)
+            .build();
+        let poller = mock_poller(EventKind::Events(vec![
+            sqs_event,
+            InvocationEvent::with_request_id(),
+        ]));
+        let mut values = HashMap::new();
+        values.insert(crate::route::EVENT_SOURCES_KEY.into(), "aws:sqs".into());
+        poller.routes.write().unwrap().add_actor(SQS_ACTOR, &values);
+
+        let actors = ActorDispatchers::default();
+        let mut mock_dispatchers = vec![];
+        for actor in &[SQS_ACTOR, MODULE_ID] {
+            let mock_dispatcher = Arc::new(MockWasccDispatcher::new(codec::Response::empty()));
+            let host_dispatcher: HostDispatcher = Arc::new(RwLock::new(Box::new(
+                SharedWasccDispatcher(Arc::clone(&mock_dispatcher)),
+            )));
+            actors.bind(
+                actor,
+                Arc::clone(&host_dispatcher),
+                RawEventDispatcher::new(host_dispatcher),
+            );
+            mock_dispatchers.push(mock_dispatcher);
+        }
+        let result = poller.run(actors.clone());
+        assert!(result.is_ok());
+
+        assert_eq!(
+            Some(SQS_ACTOR.into()),
+            *mock_dispatchers[0].actor.read().unwrap()
+        );
+        assert_eq!(
+            Some(MODULE_ID.into()),
+            *mock_dispatchers[1].actor.read().unwrap()
+        );
+        assert_eq!(
+            vec![SQS_ACTOR.to_string(), MODULE_ID.to_string()],
+            actors
+                .host_dispatchers(&[SQS_ACTOR.into(), MODULE_ID.into()])
+                .into_iter()
+                .map(|(actor, _)| actor)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// Tests that an event with no routed actor sends an error.
+    #[test]
+    fn poller_no_route() {
+        let stopper = Stopper::new();
+        let mut values = HashMap::new();
+        values.insert(crate::route::EVENT_SOURCES_KEY.into(), "aws:sqs".into());
+        let mut routes = RoutingTable::default();
+        routes.add_actor(MODULE_ID, &values);
+        let poller = Poller::new(
+            Arc::new(RwLock::new(routes)),
+            FunctionSettings::default(),
+            MockClient::new(
+                EventKind::Event(InvocationEvent::with_request_id()),
+                stopper.clone(),
+            ),
+            stopper,
+        );
+        let result = poller.run(dispatcher());
+        assert!(result.is_ok());
+
+        assert!(poller.client.invocation_response.read().unwrap().is_none());
+        assert!(poller.client.invocation_error.read().unwrap().is_some());
+    }
+
+    /// Tests the shutdown deadline on `SIGTERM`.
+    #[test]
+    fn sigterm_shutdown_deadline() {
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let shutdown = sigterm_shutdown();
+        let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+        assert_eq!("sigterm", shutdown.reason);
+        assert!(shutdown.deadline_ms >= (before + SHUTDOWN_DURATION).as_millis() as u64);
+        assert!(shutdown.deadline_ms <= (after + SHUTDOWN_DURATION).as_millis() as u64);
+    }
+
+    /// Tests that shutdown stops the poller and notifies the actor.
+    #[test]
+    fn shutdown_stops_poller() {
+        let stopper = Stopper::new();
+        let mock_dispatcher = Arc::new(MockWasccDispatcher::new(codec::Response::empty()));
+        let host_dispatcher: HostDispatcher = Arc::new(RwLock::new(Box::new(
+            SharedWasccDispatcher(Arc::clone(&mock_dispatcher)),
+        )));
+        let shutdown = codec::Shutdown {
+            reason: "spindown".into(),
+            deadline_ms: 0,
+        };
+        let result = handle_shutdown(
+            &stopper,
+            &[(MODULE_ID.into(), host_dispatcher)],
+            shutdown.clone(),
+        );
+        assert!(result.is_ok());
+
+        assert!(StopperR::stop(&stopper).unwrap());
+        assert_eq!(
+            Some(MODULE_ID.into()),
+            *mock_dispatcher.actor.read().unwrap()
+        );
+        assert_eq!(
+            Some(codec::OP_HANDLE_SHUTDOWN.into()),
+            *mock_dispatcher.op.read().unwrap()
+        );
+        assert_eq!(
+            Some(serialize(shutdown).unwrap()),
+            *mock_dispatcher.msg.read().unwrap()
+        );
+    }
+
+    /// Tests that shutdown stops the poller even if the actor fails to handle it.
+    #[test]
+    fn shutdown_error_dispatcher() {
+        let stopper = Stopper::new();
+        let result = handle_shutdown(
+            &stopper,
+            &[(MODULE_ID.into(), error_host_dispatcher())],
+            codec::Shutdown::default(),
+        );
+        assert!(result.is_err());
+
+        assert!(StopperR::stop(&stopper).unwrap());
+    }
+
     #[test]
     fn raw_event_provider_unsupported_operation() {
-        let provider = default_raw_event_provider();
+        let provider = default_raw_event_provider(&EventRouter::new());
         let result = provider.configure_dispatch(boxed_mock_dispatcher(RESPONSE_BODY));
         assert!(result.is_ok());
 
@@ -741,9 +2073,21 @@ mod tests {
             .contains("Unsupported operation"));
     }
 
+    #[test]
+    fn raw_event_provider_stream_response_chunk_not_polling() {
+        let provider = default_raw_event_provider(&EventRouter::new());
+        let result = provider.configure_dispatch(boxed_mock_dispatcher(RESPONSE_BODY));
+        assert!(result.is_ok());
+
+        let chunk = serialize(codec::ResponseChunk::new(REQUEST_ID, EVENT_BODY.to_vec())).unwrap();
+        let result = provider.handle_call(MODULE_ID, codec::OP_STREAM_RESPONSE_CHUNK, &chunk);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Not polling"));
+    }
+
     #[test]
     fn raw_event_provider_no_config() {
-        let provider = default_raw_event_provider();
+        let provider = default_raw_event_provider(&EventRouter::new());
         let result = provider.configure_dispatch(boxed_mock_dispatcher(RESPONSE_BODY));
         assert!(result.is_ok());
 
@@ -757,7 +2101,7 @@ mod tests {
 
     #[test]
     fn raw_event_provider_no_endpoint() {
-        let provider = default_raw_event_provider();
+        let provider = default_raw_event_provider(&EventRouter::new());
         let result = provider.configure_dispatch(boxed_mock_dispatcher(RESPONSE_BODY));
         assert!(result.is_ok());
 
@@ -770,12 +2114,58 @@ mod tests {
             .contains("Missing configuration value"));
     }
 
+    /// Tests that actors bound to different providers sharing a router are routed events by a single poller.
+    #[test]
+    fn providers_share_router() {
+        const SQS_ACTOR: &str = "SQS_ACTOR";
+        let stopper = Stopper::new();
+        let router = Arc::new(Router::new(stopper.clone()));
+        let raw_event_provider = LambdaRawEventProvider::new(
+            Arc::clone(&router),
+            MockClientFactory::new(EventKind::None, stopper.clone()),
+        );
+        let sqs_provider = LambdaSqsProvider::new(
+            Arc::clone(&router),
+            MockClientFactory::new(EventKind::None, stopper),
+        );
+        let result = raw_event_provider.configure_dispatch(boxed_mock_dispatcher(RESPONSE_BODY));
+        assert!(result.is_ok());
+        let result = sqs_provider.configure_dispatch(boxed_mock_dispatcher(RESPONSE_BODY));
+        assert!(result.is_ok());
+
+        let result =
+            raw_event_provider.handle_call("system", OP_BIND_ACTOR, &capability_configuration());
+        assert!(result.is_ok());
+        let poller = Arc::clone(router.poller.read().unwrap().as_ref().unwrap());
+
+        let mut values = HashMap::new();
+        values.insert("AWS_LAMBDA_RUNTIME_API".into(), "localhost:8080".into());
+        values.insert(crate::route::EVENT_SOURCES_KEY.into(), "aws:sqs".into());
+        let config = serialize(CapabilityConfiguration {
+            module: SQS_ACTOR.into(),
+            values,
+        })
+        .unwrap();
+        let result = sqs_provider.handle_call("system", OP_BIND_ACTOR, &config);
+        assert!(result.is_ok());
+
+        assert!(Arc::ptr_eq(
+            &poller,
+            router.poller.read().unwrap().as_ref().unwrap()
+        ));
+        let routes = router.routes.read().unwrap();
+        assert_eq!(SQS_ACTOR, routes.route(EventSource::Sqs).unwrap());
+        assert_eq!(MODULE_ID, routes.route(EventSource::Sns).unwrap());
+        assert_eq!(2, router.actors.host_dispatchers(&routes.actors()).len());
+    }
+
     #[test]
     fn raw_event_provider_ok() {
         let client_factory =
             mock_client_factory(EventKind::Event(InvocationEvent::with_request_id()));
         let mut stopper = Stopper::new();
-        let provider = LambdaRawEventProvider::new(stopper.clone(), client_factory);
+        let provider =
+            LambdaRawEventProvider::new(Arc::new(Router::new(stopper.clone())), client_factory);
         let mock_dispatcher = boxed_mock_dispatcher(RESPONSE_BODY);
         let result = provider.configure_dispatch(mock_dispatcher);
         assert!(result.is_ok());
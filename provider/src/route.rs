@@ -0,0 +1,170 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use std::collections::HashMap;
+
+use crate::classify::EventSource;
+
+/// The configuration value listing the event sources routed to an actor.
+pub(crate) const EVENT_SOURCES_KEY: &str = "WASCC_EVENT_SOURCES";
+
+/// The event source that matches any event.
+pub(crate) const ANY_EVENT_SOURCE: &str = "*";
+
+/// No actor is bound for the invocation event's source.
+#[derive(thiserror::Error, Debug)]
+#[error("No actor for event source {}", event_source)]
+pub(crate) struct NoRouteError {
+    event_source: String,
+}
+
+/// Maps event sources to the actors that handle them.
+/// The capability providers sharing a router share its routing table, so routed actors can be bound to different capabilities.
+#[derive(Debug, Default)]
+pub(crate) struct RoutingTable {
+    /// (event source, actor) pairs in the order the actors were bound.
+    routes: Vec<(String, String)>,
+}
+
+impl RoutingTable {
+    /// Adds routes for an actor from its binding configuration values.
+    /// An actor with no configured event sources handles any event.
+    pub fn add_actor(&mut self, actor: &str, values: &HashMap<String, String>) {
+        let sources: Vec<&str> = values
+            .get(EVENT_SOURCES_KEY)
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if sources.is_empty() {
+            self.add_route(ANY_EVENT_SOURCE, actor);
+        } else {
+            for source in sources {
+                self.add_route(source, actor);
+            }
+        }
+    }
+
+    /// Adds a route.
+    fn add_route(&mut self, source: &str, actor: &str) {
+        info!("Routing {} events to actor {}", source, actor);
+        self.routes.push((source.into(), actor.into()));
+    }
+
+    /// Returns the distinct routed actors in the order they were bound.
+    pub fn actors(&self) -> Vec<String> {
+        let mut actors: Vec<String> = vec![];
+        for (_, actor) in &self.routes {
+            if !actors.contains(actor) {
+                actors.push(actor.clone());
+            }
+        }
+
+        actors
+    }
+
+    /// Returns the actor that handles events from the specified source.
+    /// A route for the event source takes precedence over a route for its more general source,
+    /// which takes precedence over a route for any event.
+    pub fn route(&self, source: EventSource) -> anyhow::Result<&str> {
+        let name = match source {
+            EventSource::Unknown => None,
+            source => Some(source.name()),
+        };
+
+        name.and_then(|name| self.find(name))
+            .or_else(|| source.family().and_then(|family| self.find(family)))
+            .or_else(|| self.find(ANY_EVENT_SOURCE))
+            .ok_or_else(|| {
+                NoRouteError {
+                    event_source: source.to_string(),
+                }
+                .into()
+            })
+    }
+
+    /// Returns the first actor routed events from the specified source.
+    fn find(&self, source: &str) -> Option<&str> {
+        self.routes
+            .iter()
+            .find(|(route_source, _)| route_source == source)
+            .map(|(_, actor)| actor.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQS_ACTOR: &str = "SQS_ACTOR";
+    const DEFAULT_ACTOR: &str = "DEFAULT_ACTOR";
+
+    /// Returns binding configuration values for the specified event sources.
+    fn values(sources: &str) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        values.insert(EVENT_SOURCES_KEY.into(), sources.into());
+        values
+    }
+
+    #[test]
+    fn routing_table_route_by_source() {
+        let mut routes = RoutingTable::default();
+        routes.add_actor(SQS_ACTOR, &values("aws:sqs, aws:events"));
+        routes.add_actor(DEFAULT_ACTOR, &HashMap::new());
+
+        assert_eq!(SQS_ACTOR, routes.route(EventSource::Sqs).unwrap());
+        assert_eq!(SQS_ACTOR, routes.route(EventSource::Scheduled).unwrap());
+        assert_eq!(DEFAULT_ACTOR, routes.route(EventSource::Sns).unwrap());
+        assert_eq!(DEFAULT_ACTOR, routes.route(EventSource::Unknown).unwrap());
+
+        assert_eq!(
+            vec![SQS_ACTOR.to_string(), DEFAULT_ACTOR.to_string()],
+            routes.actors()
+        );
+    }
+
+    #[test]
+    fn routing_table_route_by_family() {
+        let mut routes = RoutingTable::default();
+        routes.add_actor(SQS_ACTOR, &values("aws:apigateway:v2"));
+        routes.add_actor(DEFAULT_ACTOR, &values("aws:apigateway"));
+
+        assert_eq!(SQS_ACTOR, routes.route(EventSource::ApiGatewayV2).unwrap());
+        assert_eq!(
+            DEFAULT_ACTOR,
+            routes.route(EventSource::ApiGatewayV1).unwrap()
+        );
+    }
+
+    #[test]
+    fn routing_table_no_route() {
+        let mut routes = RoutingTable::default();
+        routes.add_actor(SQS_ACTOR, &values("aws:sqs"));
+
+        let result = routes.route(EventSource::FunctionUrl);
+        assert!(result.is_err());
+        assert_eq!(
+            "No actor for event source aws:lambda-url",
+            result.err().unwrap().to_string()
+        );
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use std::env;
+
+/// The AWS Signature Version 4 signing algorithm.
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Represents AWS credentials used to sign requests.
+#[derive(Clone, Debug)]
+pub(crate) struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Returns new `Credentials`.
+    pub fn new(access_key_id: &str, secret_access_key: &str, session_token: Option<&str>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: session_token.map(String::from),
+        }
+    }
+
+    /// Returns the execution role's credentials from the function's environment variables.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let var =
+            |key: &str| env::var(key).map_err(|_| anyhow!("Missing environment variable: {}", key));
+
+        Ok(Self::new(
+            &var("AWS_ACCESS_KEY_ID")?,
+            &var("AWS_SECRET_ACCESS_KEY")?,
+            env::var("AWS_SESSION_TOKEN").ok().as_deref(),
+        ))
+    }
+}
+
+/// Signs an HTTP request using AWS Signature Version 4.
+/// Returns the headers to add to the request.
+/// https://docs.aws.amazon.com/general/latest/gr/sigv4_signing.html
+pub(crate) fn sign(
+    method: &str,
+    url: &url::Url,
+    body: &[u8],
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    time: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = time.format("%Y%m%d").to_string();
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().into(),
+    };
+    let mut headers = vec![("host", host), ("x-amz-date", amz_date.clone())];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        uri_encode(url.path(), false),
+        canonical_query_string(url),
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(body))
+    );
+    debug!("Canonical request:\n{}", canonical_request);
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = format!("AWS4{}", credentials.secret_access_key);
+    let key = hmac(key.as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    let key = hmac(&key, b"aws4_request");
+    let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+    let mut signed: Vec<(String, String)> = headers
+        .into_iter()
+        .filter(|(name, _)| *name != "host")
+        .map(|(name, value)| (name.into(), value))
+        .collect();
+    signed.push((
+        "authorization".into(),
+        format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+
+    signed
+}
+
+/// Returns the HMAC-SHA256 of the specified data.
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC can take a key of any size.
+    let mut mac = Hmac::<Sha256>::new_varkey(key).unwrap();
+    mac.input(data);
+    mac.result().code().to_vec()
+}
+
+/// Returns the canonical form of the URL's query string, its parameters sorted by name.
+fn canonical_query_string(url: &url::Url) -> String {
+    let mut parameters: Vec<String> = url
+        .query_pairs()
+        .map(|(name, value)| format!("{}={}", uri_encode(&name, true), uri_encode(&value, true)))
+        .collect();
+    parameters.sort();
+    parameters.join("&")
+}
+
+/// URI encodes every byte of a string except the unreserved characters
+/// and, optionally, the forward slash.
+pub(crate) fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Returns the credentials used by the AWS Signature Version 4 test suite.
+    fn test_suite_credentials() -> Credentials {
+        Credentials::new(
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            None,
+        )
+    }
+
+    #[test]
+    fn sign_get_vanilla() {
+        let url = url::Url::parse("https://example.amazonaws.com/").unwrap();
+        let time = Utc.ymd(2015, 8, 30).and_hms(12, 36, 0);
+
+        let headers = sign(
+            "GET",
+            &url,
+            b"",
+            &test_suite_credentials(),
+            "us-east-1",
+            "service",
+            time,
+        );
+        assert_eq!(
+            vec![
+                ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+                (
+                    "authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31".to_string()
+                ),
+            ],
+            headers
+        );
+    }
+
+    #[test]
+    fn uri_encode_path() {
+        assert_eq!(
+            "/prod/%40connections/abc%253D",
+            uri_encode("/prod/@connections/abc%3D", false)
+        );
+        assert_eq!("a%2Fb%3D", uri_encode("a/b=", true));
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Represents an Amazon SQS event received from AWS Lambda.
+/// https://docs.aws.amazon.com/lambda/latest/dg/with-sqs.html
+#[derive(Debug, Deserialize)]
+pub(crate) struct SqsEvent {
+    #[serde(rename = "Records")]
+    pub records: Vec<SqsRecord>,
+}
+
+/// Represents a single Amazon SQS message in an event.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SqsRecord {
+    pub message_id: String,
+    #[serde(default)]
+    receipt_handle: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    md5_of_body: String,
+    #[serde(default)]
+    attributes: HashMap<String, String>,
+    #[serde(default)]
+    message_attributes: HashMap<String, SqsRecordAttribute>,
+    #[serde(default, rename = "eventSourceARN")]
+    pub event_source_arn: String,
+    #[serde(default)]
+    aws_region: String,
+}
+
+/// Represents an Amazon SQS message attribute in an event.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SqsRecordAttribute {
+    #[serde(default)]
+    data_type: String,
+    #[serde(default)]
+    string_value: Option<String>,
+    /// Base64 encoded.
+    #[serde(default)]
+    binary_value: Option<String>,
+}
+
+impl SqsRecord {
+    /// Returns whether or not the message is from a FIFO queue.
+    pub fn is_fifo(&self) -> bool {
+        self.event_source_arn.ends_with(".fifo")
+    }
+}
+
+impl TryFrom<SqsRecord> for codec::SqsMessage {
+    type Error = anyhow::Error;
+
+    /// Attempts conversion of an Amazon SQS message in an event to an actor's message.
+    fn try_from(record: SqsRecord) -> anyhow::Result<Self> {
+        let mut message_attributes = HashMap::new();
+        for (name, attribute) in record.message_attributes {
+            message_attributes.insert(
+                name,
+                codec::SqsMessageAttribute {
+                    data_type: attribute.data_type,
+                    string_value: attribute.string_value,
+                    binary_value: match attribute.binary_value {
+                        Some(s) => base64::decode(s)?,
+                        None => vec![],
+                    },
+                },
+            );
+        }
+
+        Ok(codec::SqsMessage {
+            message_id: record.message_id,
+            receipt_handle: record.receipt_handle,
+            body: record.body,
+            md5_of_body: record.md5_of_body,
+            attributes: record.attributes,
+            message_attributes,
+            event_source_arn: record.event_source_arn,
+            aws_region: record.aws_region,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn sqs_event_into_messages() {
+        let event: SqsEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                {
+                    "messageId": "059f36b4-87a3-44ab-83d2-661975830a7d",
+                    "receiptHandle": "AQEBwJnKyrHigUMZj6rYigCgxlaS3SLy0a",
+                    "body": "Hello world",
+                    "attributes": {
+                        "ApproximateReceiveCount": "1"
+                    },
+                    "messageAttributes": {
+                        "Greeting": {
+                            "stringValue": "Hello",
+                            "dataType": "String"
+                        },
+                        "Payload": {
+                            "binaryValue": "AQID",
+                            "dataType": "Binary"
+                        }
+                    },
+                    "md5OfBody": "3e25960a79dbc69b674cd4ec67a72c62",
+                    "eventSource": "aws:sqs",
+                    "eventSourceARN": "arn:aws:sqs:us-east-2:123456789012:my-queue.fifo",
+                    "awsRegion": "us-east-2"
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(1, event.records.len());
+        assert!(event.records[0].is_fifo());
+
+        let message: codec::SqsMessage = event
+            .records
+            .into_iter()
+            .next()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!("059f36b4-87a3-44ab-83d2-661975830a7d", message.message_id);
+        assert_eq!("Hello world", message.body);
+        assert_eq!("1", message.attributes["ApproximateReceiveCount"]);
+        assert_eq!(
+            Some("Hello".into()),
+            message.message_attributes["Greeting"].string_value
+        );
+        assert_eq!(
+            vec![1, 2, 3],
+            message.message_attributes["Payload"].binary_value
+        );
+        assert_eq!("us-east-2", message.aws_region);
+    }
+}
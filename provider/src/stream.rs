@@ -0,0 +1,306 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+/// Represents an Amazon Kinesis Data Streams event received from AWS Lambda.
+/// https://docs.aws.amazon.com/lambda/latest/dg/with-kinesis.html
+#[derive(Debug, Deserialize)]
+pub(crate) struct KinesisEvent {
+    #[serde(rename = "Records")]
+    pub records: Vec<KinesisEventRecord>,
+}
+
+/// Represents a single Amazon Kinesis Data Streams record in an event.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct KinesisEventRecord {
+    #[serde(default, rename = "eventID")]
+    event_id: String,
+    #[serde(default, rename = "eventSourceARN")]
+    event_source_arn: String,
+    #[serde(default)]
+    aws_region: String,
+    pub kinesis: KinesisData,
+}
+
+/// Represents the Amazon Kinesis specific data of a record.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct KinesisData {
+    #[serde(default)]
+    partition_key: String,
+    pub sequence_number: String,
+    #[serde(default)]
+    approximate_arrival_timestamp: f64,
+    /// Base64 encoded.
+    #[serde(default)]
+    data: String,
+}
+
+impl TryFrom<KinesisEventRecord> for codec::KinesisRecord {
+    type Error = anyhow::Error;
+
+    /// Attempts conversion of an Amazon Kinesis record in an event to an actor's record.
+    fn try_from(record: KinesisEventRecord) -> anyhow::Result<Self> {
+        Ok(codec::KinesisRecord {
+            event_id: record.event_id,
+            event_source_arn: record.event_source_arn,
+            aws_region: record.aws_region,
+            data: base64::decode(&record.kinesis.data)?,
+            partition_key: record.kinesis.partition_key,
+            sequence_number: record.kinesis.sequence_number,
+            approximate_arrival_timestamp: record.kinesis.approximate_arrival_timestamp,
+        })
+    }
+}
+
+/// Represents an Amazon DynamoDB Streams event received from AWS Lambda.
+/// https://docs.aws.amazon.com/lambda/latest/dg/with-ddb.html
+#[derive(Debug, Deserialize)]
+pub(crate) struct DynamoDbEvent {
+    #[serde(rename = "Records")]
+    pub records: Vec<DynamoDbEventRecord>,
+}
+
+/// Represents a single Amazon DynamoDB Streams record in an event.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DynamoDbEventRecord {
+    #[serde(default, rename = "eventID")]
+    event_id: String,
+    #[serde(default)]
+    event_name: String,
+    #[serde(default, rename = "eventSourceARN")]
+    event_source_arn: String,
+    #[serde(default)]
+    aws_region: String,
+    pub dynamodb: DynamoDbStreamRecord,
+}
+
+/// Represents the Amazon DynamoDB specific data of a record.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct DynamoDbStreamRecord {
+    pub sequence_number: String,
+    #[serde(default)]
+    approximate_creation_date_time: f64,
+    #[serde(default)]
+    size_bytes: i64,
+    #[serde(default)]
+    stream_view_type: String,
+    #[serde(default)]
+    keys: HashMap<String, AttributeValue>,
+    #[serde(default)]
+    new_image: HashMap<String, AttributeValue>,
+    #[serde(default)]
+    old_image: HashMap<String, AttributeValue>,
+}
+
+impl TryFrom<DynamoDbEventRecord> for codec::DynamoDbRecord {
+    type Error = anyhow::Error;
+
+    /// Attempts conversion of an Amazon DynamoDB Streams record in an event to an actor's record.
+    fn try_from(record: DynamoDbEventRecord) -> anyhow::Result<Self> {
+        let change = record.dynamodb;
+
+        Ok(codec::DynamoDbRecord {
+            event_id: record.event_id,
+            event_name: record.event_name,
+            event_source_arn: record.event_source_arn,
+            aws_region: record.aws_region,
+            sequence_number: change.sequence_number,
+            approximate_creation_date_time: change.approximate_creation_date_time,
+            size_bytes: change.size_bytes,
+            stream_view_type: change.stream_view_type,
+            keys: attribute_map(change.keys)?,
+            new_image: attribute_map(change.new_image)?,
+            old_image: attribute_map(change.old_image)?,
+        })
+    }
+}
+
+/// Represents an Amazon DynamoDB attribute value in an event.
+/// Binary values are base64 encoded.
+#[derive(Debug, Deserialize)]
+enum AttributeValue {
+    S(String),
+    N(String),
+    B(String),
+    SS(Vec<String>),
+    NS(Vec<String>),
+    BS(Vec<String>),
+    M(HashMap<String, AttributeValue>),
+    L(Vec<AttributeValue>),
+    #[serde(rename = "NULL")]
+    Null(bool),
+    #[serde(rename = "BOOL")]
+    Bool(bool),
+}
+
+impl TryFrom<AttributeValue> for codec::AttributeValue {
+    type Error = anyhow::Error;
+
+    /// Attempts conversion of an attribute value in an event to an actor's attribute value.
+    fn try_from(value: AttributeValue) -> anyhow::Result<Self> {
+        Ok(match value {
+            AttributeValue::S(s) => codec::AttributeValue::S(s),
+            AttributeValue::N(n) => codec::AttributeValue::N(n),
+            AttributeValue::B(b) => codec::AttributeValue::B(base64::decode(b)?),
+            AttributeValue::SS(ss) => codec::AttributeValue::SS(ss),
+            AttributeValue::NS(ns) => codec::AttributeValue::NS(ns),
+            AttributeValue::BS(bs) => codec::AttributeValue::BS(
+                bs.into_iter()
+                    .map(|b| Ok(base64::decode(b)?.into()))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            AttributeValue::M(m) => codec::AttributeValue::M(attribute_map(m)?),
+            AttributeValue::L(l) => codec::AttributeValue::L(
+                l.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            AttributeValue::Null(null) => codec::AttributeValue::NULL(null),
+            AttributeValue::Bool(b) => codec::AttributeValue::BOOL(b),
+        })
+    }
+}
+
+/// Attempts conversion of a map of attribute values in an event to an actor's attribute values.
+fn attribute_map(
+    map: HashMap<String, AttributeValue>,
+) -> anyhow::Result<HashMap<String, codec::AttributeValue>> {
+    map.into_iter()
+        .map(|(name, value)| Ok((name, value.try_into()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinesis_event_into_records() {
+        let event: KinesisEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                {
+                    "kinesis": {
+                        "kinesisSchemaVersion": "1.0",
+                        "partitionKey": "1",
+                        "sequenceNumber": "49590338271490256608559692538361571095921575989136588898",
+                        "data": "SGVsbG8sIHRoaXMgaXMgYSB0ZXN0Lg==",
+                        "approximateArrivalTimestamp": 1545084650.987
+                    },
+                    "eventSource": "aws:kinesis",
+                    "eventVersion": "1.0",
+                    "eventID": "shardId-000000000006:49590338271490256608559692538361571095921575989136588898",
+                    "eventName": "aws:kinesis:record",
+                    "awsRegion": "us-east-2",
+                    "eventSourceARN": "arn:aws:kinesis:us-east-2:123456789012:stream/lambda-stream"
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(1, event.records.len());
+
+        let record: codec::KinesisRecord = event
+            .records
+            .into_iter()
+            .next()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(b"Hello, this is a test.".to_vec(), record.data);
+        assert_eq!("1", record.partition_key);
+        assert_eq!(
+            "49590338271490256608559692538361571095921575989136588898",
+            record.sequence_number
+        );
+        assert_eq!("us-east-2", record.aws_region);
+    }
+
+    #[test]
+    fn dynamodb_event_into_records() {
+        let event: DynamoDbEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                {
+                    "eventID": "c4ca4238a0b923820dcc509a6f75849b",
+                    "eventName": "MODIFY",
+                    "eventVersion": "1.1",
+                    "eventSource": "aws:dynamodb",
+                    "awsRegion": "us-east-1",
+                    "dynamodb": {
+                        "Keys": {
+                            "Id": {"N": "101"}
+                        },
+                        "NewImage": {
+                            "Message": {"S": "This item has changed"},
+                            "Id": {"N": "101"},
+                            "Tags": {"L": [{"S": "new"}, {"NULL": true}]},
+                            "Payload": {"B": "AQID"}
+                        },
+                        "OldImage": {
+                            "Message": {"S": "New item!"},
+                            "Id": {"N": "101"}
+                        },
+                        "ApproximateCreationDateTime": 1428537600,
+                        "SequenceNumber": "4421584500000000017450439092",
+                        "SizeBytes": 59,
+                        "StreamViewType": "NEW_AND_OLD_IMAGES"
+                    },
+                    "eventSourceARN": "arn:aws:dynamodb:us-east-1:123456789012:table/ExampleTableWithStream/stream/2015-06-27T00:48:05.899"
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(1, event.records.len());
+
+        let record: codec::DynamoDbRecord = event
+            .records
+            .into_iter()
+            .next()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!("MODIFY", record.event_name);
+        assert_eq!("4421584500000000017450439092", record.sequence_number);
+        assert_eq!(codec::AttributeValue::N("101".into()), record.keys["Id"]);
+        assert_eq!(
+            codec::AttributeValue::S("This item has changed".into()),
+            record.new_image["Message"]
+        );
+        assert_eq!(
+            codec::AttributeValue::L(vec![
+                codec::AttributeValue::S("new".into()),
+                codec::AttributeValue::NULL(true),
+            ]),
+            record.new_image["Tags"]
+        );
+        assert_eq!(
+            codec::AttributeValue::B(vec![1, 2, 3]),
+            record.new_image["Payload"]
+        );
+        assert_eq!(
+            codec::AttributeValue::S("New item!".into()),
+            record.old_image["Message"]
+        );
+    }
+}
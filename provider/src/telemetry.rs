@@ -0,0 +1,757 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Telemetry Provider
+//
+
+use http1::{Reply, Request};
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use wascc_codec::capabilities::CapabilityProvider;
+use wascc_codec::core::{CapabilityConfiguration, OP_BIND_ACTOR};
+use wascc_codec::{deserialize, serialize};
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::env;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+use crate::extension::{ExtensionClient, ExtensionListener, RuntimeExtensionClient};
+use crate::HostDispatcher;
+
+const EXTENSION_IDENTIFIER_HEADER_NAME: &str = "Lambda-Extension-Identifier";
+
+/// The name the telemetry subscriber registers as an internal extension under.
+const EXTENSION_NAME: &str = "wascc-telemetry";
+
+/// The host name of the execution environment as seen by the AWS Lambda Telemetry API.
+const SANDBOX_HOST: &str = "sandbox.localdomain";
+
+/// Represents the Telemetry API subscription settings.
+#[derive(Clone, Debug)]
+pub(crate) struct TelemetrySettings {
+    /// The port the telemetry listener binds to.
+    pub port: u16,
+    /// The telemetry stream types to subscribe to.
+    pub types: Vec<String>,
+}
+
+impl TelemetrySettings {
+    /// Returns the settings in the specified configuration values.
+    /// Missing or invalid values are defaulted.
+    pub fn from_config(values: &HashMap<String, String>) -> Self {
+        let default = Self::default();
+
+        Self {
+            port: values
+                .get("WASCC_TELEMETRY_PORT")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.port),
+            types: values
+                .get("WASCC_TELEMETRY_TYPES")
+                .map(|v| {
+                    v.split(',')
+                        .map(|t| t.trim().to_string())
+                        .filter(|t| !t.is_empty())
+                        .collect()
+                })
+                .unwrap_or(default.types),
+        }
+    }
+}
+
+impl Default for TelemetrySettings {
+    /// Returns the default value for `TelemetrySettings`.
+    fn default() -> Self {
+        Self {
+            port: 4243,
+            types: vec!["platform".into(), "function".into(), "extension".into()],
+        }
+    }
+}
+
+/// Represents an AWS Lambda Telemetry API client.
+pub(crate) trait TelemetryClient {
+    /// Subscribes the specified extension to telemetry sent to the specified destination.
+    fn subscribe(
+        &self,
+        extension_id: &str,
+        destination: &str,
+        settings: &TelemetrySettings,
+    ) -> anyhow::Result<()>;
+}
+
+/// Represents an AWS Lambda Telemetry API client.
+pub(crate) struct RuntimeTelemetryClient {
+    endpoint: String,
+    http_client: reqwest::blocking::Client,
+    user_agent: String,
+}
+
+impl RuntimeTelemetryClient {
+    /// Creates a new `RuntimeTelemetryClient` with the specified AWS Lambda runtime API endpoint.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            http_client: reqwest::blocking::Client::new(),
+            user_agent: format!("AWS_Lambda_waSCC/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    fn subscribe_path() -> String {
+        "2022-07-01/telemetry".into()
+    }
+}
+
+impl TelemetryClient for RuntimeTelemetryClient {
+    /// Subscribes the specified extension to telemetry sent to the specified destination.
+    fn subscribe(
+        &self,
+        extension_id: &str,
+        destination: &str,
+        settings: &TelemetrySettings,
+    ) -> anyhow::Result<()> {
+        // https://docs.aws.amazon.com/lambda/latest/dg/telemetry-api-reference.html
+        let url = format!("{}/{}", self.endpoint, Self::subscribe_path());
+        let resp = self
+            .http_client
+            .put(&url)
+            .header(USER_AGENT, self.user_agent.clone())
+            .header(EXTENSION_IDENTIFIER_HEADER_NAME, extension_id)
+            .json(&serde_json::json!({
+                "schemaVersion": "2022-12-13",
+                "types": settings.types,
+                "buffering": {
+                    "maxItems": 1000,
+                    "maxBytes": 262144,
+                    "timeoutMs": 100,
+                },
+                "destination": {
+                    "protocol": "HTTP",
+                    "URI": destination,
+                },
+            }))
+            .send()?;
+        let status = resp.status();
+        info!(
+            "PUT {} {} {}",
+            url,
+            status.as_str(),
+            status.canonical_reason().unwrap_or("Unknown")
+        );
+        if !status.is_success() {
+            return Err(anyhow!(
+                "Telemetry subscription failed: {}: {}",
+                status,
+                resp.text().unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Describes a telemetry event as sent by the AWS Lambda Telemetry API.
+#[derive(Deserialize)]
+struct TelemetryApiEvent {
+    #[serde(default)]
+    time: String,
+    #[serde(rename = "type", default)]
+    event_type: String,
+    #[serde(default)]
+    record: serde_json::Value,
+}
+
+impl TelemetryApiEvent {
+    /// Converts the event to its codec representation.
+    fn into_codec(self) -> anyhow::Result<codec::TelemetryEvent> {
+        Ok(codec::TelemetryEvent {
+            time: self.time,
+            event_type: self.event_type,
+            record: serde_json::to_vec(&self.record)?,
+        })
+    }
+}
+
+/// Receives telemetry batches from the AWS Lambda Telemetry API and dispatches them to actors.
+pub(crate) struct TelemetryListener {
+    actors: Arc<RwLock<Vec<String>>>,
+    host_dispatcher: HostDispatcher,
+    listener: TcpListener,
+}
+
+impl TelemetryListener {
+    /// Binds a new `TelemetryListener` to the specified port.
+    pub fn bind(
+        port: u16,
+        host_dispatcher: HostDispatcher,
+        actors: Arc<RwLock<Vec<String>>>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            actors,
+            host_dispatcher,
+            listener: TcpListener::bind(("0.0.0.0", port))?,
+        })
+    }
+
+    /// Returns the port the listener is bound to.
+    pub fn port(&self) -> anyhow::Result<u16> {
+        Ok(self.listener.local_addr()?.port())
+    }
+
+    /// Serves the AWS Lambda Telemetry API's requests until the listener fails.
+    pub fn run(self) -> anyhow::Result<()> {
+        let listener = self.listener.try_clone()?;
+        let telemetry = Arc::new(self);
+        http1::serve(listener, move |request| telemetry.handle(request))
+    }
+
+    /// Handles a request containing a batch of telemetry events.
+    fn handle(&self, request: &Request) -> Reply {
+        match self.dispatch_batch(&request.body) {
+            Ok(_) => (200, vec![], vec![]),
+            Err(e) => {
+                error!("{}", e);
+                (400, vec![], vec![])
+            }
+        }
+    }
+
+    /// Dispatches a batch of telemetry events to all bound actors.
+    /// Actor failures are logged and don't fail the batch.
+    fn dispatch_batch(&self, body: &[u8]) -> anyhow::Result<()> {
+        let events: Vec<TelemetryApiEvent> = serde_json::from_slice(body)?;
+        let batch = codec::TelemetryBatch {
+            events: events
+                .into_iter()
+                .map(TelemetryApiEvent::into_codec)
+                .collect::<anyhow::Result<_>>()?,
+        };
+        let msg = serialize(batch).map_err(|e| anyhow!("{}", e))?;
+
+        let host_dispatcher = self.host_dispatcher.read().unwrap();
+        for actor in self.actors.read().unwrap().iter() {
+            if let Err(e) = host_dispatcher.dispatch(actor, codec::OP_HANDLE_TELEMETRY, &msg) {
+                error!("Guest {} failed to handle telemetry: {}", actor, e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates Extensions and Telemetry API clients.
+trait TelemetryClientFactory {
+    /// Creates a new `ExtensionClient`.
+    fn new_extension_client(
+        &self,
+        endpoint: &str,
+    ) -> anyhow::Result<Box<dyn ExtensionClient + Send>>;
+
+    /// Creates a new `TelemetryClient`.
+    fn new_telemetry_client(&self, endpoint: &str) -> Box<dyn TelemetryClient>;
+}
+
+/// Creates `RuntimeExtensionClient` and `RuntimeTelemetryClient` instances.
+struct RuntimeTelemetryClientFactory;
+
+impl TelemetryClientFactory for RuntimeTelemetryClientFactory {
+    /// Creates a new `RuntimeExtensionClient`.
+    fn new_extension_client(
+        &self,
+        endpoint: &str,
+    ) -> anyhow::Result<Box<dyn ExtensionClient + Send>> {
+        Ok(Box::new(RuntimeExtensionClient::with_name(
+            endpoint,
+            EXTENSION_NAME,
+        )?))
+    }
+
+    /// Creates a new `RuntimeTelemetryClient`.
+    fn new_telemetry_client(&self, endpoint: &str) -> Box<dyn TelemetryClient> {
+        Box::new(RuntimeTelemetryClient::new(endpoint))
+    }
+}
+
+/// Represents a waSCC AWS Lambda telemetry provider.
+/// This capability provider subscribes to the AWS Lambda Telemetry API
+/// and dispatches telemetry batches to all bound actors.
+struct LambdaTelemetryProvider<CF> {
+    actors: Arc<RwLock<Vec<String>>>,
+    client_factory: CF,
+    extension_id: RwLock<Option<String>>,
+    host_dispatcher: HostDispatcher,
+    runtime_api: Option<String>,
+    subscribed: Mutex<bool>,
+}
+
+impl<CF: TelemetryClientFactory> LambdaTelemetryProvider<CF> {
+    /// Creates a new, empty `LambdaTelemetryProvider` for the specified AWS Lambda runtime API, if any.
+    fn new(client_factory: CF, runtime_api: Option<String>) -> Self {
+        Self {
+            actors: Arc::new(RwLock::new(vec![])),
+            client_factory,
+            extension_id: RwLock::new(None),
+            host_dispatcher: Arc::new(RwLock::new(Box::new(
+                wascc_codec::capabilities::NullDispatcher::new(),
+            ))),
+            runtime_api,
+            subscribed: Mutex::new(false),
+        }
+    }
+
+    /// Returns the AWS Lambda runtime API endpoint.
+    fn endpoint(&self) -> anyhow::Result<String> {
+        match &self.runtime_api {
+            Some(runtime_api) => Ok(format!("http://{}", runtime_api)),
+            None => Err(anyhow!(
+                "Missing environment variable: AWS_LAMBDA_RUNTIME_API"
+            )),
+        }
+    }
+
+    /// Registers the telemetry subscriber as an internal extension.
+    /// Extensions must register before the runtime first polls for an invocation event.
+    fn register(&self) -> anyhow::Result<()> {
+        let extension = ExtensionListener::register(
+            self.client_factory
+                .new_extension_client(&self.endpoint()?)?,
+        )?;
+        *self.extension_id.write().unwrap() = Some(extension.extension_id().into());
+
+        thread::spawn(move || {
+            if let Err(e) = extension.run() {
+                // The Lambda machinery will restart the runtime.
+                error!("Telemetry extension failed: {}", e);
+                std::process::exit(1);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Called by the host runtime when an actor is requesting a command be executed.
+    fn handle_call(&self, actor: &str, op: &str, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        info!("awslambda:telemetry handle_call `{}` from `{}`", op, actor);
+
+        match op {
+            OP_BIND_ACTOR if actor == "system" => {
+                self.bind_actor(deserialize(msg).map_err(|e| anyhow!("{}", e))?)?
+            }
+            _ => return Err(anyhow!("Unsupported operation: {}/{}", op, actor)),
+        }
+
+        Ok(vec![])
+    }
+
+    /// Binds an actor, subscribing to the Telemetry API for the first actor.
+    fn bind_actor(&self, config: CapabilityConfiguration) -> anyhow::Result<()> {
+        debug!("awslambda:telemetry bind_actor");
+
+        let mut subscribed = self.subscribed.lock().unwrap();
+        if !*subscribed {
+            self.subscribe(&config.values)?;
+            *subscribed = true;
+        }
+
+        self.actors.write().unwrap().push(config.module);
+
+        Ok(())
+    }
+
+    /// Starts the telemetry listener and subscribes it to the Telemetry API.
+    fn subscribe(&self, values: &HashMap<String, String>) -> anyhow::Result<()> {
+        let extension_id = match self.extension_id.read().unwrap().clone() {
+            Some(extension_id) => extension_id,
+            None => return Err(anyhow!("Telemetry extension not registered")),
+        };
+        let endpoint = self.endpoint()?;
+        let settings = TelemetrySettings::from_config(values);
+
+        // The listener must be ready before subscribing.
+        let listener = TelemetryListener::bind(
+            settings.port,
+            Arc::clone(&self.host_dispatcher),
+            Arc::clone(&self.actors),
+        )?;
+        let destination = format!("http://{}:{}", SANDBOX_HOST, listener.port()?);
+
+        self.client_factory
+            .new_telemetry_client(&endpoint)
+            .subscribe(&extension_id, &destination, &settings)?;
+
+        thread::spawn(move || {
+            info!("Starting telemetry listener on {}", destination);
+
+            if let Err(e) = listener.run() {
+                error!("Telemetry listener failed: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Returns an instance of the default telemetry capability provider.
+pub fn default_telemetry_provider() -> impl CapabilityProvider {
+    LambdaTelemetryProvider::new(
+        RuntimeTelemetryClientFactory,
+        env::var("AWS_LAMBDA_RUNTIME_API").ok(),
+    )
+}
+
+impl<CF: Any + Send + Sync + TelemetryClientFactory> CapabilityProvider
+    for LambdaTelemetryProvider<CF>
+{
+    /// Returns the capability ID in the formated `namespace:id`.
+    fn capability_id(&self) -> &'static str {
+        "awslambda:telemetry"
+    }
+
+    /// Called when the host runtime is ready and has configured a dispatcher.
+    /// The host does this when the capability is added, before any actor is bound
+    /// and so before any poller starts, which is when the telemetry extension is registered.
+    fn configure_dispatch(
+        &self,
+        dispatcher: Box<dyn wascc_codec::capabilities::Dispatcher>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        debug!("awslambda:telemetry configure_dispatch");
+
+        let mut lock = self.host_dispatcher.write().unwrap();
+        *lock = dispatcher;
+        drop(lock);
+
+        if let Err(e) = self.register() {
+            warn!("Unable to register telemetry extension: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Called by the host runtime when an actor is requesting a command be executed.
+    fn handle_call(
+        &self,
+        actor: &str,
+        op: &str,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.handle_call(actor, op, msg).map_err(|e| e.into())
+    }
+
+    /// Returns the human-readable, friendly name of this capability provider.
+    fn name(&self) -> &'static str {
+        "waSCC AWS Lambda telemetry provider"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::ExtensionEvent;
+    use crate::tests_common::*;
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+
+    const EXTENSION_ID: &str = "EXTENSION_ID";
+
+    /// Returns a batch of telemetry events as sent by the AWS Lambda Telemetry API.
+    fn telemetry_api_batch() -> serde_json::Value {
+        serde_json::json!([
+            {
+                "time": "2022-10-12T00:00:00.000Z",
+                "type": "platform.start",
+                "record": {
+                    "requestId": REQUEST_ID,
+                    "version": "$LATEST",
+                },
+            },
+            {
+                "time": "2022-10-12T00:00:00.001Z",
+                "type": "function",
+                "record": "Hello world",
+            },
+        ])
+    }
+
+    /// Represents a mock Extensions API client that records registration.
+    struct MockExtensionClient {
+        registered: Arc<RwLock<bool>>,
+    }
+
+    impl ExtensionClient for MockExtensionClient {
+        /// Registers an extension.
+        fn register(&self) -> anyhow::Result<String> {
+            let mut lock = self.registered.write().unwrap();
+            *lock = true;
+
+            Ok(EXTENSION_ID.into())
+        }
+
+        /// Waits for the next AWS Lambda extension event, which never comes.
+        fn next_event(&self, _extension_id: &str) -> anyhow::Result<ExtensionEvent> {
+            loop {
+                thread::park();
+            }
+        }
+    }
+
+    /// Represents a mock Telemetry API client that records the subscription destination.
+    struct MockTelemetryClient {
+        destination: Arc<RwLock<Option<String>>>,
+    }
+
+    impl TelemetryClient for MockTelemetryClient {
+        /// Subscribes to telemetry.
+        fn subscribe(
+            &self,
+            _extension_id: &str,
+            destination: &str,
+            _settings: &TelemetrySettings,
+        ) -> anyhow::Result<()> {
+            let mut lock = self.destination.write().unwrap();
+            *lock = Some(destination.into());
+
+            Ok(())
+        }
+    }
+
+    /// Creates mock clients.
+    struct MockTelemetryClientFactory {
+        destination: Arc<RwLock<Option<String>>>,
+        registered: Arc<RwLock<bool>>,
+    }
+
+    impl TelemetryClientFactory for MockTelemetryClientFactory {
+        /// Creates a new `MockExtensionClient`.
+        fn new_extension_client(
+            &self,
+            _endpoint: &str,
+        ) -> anyhow::Result<Box<dyn ExtensionClient + Send>> {
+            Ok(Box::new(MockExtensionClient {
+                registered: Arc::clone(&self.registered),
+            }))
+        }
+
+        /// Creates a new `MockTelemetryClient`.
+        fn new_telemetry_client(&self, _endpoint: &str) -> Box<dyn TelemetryClient> {
+            Box::new(MockTelemetryClient {
+                destination: Arc::clone(&self.destination),
+            })
+        }
+    }
+
+    /// Returns a serialized test capability configuration.
+    fn capability_configuration() -> Vec<u8> {
+        let mut values = HashMap::new();
+        values.insert("WASCC_TELEMETRY_PORT".into(), "0".into());
+        serialize(CapabilityConfiguration {
+            module: MODULE_ID.into(),
+            values,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn telemetry_settings_from_config() {
+        let mut values = HashMap::new();
+        values.insert("WASCC_TELEMETRY_PORT".into(), "8080".into());
+        values.insert("WASCC_TELEMETRY_TYPES".into(), "platform, function".into());
+        let settings = TelemetrySettings::from_config(&values);
+        assert_eq!(8080, settings.port);
+        assert_eq!(vec!["platform", "function"], settings.types);
+
+        let settings = TelemetrySettings::from_config(&HashMap::new());
+        assert_eq!(4243, settings.port);
+        assert_eq!(vec!["platform", "function", "extension"], settings.types);
+    }
+
+    #[test]
+    fn runtime_telemetry_client_subscribe() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("PUT"),
+                request::path(format!("/{}", RuntimeTelemetryClient::subscribe_path())),
+                request::headers(contains(entry("lambda-extension-identifier", EXTENSION_ID))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "schemaVersion": "2022-12-13",
+                    "types": ["platform", "function", "extension"],
+                    "buffering": {
+                        "maxItems": 1000,
+                        "maxBytes": 262144,
+                        "timeoutMs": 100,
+                    },
+                    "destination": {
+                        "protocol": "HTTP",
+                        "URI": "http://sandbox.localdomain:4243",
+                    },
+                })))),
+            ])
+            .respond_with(status_code(200)),
+        );
+
+        let client = RuntimeTelemetryClient::new(server.url_str("").trim_end_matches('/'));
+        let result = client.subscribe(
+            EXTENSION_ID,
+            "http://sandbox.localdomain:4243",
+            &TelemetrySettings::default(),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn runtime_telemetry_client_subscribe_error() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("PUT"),
+                request::path(format!("/{}", RuntimeTelemetryClient::subscribe_path())),
+            ])
+            .respond_with(status_code(400)),
+        );
+
+        let client = RuntimeTelemetryClient::new(server.url_str("").trim_end_matches('/'));
+        let result = client.subscribe(
+            EXTENSION_ID,
+            "http://sandbox.localdomain:4243",
+            &TelemetrySettings::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn runtime_telemetry_client_factory_register() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path("/2020-01-01/extension/register"),
+                request::headers(contains(entry("lambda-extension-name", EXTENSION_NAME))),
+                request::body(json_decoded(eq(serde_json::json!({
+                    "events": ["INVOKE"],
+                })))),
+            ])
+            .respond_with(
+                status_code(200).insert_header("Lambda-Extension-Identifier", EXTENSION_ID),
+            ),
+        );
+
+        let client = RuntimeTelemetryClientFactory
+            .new_extension_client(server.url_str("").trim_end_matches('/'))
+            .unwrap();
+        let result = client.register();
+        assert!(result.is_ok());
+        assert_eq!(EXTENSION_ID, result.unwrap());
+    }
+
+    #[test]
+    fn telemetry_provider_not_registered() {
+        let registered = Arc::new(RwLock::new(false));
+        let provider = LambdaTelemetryProvider::new(
+            MockTelemetryClientFactory {
+                destination: Arc::new(RwLock::new(None)),
+                registered: Arc::clone(&registered),
+            },
+            None,
+        );
+        let result = CapabilityProvider::configure_dispatch(
+            &provider,
+            Box::new(SharedWasccDispatcher(Arc::new(
+                MockWasccDispatcher::new(()),
+            ))),
+        );
+        assert!(result.is_ok());
+        assert!(!*registered.read().unwrap());
+
+        let result = provider.handle_call("system", OP_BIND_ACTOR, &capability_configuration());
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not registered"));
+    }
+
+    #[test]
+    fn telemetry_provider_unsupported_operation() {
+        let provider = default_telemetry_provider();
+        let result = provider.handle_call("system", "", &[]);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Unsupported operation"));
+    }
+
+    #[test]
+    fn telemetry_provider_dispatch_batch() {
+        let destination = Arc::new(RwLock::new(None));
+        let registered = Arc::new(RwLock::new(false));
+        let provider = LambdaTelemetryProvider::new(
+            MockTelemetryClientFactory {
+                destination: Arc::clone(&destination),
+                registered: Arc::clone(&registered),
+            },
+            Some("localhost:8080".into()),
+        );
+        let mock_dispatcher = Arc::new(MockWasccDispatcher::new(()));
+        let result = CapabilityProvider::configure_dispatch(
+            &provider,
+            Box::new(SharedWasccDispatcher(Arc::clone(&mock_dispatcher))),
+        );
+        assert!(result.is_ok());
+        // The extension is registered before any actor is bound.
+        assert!(*registered.read().unwrap());
+        assert!(destination.read().unwrap().is_none());
+
+        let result = provider.handle_call("system", OP_BIND_ACTOR, &capability_configuration());
+        assert!(result.is_ok());
+
+        // Send a batch to the listener in place of the Telemetry API.
+        let destination = destination.read().unwrap().clone().unwrap();
+        assert!(destination.starts_with("http://sandbox.localdomain:"));
+        let url = destination.replace(SANDBOX_HOST, "127.0.0.1");
+        let resp = reqwest::blocking::Client::new()
+            .post(&url)
+            .json(&telemetry_api_batch())
+            .send()
+            .unwrap();
+        assert!(resp.status().is_success());
+
+        assert_eq!(
+            Some(MODULE_ID.into()),
+            *mock_dispatcher.actor.read().unwrap()
+        );
+        assert_eq!(
+            Some(codec::OP_HANDLE_TELEMETRY.into()),
+            *mock_dispatcher.op.read().unwrap()
+        );
+        let msg = mock_dispatcher.msg.read().unwrap().clone().unwrap();
+        let batch: codec::TelemetryBatch = deserialize(&msg).unwrap();
+        assert_eq!(2, batch.events.len());
+        assert_eq!("platform.start", batch.events[0].event_type);
+        assert_eq!(
+            serde_json::json!({"requestId": REQUEST_ID, "version": "$LATEST"}),
+            serde_json::from_slice::<serde_json::Value>(&batch.events[0].record).unwrap()
+        );
+        assert_eq!("function", batch.events[1].event_type);
+        assert_eq!(b"\"Hello world\"".to_vec(), batch.events[1].record);
+
+        // Invalid batches are rejected.
+        let resp = reqwest::blocking::Client::new()
+            .post(&url)
+            .body("{}")
+            .send()
+            .unwrap();
+        assert_eq!(400, resp.status().as_u16());
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright 2015-2020 Capital One Services, LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//
+// waSCC AWS Lambda Runtime Providers
+//
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
+
+use crate::http::{body_string, query_string, request_headers};
+use crate::sigv4::{self, uri_encode, Credentials};
+
+/// The service name used to sign API Gateway management API requests.
+const MANAGEMENT_API_SERVICE: &str = "execute-api";
+
+/// Represents an API Gateway WebSocket API event.
+/// https://docs.aws.amazon.com/apigateway/latest/developerguide/apigateway-websocket-api-integration-requests.html
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebSocketRequest {
+    #[serde(default)]
+    headers: Option<HashMap<String, String>>,
+    #[serde(default)]
+    multi_value_headers: Option<HashMap<String, Vec<String>>>,
+    #[serde(default)]
+    query_string_parameters: Option<HashMap<String, String>>,
+    #[serde(default)]
+    multi_value_query_string_parameters: Option<HashMap<String, Vec<String>>>,
+    request_context: WebSocketRequestContext,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    is_base64_encoded: bool,
+}
+
+/// Represents the request context of an API Gateway WebSocket API event.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebSocketRequestContext {
+    #[serde(default)]
+    route_key: String,
+    #[serde(default)]
+    event_type: String,
+    connection_id: String,
+    #[serde(default)]
+    domain_name: String,
+    #[serde(default)]
+    stage: String,
+}
+
+impl WebSocketRequest {
+    /// Returns the route key, e.g. `$connect`.
+    pub fn route_key(&self) -> &str {
+        &self.request_context.route_key
+    }
+}
+
+impl TryFrom<WebSocketRequest> for codec::WebSocketEvent {
+    type Error = anyhow::Error;
+
+    /// Attempts conversion of an API Gateway WebSocket API event to an actor's WebSocket event.
+    fn try_from(request: WebSocketRequest) -> anyhow::Result<Self> {
+        Ok(codec::WebSocketEvent {
+            route_key: request.request_context.route_key,
+            event_type: request.request_context.event_type,
+            connection_id: request.request_context.connection_id,
+            domain_name: request.request_context.domain_name,
+            stage: request.request_context.stage,
+            header: request_headers(
+                request.headers.unwrap_or_default(),
+                request.multi_value_headers.unwrap_or_default(),
+            ),
+            query_string: query_string(
+                request.query_string_parameters.unwrap_or_default(),
+                request
+                    .multi_value_query_string_parameters
+                    .unwrap_or_default(),
+            ),
+            body: match request.body {
+                Some(s) if request.is_base64_encoded => base64::decode(s)?,
+                Some(s) => s.into_bytes(),
+                None => vec![],
+            },
+            context: codec::Context::default(),
+        })
+    }
+}
+
+/// Represents the response to an API Gateway WebSocket API event.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WebSocketApiResponse {
+    status_code: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    is_base64_encoded: bool,
+}
+
+impl From<codec::WebSocketResponse> for WebSocketApiResponse {
+    /// Converts an actor's WebSocket response to an API Gateway WebSocket API response.
+    fn from(response: codec::WebSocketResponse) -> Self {
+        let (body, is_base64_encoded) = body_string(response.body);
+
+        WebSocketApiResponse {
+            status_code: response.status_code,
+            body,
+            is_base64_encoded,
+        }
+    }
+}
+
+/// Posting to a WebSocket API client connection failed.
+#[derive(thiserror::Error, Debug)]
+#[error("Failed to post to connection {}: {}", connection_id, status)]
+pub(crate) struct PostToConnectionError {
+    connection_id: String,
+    status: reqwest::StatusCode,
+}
+
+/// Represents an API Gateway management API client.
+/// https://docs.aws.amazon.com/apigateway/latest/developerguide/apigateway-how-to-call-websocket-api-connections.html
+pub(crate) struct ManagementApiClient {
+    /// Overrides the `https://{domain name}/{stage}` endpoint of each API, e.g. for a custom domain name.
+    endpoint: Option<String>,
+    http_client: reqwest::blocking::Client,
+    region: String,
+}
+
+impl ManagementApiClient {
+    /// Creates a new `ManagementApiClient` in the specified region,
+    /// optionally overriding the management API endpoint.
+    pub fn new(endpoint: Option<&str>, region: &str) -> Self {
+        Self {
+            endpoint: endpoint.map(|ep| ep.trim_end_matches('/').into()),
+            http_client: reqwest::blocking::Client::new(),
+            region: region.into(),
+        }
+    }
+
+    /// Creates a new `ManagementApiClient` in the function's region.
+    pub fn from_env(endpoint: Option<&str>) -> Self {
+        Self::new(endpoint, &env::var("AWS_REGION").unwrap_or_default())
+    }
+
+    /// Sends a message to a client connection.
+    pub fn post_to_connection(
+        &self,
+        credentials: &Credentials,
+        request: codec::PostToConnectionRequest,
+    ) -> anyhow::Result<()> {
+        let endpoint = match &self.endpoint {
+            Some(ep) => ep.clone(),
+            None => format!("https://{}/{}", request.domain_name, request.stage),
+        };
+        let url = url::Url::parse(&format!(
+            "{}/@connections/{}",
+            endpoint,
+            uri_encode(&request.connection_id, true)
+        ))?;
+
+        let headers = sigv4::sign(
+            "POST",
+            &url,
+            &request.data,
+            credentials,
+            &self.region,
+            MANAGEMENT_API_SERVICE,
+            Utc::now(),
+        );
+        let mut builder = self.http_client.post(url);
+        for (name, value) in headers {
+            builder = builder.header(name.as_str(), value);
+        }
+        let response = builder.body(request.data).send()?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PostToConnectionError {
+                connection_id: request.connection_id,
+                status,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_common::*;
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use std::convert::TryInto;
+
+    const CONNECTION_ID: &str = "L0SM9cOFvHcCIhw=";
+
+    /// Returns a post to the test connection.
+    fn post_to_connection_request() -> codec::PostToConnectionRequest {
+        codec::PostToConnectionRequest {
+            domain_name: "abcdefg.execute-api.us-east-1.amazonaws.com".into(),
+            stage: "prod".into(),
+            connection_id: CONNECTION_ID.into(),
+            data: b"Hello world".to_vec(),
+        }
+    }
+
+    #[test]
+    fn websocket_request_message() {
+        let mut request = valid_websocket_request("sendmessage", "MESSAGE");
+        request["body"] = "{\"action\":\"sendmessage\"}".into();
+
+        let request: WebSocketRequest = serde_json::from_value(request).unwrap();
+        assert_eq!("sendmessage", request.route_key());
+
+        let result: codec::WebSocketEvent = request.try_into().unwrap();
+        assert_eq!("MESSAGE", result.event_type);
+        assert_eq!(CONNECTION_ID, result.connection_id);
+        assert_eq!("prod", result.stage);
+        assert_eq!(b"{\"action\":\"sendmessage\"}".to_vec(), result.body);
+    }
+
+    #[test]
+    fn websocket_request_connect() {
+        let mut request = valid_websocket_request("$connect", "CONNECT");
+        request["headers"] =
+            serde_json::json!({"Host": "abcdefg.execute-api.us-east-1.amazonaws.com"});
+        request["queryStringParameters"] = serde_json::json!({"token": "abc"});
+
+        let request: WebSocketRequest = serde_json::from_value(request).unwrap();
+        let result: codec::WebSocketEvent = request.try_into().unwrap();
+        assert_eq!("CONNECT", result.event_type);
+        assert_eq!("token=abc", result.query_string);
+        assert!(result.header.contains_key("Host"));
+        assert!(result.body.is_empty());
+    }
+
+    #[test]
+    fn management_api_client_post_to_connection_ok() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("POST"),
+                request::path("/prod/@connections/L0SM9cOFvHcCIhw%3D"),
+                request::headers(contains(entry("x-amz-security-token", "TOKEN"))),
+                request::body("Hello world"),
+            ])
+            .respond_with(status_code(200)),
+        );
+
+        let client = ManagementApiClient::new(Some(&server.url_str("/prod/")), "us-east-1");
+        let credentials = Credentials::new("AKID", "SECRET", Some("TOKEN"));
+        let result = client.post_to_connection(&credentials, post_to_connection_request());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn management_api_client_post_to_connection_gone() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![request::method("POST")]).respond_with(status_code(410)),
+        );
+
+        let client = ManagementApiClient::new(Some(&server.url_str("/prod")), "us-east-1");
+        let credentials = Credentials::new("AKID", "SECRET", None);
+        let result = client.post_to_connection(&credentials, post_to_connection_request());
+        assert!(result.is_err());
+
+        let e = result.err().unwrap();
+        assert!(e.is::<PostToConnectionError>());
+    }
+}
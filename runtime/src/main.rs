@@ -21,18 +21,76 @@ extern crate anyhow;
 
 use log::{debug, error, info, warn};
 use provider::{
-    default_http_request_provider, default_raw_event_provider, initerr_reporter,
-    InitializationErrorReporter,
+    default_http_request_provider, default_raw_event_provider, default_sqs_provider,
+    default_stream_provider, default_telemetry_provider, default_websocket_provider,
+    initerr_reporter, EventRouter, InitializationErrorReporter,
 };
+use serde::Deserialize;
 use wascc_codec::capabilities::CapabilityProvider;
 use wascc_host::{HostManifest, NativeCapability, WasccHost};
 use wascc_logging::LoggingProvider;
 
 use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 
+const BINARY_MEDIA_TYPES_KEY: &str = "WASCC_BINARY_MEDIA_TYPES";
+const EVENT_SOURCES_KEY: &str = "WASCC_EVENT_SOURCES";
 const MANIFEST_FILE: &str = "manifest.yaml";
 
+/// Represents the runtime's additions to the waSCC host manifest.
+#[derive(Debug, Default, Deserialize)]
+struct RuntimeManifest {
+    /// Routes from event sources to actors.
+    #[serde(default)]
+    routes: Vec<Route>,
+    /// Media types whose HTTP response bodies are base64 encoded, e.g. `image/*`.
+    #[serde(default, rename = "binaryMediaTypes")]
+    binary_media_types: Vec<String>,
+    /// Additional capability configuration values for each actor, keyed by the actor's public key.
+    #[serde(default, rename = "actorConfig")]
+    actor_config: HashMap<String, HashMap<String, String>>,
+}
+
+impl RuntimeManifest {
+    /// Returns the capability configuration values for an actor,
+    /// with any of the actor's additional values taking precedence.
+    fn actor_values(
+        &self,
+        actor: &str,
+        values: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut values = values.clone();
+        if let Some(actor_values) = self.actor_config.get(actor) {
+            values.extend(actor_values.clone());
+        }
+        values
+    }
+
+    /// Loads the runtime's additions from a waSCC host manifest file,
+    /// expanding environment variables in the same way as the host.
+    fn from_yaml(path: impl AsRef<Path>, expand_env: bool) -> anyhow::Result<Self> {
+        let mut contents = std::fs::read_to_string(path)?;
+        if expand_env {
+            let mut options = envmnt::ExpandOptions::new();
+            options.default_to_empty = false;
+            options.expansion_type = Some(envmnt::ExpansionType::UnixBracketsWithDefaults);
+            contents = envmnt::expand(&contents, Some(options));
+        }
+
+        Ok(serde_yaml::from_str(&contents)?)
+    }
+}
+
+/// Routes the events from an event source to an actor.
+#[derive(Debug, Deserialize)]
+struct Route {
+    /// The event source, e.g. `aws:sqs`, or `*` for any event.
+    source: String,
+    /// The actor's public key.
+    actor: String,
+}
+
 /// Entry point.
 fn main() -> anyhow::Result<()> {
     // No timestamp in the log format as CloudWatch already adds it.
@@ -49,7 +107,7 @@ fn main() -> anyhow::Result<()> {
         env!("CARGO_PKG_VERSION")
     );
 
-    let reporter = initerr_reporter(&format!("http://{}", env::var("AWS_LAMBDA_RUNTIME_API")?));
+    let reporter = initerr_reporter(&format!("http://{}", env::var("AWS_LAMBDA_RUNTIME_API")?))?;
 
     match load_and_run() {
         Ok(_) => {}
@@ -59,6 +117,7 @@ fn main() -> anyhow::Result<()> {
         }
     };
 
+    // The providers exit the process when the execution environment shuts down.
     debug!("Main thread park");
     std::thread::park();
 
@@ -71,24 +130,52 @@ fn main() -> anyhow::Result<()> {
 fn load_and_run() -> anyhow::Result<()> {
     let host = WasccHost::new();
 
-    let http_request_provider = default_http_request_provider();
-    let raw_event_provider = default_raw_event_provider();
+    // Load from well-known manifest file and expand any environment variables.
+    if let Some(cwd) = std::env::current_dir()?.to_str() {
+        info!("Loading {} from {}", MANIFEST_FILE, cwd);
+    }
+    let runtime_manifest = RuntimeManifest::from_yaml(MANIFEST_FILE, true)
+        .map_err(|e| anyhow!("Failed to load manifest routes: {}", e))?;
+
+    // The Lambda providers share a single poller that routes each invocation event to an actor.
+    let router = EventRouter::new();
+    let http_request_provider = default_http_request_provider(&router);
+    let raw_event_provider = default_raw_event_provider(&router);
+    let sqs_provider = default_sqs_provider(&router);
+    let stream_provider = default_stream_provider(&router);
+    let websocket_provider = default_websocket_provider(&router);
+    let telemetry_provider = default_telemetry_provider();
     let logging_provider = LoggingProvider::new();
 
-    let lambda_provider_config = lambda_provider_config();
+    let lambda_provider_config = lambda_provider_config(&runtime_manifest);
     let logging_provider_config = HashMap::new(); // No configuration.
 
     // All of these capabilities can be configured for any actor.
-    let any_capabilities: Vec<(String, &HashMap<String, String>)> = vec![(
-        logging_provider.capability_id().into(),
-        &logging_provider_config,
-    )];
+    let any_capabilities: Vec<(String, &HashMap<String, String>)> = vec![
+        (
+            logging_provider.capability_id().into(),
+            &logging_provider_config,
+        ),
+        (
+            telemetry_provider.capability_id().into(),
+            &lambda_provider_config,
+        ),
+    ];
     // Exactly one of these capabilities can be configured for a single actor.
     let exactly_one_capabilities: Vec<(String, &HashMap<String, String>)> = vec![
         (
             http_request_provider.capability_id().into(),
             &lambda_provider_config,
         ),
+        (sqs_provider.capability_id().into(), &lambda_provider_config),
+        (
+            stream_provider.capability_id().into(),
+            &lambda_provider_config,
+        ),
+        (
+            websocket_provider.capability_id().into(),
+            &lambda_provider_config,
+        ),
         (
             raw_event_provider.capability_id().into(),
             &lambda_provider_config,
@@ -97,20 +184,23 @@ fn load_and_run() -> anyhow::Result<()> {
 
     add_capability(&host, http_request_provider)?;
     add_capability(&host, raw_event_provider)?;
+    add_capability(&host, sqs_provider)?;
+    add_capability(&host, stream_provider)?;
+    add_capability(&host, websocket_provider)?;
+    add_capability(&host, telemetry_provider)?;
     add_capability(&host, logging_provider)?;
 
-    // Load from well-known manifest file and expand any environment variables.
-    if let Some(cwd) = std::env::current_dir()?.to_str() {
-        info!("Loading {} from {}", MANIFEST_FILE, cwd);
-    }
     let manifest = HostManifest::from_yaml(MANIFEST_FILE, true)
         .map_err(|e| anyhow!("Failed to load manifest file: {}", e))?;
     host.apply_manifest(manifest)
         .map_err(|e| anyhow!("Failed to apply manifest: {}", e))?;
 
-    autoconfigure_actors(&host, any_capabilities, exactly_one_capabilities);
-
-    Ok(())
+    autoconfigure_actors(
+        &host,
+        any_capabilities,
+        exactly_one_capabilities,
+        &runtime_manifest,
+    )
 }
 
 /// Adds a built-in capability provider.
@@ -127,25 +217,79 @@ fn add_capability(host: &WasccHost, instance: impl CapabilityProvider) -> anyhow
 /// Autoconfigures actors.
 /// For every actor loaded into the host
 /// - Attempt to configure with each of the `any` capabilities
-/// - Attempt to configure one actor with one of the `exactly_one` capabilities
+/// - If there are no routes, attempt to configure one actor with one of the `exactly_one` capabilities
+/// - Otherwise configure every routed actor with one of the `exactly_one` capabilities
 fn autoconfigure_actors(
     host: &WasccHost,
     any: Vec<(String, &HashMap<String, String>)>,
     exactly_one: Vec<(String, &HashMap<String, String>)>,
-) {
+    manifest: &RuntimeManifest,
+) -> anyhow::Result<()> {
     for actor in host.actors() {
         for capability in &any {
-            configure_actor(host, &actor.0, &capability.0, capability.1);
+            configure_actor(
+                host,
+                &actor.0,
+                &capability.0,
+                &manifest.actor_values(&actor.0, capability.1),
+            );
         }
     }
 
+    if !manifest.routes.is_empty() {
+        return autoconfigure_routed_actors(host, exactly_one, manifest);
+    }
+
     for actor in host.actors() {
         for capability in &exactly_one {
-            if configure_actor(host, &actor.0, &capability.0, capability.1) {
-                return;
+            if configure_actor(
+                host,
+                &actor.0,
+                &capability.0,
+                &manifest.actor_values(&actor.0, capability.1),
+            ) {
+                return Ok(());
             }
         }
     }
+
+    Ok(())
+}
+
+/// Autoconfigures routed actors.
+/// Each routed actor is configured with the first of the `exactly_one` capabilities it can be.
+/// The providers share a single poller, so routed actors can be bound to different capabilities,
+/// e.g. SQS messages to an `awslambda:sqs` actor and API Gateway requests to a `wascc:http_server` actor.
+fn autoconfigure_routed_actors(
+    host: &WasccHost,
+    exactly_one: Vec<(String, &HashMap<String, String>)>,
+    manifest: &RuntimeManifest,
+) -> anyhow::Result<()> {
+    // Group the event sources by actor, in manifest order.
+    let mut actors: Vec<(&str, Vec<&str>)> = vec![];
+    for route in &manifest.routes {
+        match actors.iter_mut().find(|(actor, _)| *actor == route.actor) {
+            Some((_, sources)) => sources.push(&route.source),
+            None => actors.push((route.actor.as_str(), vec![route.source.as_str()])),
+        }
+    }
+
+    for (actor, sources) in actors {
+        let with_sources = |config: &HashMap<String, String>| {
+            let mut config = manifest.actor_values(actor, config);
+            config.insert(EVENT_SOURCES_KEY.into(), sources.join(","));
+            config
+        };
+
+        if !exactly_one
+            .iter()
+            .any(|c| configure_actor(host, actor, &c.0, &with_sources(c.1)))
+        {
+            return Err(anyhow!("Unable to route events to actor {}", actor));
+        }
+    }
+
+    Ok(())
 }
 
 /// Configures an actor with a capability.
@@ -175,10 +319,11 @@ fn configure_actor(
 }
 
 /// Returns the configuration for any Lambda capability provider.
-fn lambda_provider_config() -> HashMap<String, String> {
+fn lambda_provider_config(manifest: &RuntimeManifest) -> HashMap<String, String> {
     let mut config = HashMap::new();
     // https://docs.aws.amazon.com/lambda/latest/dg/configuration-envvars.html#configuration-envvars-runtime
     let keys = vec![
+        "AWS_LAMBDA_FUNCTION_MEMORY_SIZE",
         "AWS_LAMBDA_FUNCTION_NAME",
         "AWS_LAMBDA_FUNCTION_VERSION",
         "AWS_LAMBDA_LOG_GROUP_NAME",
@@ -194,6 +339,35 @@ fn lambda_provider_config() -> HashMap<String, String> {
             warn!("Environment variable {} not set", key);
         }
     }
+    // Optional runtime settings.
+    let optional_keys = vec![
+        "WASCC_RUNTIME_API_BACKOFF_MS",
+        "WASCC_RUNTIME_API_MAX_ATTEMPTS",
+        "WASCC_RUNTIME_API_MAX_BACKOFF_MS",
+        "WASCC_RUNTIME_API_POST_TIMEOUT_MS",
+        "WASCC_TELEMETRY_PORT",
+        "WASCC_TELEMETRY_TYPES",
+        "WASCC_AUTHORIZER_SIMPLE_RESPONSES",
+        "WASCC_HTTP_BASE_PATH",
+        "WASCC_HTTP_COMPRESSION",
+        "WASCC_HTTP_COMPRESSION_THRESHOLD",
+        "WASCC_HTTP_PROXY_PATH",
+        "WASCC_HTTP_STRIP_STAGE",
+        "WASCC_WEBSOCKET_API_ENDPOINT",
+        BINARY_MEDIA_TYPES_KEY,
+    ];
+    for key in optional_keys {
+        if let Ok(value) = env::var(key) {
+            config.insert(key.into(), value);
+        }
+    }
+    // Binary media types in the manifest are added to any in the environment.
+    if !manifest.binary_media_types.is_empty() {
+        let mut binary_media_types: Vec<String> =
+            config.remove(BINARY_MEDIA_TYPES_KEY).into_iter().collect();
+        binary_media_types.extend(manifest.binary_media_types.iter().cloned());
+        config.insert(BINARY_MEDIA_TYPES_KEY.into(), binary_media_types.join(","));
+    }
 
     config
 }